@@ -0,0 +1,115 @@
+use std::{path::Path, process::ExitCode};
+
+use haxbevy::{
+    bot::BotRegistry,
+    game::TICKS_PER_SECOND,
+    headless::HeadlessMatch,
+    lobby::{Lobby, LobbySlot},
+    parser::{stadium::parse_stadium_file, utils::Team},
+};
+
+const USAGE: &str =
+    "usage: haxbevy-headless <stadium> [--red BOT]... [--blue BOT]... [--ticks N] [--seed N] [--stats] [--record FILE]
+plays a match between bots without a window and prints the score
+--stats also prints the match statistics as JSON
+--record saves the positions during the match for haxbevy-render";
+
+// 10 minutes of game time
+const DEFAULT_MAX_TICKS: u32 = 10 * 60 * TICKS_PER_SECOND;
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    let [input, options @ ..] = args.as_slice() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let bots = BotRegistry::default();
+    let mut lobby = Lobby { slots: vec![] };
+    let mut max_ticks = DEFAULT_MAX_TICKS;
+    let mut seed = 0;
+    let mut print_stats = false;
+    let mut record = None;
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        if option == "--stats" {
+            print_stats = true;
+            continue;
+        }
+        let value = options.next().map(String::as_str).unwrap_or_default();
+        match option.as_str() {
+            "--red" | "--blue" => {
+                if bots.create(value).is_none() {
+                    let names = bots.bots.iter().map(|bot| bot.name).collect::<Vec<_>>();
+                    eprintln!("unknown bot '{}', available: {}", value, names.join(", "));
+                    return ExitCode::from(2);
+                }
+                let team = if option == "--red" {
+                    Team::Red
+                } else {
+                    Team::Blue
+                };
+                lobby.slots.push(LobbySlot::bot(value, team));
+            }
+            "--ticks" => match value.parse() {
+                Ok(ticks) => max_ticks = ticks,
+                Err(_) => {
+                    eprintln!("invalid tick count '{}'", value);
+                    return ExitCode::from(2);
+                }
+            },
+            "--record" => record = Some(value.to_string()),
+            "--seed" => match value.parse() {
+                Ok(value) => seed = value,
+                Err(_) => {
+                    eprintln!("invalid seed '{}'", value);
+                    return ExitCode::from(2);
+                }
+            },
+            _ => {
+                eprintln!("{}", USAGE);
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    let stadium = match std::fs::read(input)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| parse_stadium_file(Path::new(input), &bytes).map_err(|e| e.to_string()))
+    {
+        Ok((stadium, _)) => stadium,
+        Err(e) => {
+            eprintln!("{}: {}", input, e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut game = HeadlessMatch::new(stadium, lobby, bots, seed);
+    let ticks = game.run_until_end(max_ticks);
+    let state = game.match_state();
+    println!(
+        "red {} - {} blue after {} ticks ({}s of play)",
+        state.red_score,
+        state.blue_score,
+        ticks,
+        state.seconds()
+    );
+    if print_stats {
+        let stats = serde_json::to_string_pretty(&game.stats().to_json())
+            .expect("stats can always be serialized");
+        println!("{}", stats);
+    }
+    if let Some(path) = record {
+        let recording =
+            serde_json::to_vec(game.recording()).expect("recordings can always be serialized");
+        if let Err(e) = std::fs::write(&path, recording) {
+            eprintln!("{}: {}", path, e);
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,59 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use haxbevy::lint::{lint_file, Severity};
+
+const USAGE: &str = "usage: haxbevy-lint [--json] <stadium file>...";
+
+fn main() -> ExitCode {
+    let mut json = false;
+    let mut files = vec![];
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+    if files.is_empty() {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    }
+
+    let reports = files.iter().map(|f| lint_file(f)).collect::<Vec<_>>();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reports).unwrap());
+    } else {
+        for report in &reports {
+            for issue in &report.issues {
+                let severity = match issue.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                };
+                if issue.path.is_empty() {
+                    println!("{}: {}: {}", report.file, severity, issue.message);
+                } else {
+                    println!(
+                        "{}: {}: {}: {}",
+                        report.file, severity, issue.path, issue.message
+                    );
+                }
+            }
+            println!(
+                "{}: {} errors, {} warnings",
+                report.file,
+                report.count(Severity::Error),
+                report.count(Severity::Warning)
+            );
+        }
+    }
+
+    if reports.iter().any(|report| report.has_errors()) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
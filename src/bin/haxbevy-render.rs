@@ -0,0 +1,118 @@
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use bevy::prelude::Color;
+use haxbevy::{
+    parser::stadium::parse_stadium_file,
+    preview::{team_color, Scene},
+    recording::MatchRecording,
+};
+
+const USAGE: &str = "usage: haxbevy-render [--scale <pixels per unit>] [--recording <recording.json> [--heatmap <player id>]... [--ball]] <stadium file> <output.svg|output.png>
+with a recording, draws the heatmaps of the given players and the path of the ball, only the ball by default";
+
+const HEATMAP_CELL_SIZE: f64 = 20.0;
+const BALL_PATH_COLOR: Color = Color::WHITE;
+
+#[derive(Default)]
+struct Overlays {
+    recording: Option<PathBuf>,
+    heatmaps: Vec<usize>,
+    ball: bool,
+}
+
+fn add_overlays(scene: &mut Scene, overlays: &Overlays) -> Result<(), String> {
+    let Some(path) = &overlays.recording else {
+        return Ok(());
+    };
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let recording: MatchRecording =
+        serde_json::from_slice(&bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    for id in &overlays.heatmaps {
+        let player = recording
+            .player(*id)
+            .ok_or_else(|| format!("no player {} in {}", id, path.display()))?;
+        scene.add_heatmap(
+            &player.positions,
+            HEATMAP_CELL_SIZE,
+            team_color(player.team),
+        );
+    }
+    if overlays.ball || overlays.heatmaps.is_empty() {
+        for path in &recording.ball {
+            scene.add_trajectory(path, BALL_PATH_COLOR);
+        }
+    }
+    Ok(())
+}
+
+fn render(input: &Path, output: &Path, scale: f64, overlays: &Overlays) -> Result<(), String> {
+    let bytes = std::fs::read(input).map_err(|e| e.to_string())?;
+    let (stadium, _) = parse_stadium_file(input, &bytes).map_err(|e| e.to_string())?;
+    let mut scene = Scene::from_stadium(&stadium);
+    add_overlays(&mut scene, overlays)?;
+
+    let data = match output.extension().and_then(|e| e.to_str()) {
+        Some("svg") => scene.to_svg().into_bytes(),
+        Some("png") => scene.to_png(scale)?,
+        _ => return Err("the output must be a .svg or .png file".to_string()),
+    };
+    std::fs::write(output, data).map_err(|e| e.to_string())
+}
+
+fn main() -> ExitCode {
+    let mut scale = 1.0;
+    let mut overlays = Overlays::default();
+    let mut files = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--scale" => match args.next().and_then(|s| s.parse().ok()) {
+                Some(s) if s > 0.0 => scale = s,
+                _ => {
+                    eprintln!("{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            "--recording" => match args.next() {
+                Some(path) => overlays.recording = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            "--heatmap" => match args.next().and_then(|s| s.parse().ok()) {
+                Some(id) => overlays.heatmaps.push(id),
+                None => {
+                    eprintln!("{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            "--ball" => overlays.ball = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+    if overlays.recording.is_none() && (overlays.ball || !overlays.heatmaps.is_empty()) {
+        eprintln!("--heatmap and --ball need a --recording");
+        return ExitCode::from(2);
+    }
+    let [input, output] = files.as_slice() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    match render(input, output, scale, &overlays) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}: {}", input.display(), e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::{path::Path, process::ExitCode};
+
+use bevy::math::DVec2;
+use haxbevy::parser::{
+    stadium::{parse_stadium_raw_file, stadium_file_bytes},
+    transform::MirrorAxis,
+};
+
+const USAGE: &str = "usage: haxbevy-transform <stadium> <out.hbs|out.hbsb> [operation]...
+operations, applied in order:
+  --scale S | --scale SX,SY
+  --translate DX,DY
+  --mirror horizontal|vertical
+  --rotate cw|ccw";
+
+// a number or a pair of numbers separated by a comma
+fn parse_vector(s: &str) -> Option<DVec2> {
+    match s.split_once(',') {
+        Some((x, y)) => Some(DVec2::new(x.trim().parse().ok()?, y.trim().parse().ok()?)),
+        None => s.trim().parse().ok().map(DVec2::splat),
+    }
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    let [input, output, operations @ ..] = args.as_slice() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let mut raw = match std::fs::read(input)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| {
+            parse_stadium_raw_file(Path::new(input), &bytes).map_err(|e| e.to_string())
+        }) {
+        Ok(raw) => raw,
+        Err(e) => {
+            eprintln!("{}: {}", input, e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut operations = operations.iter();
+    while let Some(operation) = operations.next() {
+        let value = operations.next().map(String::as_str).unwrap_or_default();
+        match operation.as_str() {
+            "--scale" => match parse_vector(value) {
+                Some(factor) if factor.x > 0.0 && factor.y > 0.0 => raw.scale(factor),
+                _ => {
+                    eprintln!("invalid scale '{}', use --mirror to flip", value);
+                    return ExitCode::from(2);
+                }
+            },
+            "--translate" => match parse_vector(value) {
+                Some(offset) => raw.translate(offset),
+                None => {
+                    eprintln!("invalid offset '{}'", value);
+                    return ExitCode::from(2);
+                }
+            },
+            "--mirror" => match value {
+                "horizontal" | "h" => raw.mirror(MirrorAxis::Horizontal),
+                "vertical" | "v" => raw.mirror(MirrorAxis::Vertical),
+                _ => {
+                    eprintln!("invalid mirror axis '{}'", value);
+                    return ExitCode::from(2);
+                }
+            },
+            "--rotate" => match value {
+                "cw" => raw.rotate_90(true),
+                "ccw" => raw.rotate_90(false),
+                _ => {
+                    eprintln!("invalid rotation '{}'", value);
+                    return ExitCode::from(2);
+                }
+            },
+            _ => {
+                eprintln!("{}", USAGE);
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    if let Err(e) = std::fs::write(output, stadium_file_bytes(Path::new(output), &raw)) {
+        eprintln!("{}: {}", output, e);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
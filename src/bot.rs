@@ -0,0 +1,287 @@
+use std::sync::Arc;
+
+use bevy::{ecs::system::SystemParam, math::DVec2, prelude::*};
+
+use crate::{
+    builtin_bots::register_builtin_bots,
+    game::{MatchPhase, MatchState},
+    parser::{
+        ball_physics::BallComp,
+        disc::{Radius, Velocity},
+        goal::GoalComp,
+        plane::PlaneComp,
+        stadium::StadiumComp,
+        utils::{Position, Team},
+    },
+    physics::PhysicsSet,
+    player::{KickState, Player, PlayerInput},
+    AppState, PauseState,
+};
+
+pub struct BotPlugin;
+
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BotRegistry>()
+            .init_resource::<SnapshotGeometry>()
+            // with the players, which are spawned in Update
+            .add_systems(Update, update_geometry.run_if(in_state(AppState::InGame)))
+            .add_systems(
+                FixedUpdate,
+                run_bots
+                    .before(PhysicsSet)
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(in_state(PauseState::Running)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiscState {
+    pub position: DVec2,
+    pub velocity: DVec2,
+    pub radius: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PlayerState {
+    pub id: usize,
+    pub team: Team,
+    pub disc: DiscState,
+    // the kick button is held and the player can still kick
+    pub kicking: bool,
+}
+
+// the parts of the stadium that never move
+#[derive(Debug, Clone, Default)]
+pub struct StadiumGeometry {
+    pub width: f64,
+    pub height: f64,
+    pub spawn_distance: f64,
+    pub goals: Vec<GoalComp>,
+    pub planes: Vec<PlaneComp>,
+}
+
+impl StadiumGeometry {
+    // the goal defended by the team
+    pub fn own_goal(&self, team: Team) -> Option<&GoalComp> {
+        self.goals.iter().find(|goal| goal.team == team)
+    }
+
+    pub fn opponent_goal(&self, team: Team) -> Option<&GoalComp> {
+        self.own_goal(team.opponent())
+    }
+
+    // the center of the goal the team attacks, the far side of the stadium without goals
+    pub fn attack_point(&self, team: Team) -> DVec2 {
+        match self.opponent_goal(team) {
+            Some(goal) => (goal.p0 + goal.p1) / 2.0,
+            None => DVec2::new(attack_side(team) * self.width, 0.0),
+        }
+    }
+
+    pub fn defend_point(&self, team: Team) -> DVec2 {
+        match self.own_goal(team) {
+            Some(goal) => (goal.p0 + goal.p1) / 2.0,
+            None => DVec2::new(-attack_side(team) * self.width, 0.0),
+        }
+    }
+}
+
+// red spawns on the left and attacks to the right
+fn attack_side(team: Team) -> f64 {
+    match team {
+        Team::Blue => -1.0,
+        _ => 1.0,
+    }
+}
+
+// what bots see at each fixed tick
+#[derive(Debug, Clone)]
+pub struct GameSnapshot {
+    pub ticks: u32,
+    // in minutes, 0 means no limit
+    pub time_limit: u32,
+    pub phase: MatchPhase,
+    pub red_score: u32,
+    pub blue_score: u32,
+    pub ball: DiscState,
+    // sorted by id
+    pub players: Vec<PlayerState>,
+    pub stadium: Arc<StadiumGeometry>,
+}
+
+impl GameSnapshot {
+    pub fn player(&self, id: usize) -> Option<&PlayerState> {
+        self.players.iter().find(|player| player.id == id)
+    }
+
+    pub fn teammates(&self, id: usize) -> impl Iterator<Item = &PlayerState> {
+        let team = self.player(id).map(|player| player.team);
+        self.players
+            .iter()
+            .filter(move |player| player.id != id && Some(player.team) == team)
+    }
+
+    pub fn opponents(&self, id: usize) -> impl Iterator<Item = &PlayerState> {
+        let team = self.player(id).map(|player| player.team.opponent());
+        self.players
+            .iter()
+            .filter(move |player| Some(player.team) == team)
+    }
+}
+
+// an AI player, it sends inputs through the same PlayerInput as the keyboard
+pub trait Bot: Send + Sync {
+    // called at every fixed tick of the match, `me` is the id of the controlled player
+    fn think(&mut self, snapshot: &GameSnapshot, me: usize) -> PlayerInput;
+
+    // called once before the match with a seed drawn from the MatchRng,
+    // bots must take all their random decisions from it
+    fn seed(&mut self, _seed: u64) {}
+}
+
+#[derive(Component)]
+pub struct BotController(pub Box<dyn Bot>);
+
+#[derive(Clone)]
+pub struct BotFactory {
+    pub name: &'static str,
+    pub create: fn() -> Box<dyn Bot>,
+}
+
+// the bots that can be attached to a lobby slot
+#[derive(Resource, Clone)]
+pub struct BotRegistry {
+    pub bots: Vec<BotFactory>,
+}
+
+impl Default for BotRegistry {
+    fn default() -> Self {
+        let mut registry = BotRegistry { bots: vec![] };
+        registry.register("Idle", || Box::new(IdleBot));
+        register_builtin_bots(&mut registry);
+        registry
+    }
+}
+
+impl BotRegistry {
+    // a bot registered twice replaces the previous one
+    pub fn register(&mut self, name: &'static str, create: fn() -> Box<dyn Bot>) {
+        self.bots.retain(|bot| bot.name != name);
+        self.bots.push(BotFactory { name, create });
+    }
+
+    pub fn create(&self, name: &str) -> Option<Box<dyn Bot>> {
+        self.bots
+            .iter()
+            .find(|bot| bot.name == name)
+            .map(|bot| (bot.create)())
+    }
+}
+
+// stands still, a target for practice
+pub struct IdleBot;
+
+impl Bot for IdleBot {
+    fn think(&mut self, _snapshot: &GameSnapshot, _me: usize) -> PlayerInput {
+        PlayerInput::default()
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct SnapshotGeometry(pub Arc<StadiumGeometry>);
+
+fn update_geometry(
+    mut geometry: ResMut<SnapshotGeometry>,
+    stadiums: Query<&StadiumComp, Added<StadiumComp>>,
+    goals: Query<&GoalComp>,
+    planes: Query<&PlaneComp>,
+) {
+    // the stadium is spawned again on hot reload
+    let Ok(stadium) = stadiums.get_single() else {
+        return;
+    };
+    geometry.0 = Arc::new(StadiumGeometry {
+        width: stadium.width,
+        height: stadium.height,
+        spawn_distance: stadium.spawn_distance,
+        goals: goals.iter().copied().collect(),
+        planes: planes.iter().copied().collect(),
+    });
+}
+
+// everything needed to build a GameSnapshot
+#[derive(SystemParam)]
+pub struct SnapshotQuery<'w, 's> {
+    match_state: Option<Res<'w, MatchState>>,
+    geometry: Res<'w, SnapshotGeometry>,
+    balls: Query<'w, 's, (&'static Position, &'static Velocity, &'static Radius), With<BallComp>>,
+    players: Query<
+        'w,
+        's,
+        (
+            &'static Player,
+            &'static Position,
+            &'static Velocity,
+            &'static Radius,
+            &'static KickState,
+        ),
+    >,
+}
+
+impl SnapshotQuery<'_, '_> {
+    pub fn snapshot(&self) -> GameSnapshot {
+        let match_state = self.match_state.as_deref().cloned().unwrap_or_default();
+        let ball = self
+            .balls
+            .get_single()
+            .map(|(position, velocity, radius)| DiscState {
+                position: position.0,
+                velocity: velocity.0,
+                radius: radius.0,
+            })
+            .unwrap_or_default();
+        let mut players = self
+            .players
+            .iter()
+            .map(
+                |(player, position, velocity, radius, kick_state)| PlayerState {
+                    id: player.id,
+                    team: player.team,
+                    disc: DiscState {
+                        position: position.0,
+                        velocity: velocity.0,
+                        radius: radius.0,
+                    },
+                    kicking: kick_state.kicking,
+                },
+            )
+            .collect::<Vec<_>>();
+        players.sort_by_key(|player| player.id);
+
+        GameSnapshot {
+            ticks: match_state.ticks,
+            time_limit: match_state.time_limit,
+            phase: match_state.phase,
+            red_score: match_state.red_score,
+            blue_score: match_state.blue_score,
+            ball,
+            players,
+            stadium: self.geometry.0.clone(),
+        }
+    }
+}
+
+fn run_bots(
+    snapshot: SnapshotQuery,
+    mut bots: Query<(&Player, &mut BotController, &mut PlayerInput)>,
+) {
+    if bots.is_empty() {
+        return;
+    }
+    let snapshot = snapshot.snapshot();
+    for (player, mut bot, mut input) in bots.iter_mut() {
+        *input = bot.0.think(&snapshot, player.id);
+    }
+}
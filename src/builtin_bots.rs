@@ -0,0 +1,316 @@
+use std::collections::VecDeque;
+
+use bevy::math::DVec2;
+
+use crate::{
+    bot::{Bot, BotRegistry, DiscState, GameSnapshot, PlayerState},
+    physics::KICK_MARGIN,
+    player::PlayerInput,
+    rng::Rng,
+};
+
+// how far from its goal line the goalkeeper waits
+const GOALKEEPER_DISTANCE: f64 = 40.0;
+// the goalkeeper leaves its line when the ball gets that close
+const GOALKEEPER_CLEAR_DISTANCE: f64 = 150.0;
+// close enough to a waypoint to stop running
+const ARRIVED_DISTANCE: f64 = 4.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    // ticks between the ball moving and the bot noticing it
+    pub fn reaction_delay(&self) -> usize {
+        match self {
+            Difficulty::Easy => 20,
+            Difficulty::Normal => 8,
+            Difficulty::Hard => 1,
+        }
+    }
+
+    // largest error on the direction of a kick, in radians
+    pub fn aim_noise(&self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.4,
+            Difficulty::Normal => 0.15,
+            Difficulty::Hard => 0.02,
+        }
+    }
+}
+
+// what every built-in bot shares: a delayed view of the ball, a noisy aim and the kick button
+struct BotBrain {
+    difficulty: Difficulty,
+    rng: Rng,
+    balls: VecDeque<DiscState>,
+    // rolled again after every kick
+    aim_error: f64,
+    kick_pressed: bool,
+}
+
+impl BotBrain {
+    fn new(difficulty: Difficulty) -> BotBrain {
+        BotBrain {
+            difficulty,
+            rng: Rng::new(0),
+            balls: VecDeque::new(),
+            aim_error: 0.0,
+            kick_pressed: false,
+        }
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+        self.aim_error = self.rng.spread(self.difficulty.aim_noise());
+    }
+
+    // the ball as it was `reaction_delay` ticks ago
+    fn see_ball(&mut self, snapshot: &GameSnapshot) -> DiscState {
+        self.balls.push_back(snapshot.ball);
+        while self.balls.len() > self.difficulty.reaction_delay() + 1 {
+            self.balls.pop_front();
+        }
+        self.balls[0]
+    }
+
+    fn move_to(&self, me: &PlayerState, point: DVec2) -> PlayerInput {
+        let offset = point - me.disc.position;
+        PlayerInput {
+            direction: if offset.length() < ARRIVED_DISTANCE {
+                DVec2::ZERO
+            } else {
+                offset
+            },
+            kick: false,
+        }
+    }
+
+    // run behind the ball and kick it toward the target
+    fn kick_to(&mut self, me: &PlayerState, ball: DiscState, target: DVec2) -> PlayerInput {
+        let aim = DVec2::from_angle(self.aim_error)
+            .rotate(target - ball.position)
+            .normalize_or_zero();
+        let contact = me.disc.radius + ball.radius;
+        let to_ball = ball.position - me.disc.position;
+
+        // lined up behind the ball, the kick pushes the ball away from the player
+        // so aim at the point of the ball opposite to the target
+        if to_ball.normalize_or_zero().dot(aim) > 0.9 {
+            let in_range = to_ball.length() <= contact + KICK_MARGIN;
+            // the button must be released between two kicks
+            let kick = in_range && !self.kick_pressed;
+            self.kick_pressed = kick;
+            if kick {
+                self.aim_error = self.rng.spread(self.difficulty.aim_noise());
+            }
+            return PlayerInput {
+                direction: ball.position - aim * ball.radius - me.disc.position,
+                kick,
+            };
+        }
+
+        self.kick_pressed = false;
+        let behind = ball.position - aim * (contact + ball.radius);
+        // go around the ball instead of pushing it the wrong way
+        if to_ball.length() < contact + ball.radius * 2.0 {
+            let side = aim.perp() * aim.perp().dot(-to_ball).signum();
+            return PlayerInput {
+                direction: side - aim,
+                kick: false,
+            };
+        }
+        self.move_to(me, behind)
+    }
+}
+
+// runs to the ball and kicks it toward the opponent goal
+pub struct ChaserBot {
+    brain: BotBrain,
+}
+
+impl ChaserBot {
+    pub fn new(difficulty: Difficulty) -> ChaserBot {
+        ChaserBot {
+            brain: BotBrain::new(difficulty),
+        }
+    }
+}
+
+impl Bot for ChaserBot {
+    fn think(&mut self, snapshot: &GameSnapshot, me: usize) -> PlayerInput {
+        let ball = self.brain.see_ball(snapshot);
+        let Some(player) = snapshot.player(me) else {
+            return PlayerInput::default();
+        };
+        let target = snapshot.stadium.attack_point(player.team);
+        self.brain.kick_to(player, ball, target)
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.brain.seed(seed);
+    }
+}
+
+// stays between the ball and its goal, and clears the ball when it comes close
+pub struct GoalkeeperBot {
+    brain: BotBrain,
+}
+
+impl GoalkeeperBot {
+    pub fn new(difficulty: Difficulty) -> GoalkeeperBot {
+        GoalkeeperBot {
+            brain: BotBrain::new(difficulty),
+        }
+    }
+}
+
+impl Bot for GoalkeeperBot {
+    fn think(&mut self, snapshot: &GameSnapshot, me: usize) -> PlayerInput {
+        let ball = self.brain.see_ball(snapshot);
+        let Some(player) = snapshot.player(me) else {
+            return PlayerInput::default();
+        };
+        let goal = snapshot.stadium.defend_point(player.team);
+        let to_ball = ball.position - goal;
+
+        if to_ball.length() < GOALKEEPER_CLEAR_DISTANCE {
+            let target = snapshot.stadium.attack_point(player.team);
+            return self.brain.kick_to(player, ball, target);
+        }
+        // on the line from the goal to the ball, never further than the ball
+        let distance = GOALKEEPER_DISTANCE.min(to_ball.length());
+        self.brain
+            .move_to(player, goal + to_ball.normalize_or_zero() * distance)
+    }
+    fn seed(&mut self, seed: u64) {
+        self.brain.seed(seed);
+    }
+}
+
+// passes to the teammate closest to the opponent goal, or offers itself for a pass
+pub struct PasserBot {
+    brain: BotBrain,
+}
+
+impl PasserBot {
+    pub fn new(difficulty: Difficulty) -> PasserBot {
+        PasserBot {
+            brain: BotBrain::new(difficulty),
+        }
+    }
+}
+
+impl Bot for PasserBot {
+    fn think(&mut self, snapshot: &GameSnapshot, me: usize) -> PlayerInput {
+        let ball = self.brain.see_ball(snapshot);
+        let Some(player) = snapshot.player(me) else {
+            return PlayerInput::default();
+        };
+        let attack = snapshot.stadium.attack_point(player.team);
+        let ball_distance = |p: &PlayerState| p.disc.position.distance(ball.position);
+
+        // the teammate closest to the ball goes for it
+        let closest = snapshot
+            .teammates(me)
+            .all(|teammate| ball_distance(player) <= ball_distance(teammate));
+        if closest {
+            let receiver = snapshot.teammates(me).min_by(|a, b| {
+                let a = a.disc.position.distance(attack);
+                let b = b.disc.position.distance(attack);
+                a.total_cmp(&b)
+            });
+            let target = match receiver {
+                // lead the pass, the receiver keeps running
+                Some(receiver)
+                    if receiver.disc.position.distance(attack)
+                        < player.disc.position.distance(attack) =>
+                {
+                    receiver.disc.position + receiver.disc.velocity * 10.0
+                }
+                _ => attack,
+            };
+            return self.brain.kick_to(player, ball, target);
+        }
+
+        // halfway to the opponent goal, on the other side of the ball
+        let support = DVec2::new((ball.position.x + attack.x) / 2.0, -ball.position.y / 2.0);
+        self.brain.move_to(player, support)
+    }
+    fn seed(&mut self, seed: u64) {
+        self.brain.seed(seed);
+    }
+}
+
+pub fn register_builtin_bots(registry: &mut BotRegistry) {
+    registry.register("Chaser (easy)", || {
+        Box::new(ChaserBot::new(Difficulty::Easy))
+    });
+    registry.register("Chaser (normal)", || {
+        Box::new(ChaserBot::new(Difficulty::Normal))
+    });
+    registry.register("Chaser (hard)", || {
+        Box::new(ChaserBot::new(Difficulty::Hard))
+    });
+    registry.register("Goalkeeper (easy)", || {
+        Box::new(GoalkeeperBot::new(Difficulty::Easy))
+    });
+    registry.register("Goalkeeper (normal)", || {
+        Box::new(GoalkeeperBot::new(Difficulty::Normal))
+    });
+    registry.register("Goalkeeper (hard)", || {
+        Box::new(GoalkeeperBot::new(Difficulty::Hard))
+    });
+    registry.register("Passer (easy)", || {
+        Box::new(PasserBot::new(Difficulty::Easy))
+    });
+    registry.register("Passer (normal)", || {
+        Box::new(PasserBot::new(Difficulty::Normal))
+    });
+    registry.register("Passer (hard)", || {
+        Box::new(PasserBot::new(Difficulty::Hard))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        headless::HeadlessMatch,
+        lobby::{Lobby, LobbySlot},
+        parser::utils::Team,
+        test_utils::classic,
+    };
+
+    fn play(red: &str, blue: &str, ticks: u32, seed: u64) -> (u32, u32) {
+        let lobby = Lobby {
+            slots: vec![
+                LobbySlot::bot(red, Team::Red),
+                LobbySlot::bot(blue, Team::Blue),
+            ],
+        };
+        let mut game = HeadlessMatch::new(classic(), lobby, BotRegistry::default(), seed);
+        game.run_until_end(ticks);
+        let state = game.match_state();
+        (state.red_score, state.blue_score)
+    }
+
+    #[test]
+    fn chaser_scores_against_idle() {
+        let (red, blue) = play("Chaser (hard)", "Idle", 60 * 60, 0);
+        assert!(red > 0 && blue == 0, "{} - {}", red, blue);
+    }
+
+    #[test]
+    fn bots_are_deterministic() {
+        let first = play("Chaser (easy)", "Goalkeeper (normal)", 60 * 30, 7);
+        assert_eq!(
+            first,
+            play("Chaser (easy)", "Goalkeeper (normal)", 60 * 30, 7)
+        );
+    }
+}
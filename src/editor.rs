@@ -0,0 +1,1093 @@
+use std::{collections::HashMap, path::Path};
+
+use bevy::{
+    input::mouse::{MouseMotion, MouseWheel},
+    math::DVec2,
+    prelude::*,
+    render::camera::ScalingMode,
+    window::PrimaryWindow,
+};
+use bevy_egui::{egui, EguiContexts};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    menu::{DataAssets, StadiumAsset},
+    parser::{
+        disc::DiscRaw,
+        goal::GoalRaw,
+        hx_trait::{handle_traits, Trait},
+        plane::PlaneRaw,
+        segment::{Segment, SegmentRaw},
+        stadium::{resolve_stadium, stadium_file_bytes, StadiumRaw},
+        transform::MirrorAxis,
+        utils::{distance_to_polyline, parse_color, Team},
+        vertex::VertexRaw,
+    },
+    preview::team_color,
+    AppState,
+};
+
+const EDITOR_BG_COLOR: Color = Color::rgb(0.2, 0.2, 0.22);
+const FIELD_COLOR: Color = Color::rgb(0.45, 0.45, 0.45);
+const SELECTION_COLOR: Color = Color::YELLOW;
+const HIDDEN_SEGMENT_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.25);
+// picking distance, in pixels
+const PICK_DISTANCE: f32 = 8.0;
+const ARC_STEPS: usize = 32;
+const NEW_STADIUM_PATH: &str = "assets/stadiums/custom/new.hbs";
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Editor), setup_editor)
+            .add_systems(OnExit(AppState::Editor), cleanup_editor)
+            .add_systems(
+                Update,
+                (editor_panel, camera_controls, handle_pointer, draw_stadium)
+                    .chain()
+                    .run_if(in_state(AppState::Editor)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tool {
+    Select,
+    Vertex,
+    Segment,
+    Disc,
+    Plane,
+    Goal,
+    RedSpawn,
+    BlueSpawn,
+}
+
+impl Tool {
+    const ALL: [Tool; 8] = [
+        Tool::Select,
+        Tool::Vertex,
+        Tool::Segment,
+        Tool::Disc,
+        Tool::Plane,
+        Tool::Goal,
+        Tool::RedSpawn,
+        Tool::BlueSpawn,
+    ];
+
+    fn get_name(&self) -> &'static str {
+        match self {
+            Tool::Select => "Select",
+            Tool::Vertex => "Vertex",
+            Tool::Segment => "Segment",
+            Tool::Disc => "Disc",
+            Tool::Plane => "Plane",
+            Tool::Goal => "Goal",
+            Tool::RedSpawn => "Red spawn",
+            Tool::BlueSpawn => "Blue spawn",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Selection {
+    Vertex(usize),
+    Segment(usize),
+    Disc(usize),
+    Plane(usize),
+    Goal(usize),
+    RedSpawn(usize),
+    BlueSpawn(usize),
+}
+
+// the stadium being edited, kept while test-playing it
+#[derive(Resource)]
+pub struct EditorSession {
+    pub raw: StadiumRaw,
+    pub path: String,
+    tool: Tool,
+    selection: Option<Selection>,
+    // first vertex of the segment being created
+    segment_start: Option<usize>,
+    // first post of the goal being created
+    goal_start: Option<DVec2>,
+    new_curve: f64,
+    new_goal_team: Team,
+    new_plane_normal: [f64; 2],
+    dragging: bool,
+    last_cursor: DVec2,
+    traits_text: String,
+    // parsed from raw.traits whenever they change, the editor draws with them every frame
+    traits: HashMap<String, Trait>,
+    // inputs of the transform section
+    transform_scale: [f64; 2],
+    transform_offset: [f64; 2],
+    status: String,
+}
+
+impl EditorSession {
+    fn new(raw: StadiumRaw, path: String) -> EditorSession {
+        let traits_text = traits_to_text(&raw);
+        let traits = parse_traits(&raw);
+        EditorSession {
+            raw,
+            path,
+            tool: Tool::Select,
+            selection: None,
+            segment_start: None,
+            goal_start: None,
+            new_curve: 0.0,
+            new_goal_team: Team::Red,
+            new_plane_normal: [0.0, 1.0],
+            dragging: false,
+            last_cursor: DVec2::ZERO,
+            traits_text,
+            traits,
+            transform_scale: [1.0, 1.0],
+            transform_offset: [0.0, 0.0],
+            status: String::new(),
+        }
+    }
+
+    fn traits(&self) -> &HashMap<String, Trait> {
+        &self.traits
+    }
+
+    // after raw.traits changed
+    fn refresh_traits(&mut self) {
+        self.traits_text = traits_to_text(&self.raw);
+        self.traits = parse_traits(&self.raw);
+    }
+
+    fn vertexes(&mut self) -> &mut Vec<VertexRaw> {
+        self.raw.vertexes.get_or_insert_with(Vec::new)
+    }
+
+    fn segments(&mut self) -> &mut Vec<SegmentRaw> {
+        self.raw.segments.get_or_insert_with(Vec::new)
+    }
+
+    fn discs(&mut self) -> &mut Vec<DiscRaw> {
+        self.raw.discs.get_or_insert_with(Vec::new)
+    }
+
+    fn planes(&mut self) -> &mut Vec<PlaneRaw> {
+        self.raw.planes.get_or_insert_with(Vec::new)
+    }
+
+    fn goals(&mut self) -> &mut Vec<GoalRaw> {
+        self.raw.goals.get_or_insert_with(Vec::new)
+    }
+
+    fn spawn_points(&mut self, team: Team) -> &mut Vec<Vec<f64>> {
+        match team {
+            Team::Blue => self.raw.blue_spawn_points.get_or_insert_with(Vec::new),
+            _ => self.raw.red_spawn_points.get_or_insert_with(Vec::new),
+        }
+    }
+
+    fn delete_selection(&mut self) {
+        let Some(selection) = self.selection.take() else {
+            return;
+        };
+
+        match selection {
+            Selection::Vertex(i) => remove_vertex(&mut self.raw, i),
+            Selection::Segment(i) => {
+                self.segments().remove(i);
+            }
+            Selection::Disc(i) => {
+                self.discs().remove(i);
+            }
+            Selection::Plane(i) => {
+                self.planes().remove(i);
+            }
+            Selection::Goal(i) => {
+                self.goals().remove(i);
+            }
+            Selection::RedSpawn(i) => {
+                self.spawn_points(Team::Red).remove(i);
+            }
+            Selection::BlueSpawn(i) => {
+                self.spawn_points(Team::Blue).remove(i);
+            }
+        }
+    }
+
+    fn pick(&self, pos: DVec2, tolerance: f64) -> Option<Selection> {
+        let traits = self.traits();
+        let vertexes = vertex_positions(&self.raw);
+
+        if let Some(i) = vertexes.iter().position(|v| v.distance(pos) <= tolerance) {
+            return Some(Selection::Vertex(i));
+        }
+
+        // objects the parser rejects are not drawn and can not be picked
+        for (i, disc) in self.raw.discs.iter().flatten().enumerate() {
+            let Ok(disc) = disc.to_disc(traits) else {
+                continue;
+            };
+            if disc.position.distance(pos) <= disc.radius.max(tolerance) {
+                return Some(Selection::Disc(i));
+            }
+        }
+
+        for (team, points) in [
+            (Team::Red, &self.raw.red_spawn_points),
+            (Team::Blue, &self.raw.blue_spawn_points),
+        ] {
+            for (i, point) in points.iter().flatten().enumerate() {
+                if DVec2::new(point[0], point[1]).distance(pos) <= tolerance {
+                    return Some(match team {
+                        Team::Blue => Selection::BlueSpawn(i),
+                        _ => Selection::RedSpawn(i),
+                    });
+                }
+            }
+        }
+
+        for (i, goal) in self.raw.goals.iter().flatten().enumerate() {
+            if distance_to_polyline(pos, &[DVec2::from(goal.p0), DVec2::from(goal.p1)]) <= tolerance
+            {
+                return Some(Selection::Goal(i));
+            }
+        }
+
+        for (i, segment) in self.raw.segments.iter().flatten().enumerate() {
+            let Ok(segment) = segment.to_segment(traits) else {
+                continue;
+            };
+            let points = segment.points(&vertexes, ARC_STEPS);
+            if distance_to_polyline(pos, &points) <= tolerance {
+                return Some(Selection::Segment(i));
+            }
+        }
+
+        for (i, plane) in self.raw.planes.iter().flatten().enumerate() {
+            let normal = DVec2::from(plane.normal).normalize_or_zero();
+            if (normal.dot(pos) - plane.dist).abs() <= tolerance {
+                return Some(Selection::Plane(i));
+            }
+        }
+
+        None
+    }
+
+    fn move_selection(&mut self, pos: DVec2, delta: DVec2) {
+        let Some(selection) = self.selection else {
+            return;
+        };
+        let pos = pos.round();
+
+        match selection {
+            Selection::Vertex(i) => {
+                let vertex = &mut self.vertexes()[i];
+                (vertex.x, vertex.y) = (pos.x, pos.y);
+            }
+            Selection::Disc(i) => self.discs()[i].pos = pos.to_array(),
+            Selection::RedSpawn(i) => self.spawn_points(Team::Red)[i] = pos.to_array().to_vec(),
+            Selection::BlueSpawn(i) => self.spawn_points(Team::Blue)[i] = pos.to_array().to_vec(),
+            Selection::Goal(i) => {
+                let goal = &mut self.goals()[i];
+                goal.p0 = (DVec2::from(goal.p0) + delta).to_array();
+                goal.p1 = (DVec2::from(goal.p1) + delta).to_array();
+            }
+            Selection::Plane(i) => {
+                let plane = &mut self.planes()[i];
+                let normal = DVec2::from(plane.normal).normalize_or_zero();
+                plane.dist = normal.dot(pos).round();
+            }
+            Selection::Segment(_) => {}
+        }
+    }
+
+    fn click(&mut self, pos: DVec2, tolerance: f64) {
+        let rounded = pos.round();
+
+        match self.tool {
+            Tool::Select => {
+                self.selection = self.pick(pos, tolerance);
+                self.dragging = self.selection.is_some();
+            }
+            Tool::Vertex => {
+                self.vertexes().push(VertexRaw {
+                    x: rounded.x,
+                    y: rounded.y,
+                    b_coef: None,
+                    c_group: None,
+                    c_mask: None,
+                    hx_trait: None,
+                    extra: Map::new(),
+                });
+                self.selection = Some(Selection::Vertex(self.vertexes().len() - 1));
+            }
+            Tool::Segment => {
+                let Some(Selection::Vertex(v)) = self.pick(pos, tolerance) else {
+                    return;
+                };
+                match self.segment_start.take() {
+                    Some(v0) if v0 != v => {
+                        let curve = (self.new_curve != 0.0).then_some(self.new_curve);
+                        self.segments().push(SegmentRaw {
+                            v0,
+                            v1: v,
+                            b_coef: None,
+                            curve,
+                            curve_f: None,
+                            bias: None,
+                            c_group: None,
+                            c_mask: None,
+                            vis: None,
+                            color: None,
+                            hx_trait: None,
+                            extra: Map::new(),
+                        });
+                        self.selection = Some(Selection::Segment(self.segments().len() - 1));
+                    }
+                    _ => self.segment_start = Some(v),
+                }
+            }
+            Tool::Disc => {
+                self.discs().push(DiscRaw {
+                    pos: rounded.to_array(),
+                    speed: None,
+                    gravity: None,
+                    radius: None,
+                    inv_mass: None,
+                    damping: None,
+                    b_coef: None,
+                    color: None,
+                    c_group: None,
+                    c_mask: None,
+                    vis: None,
+                    hx_trait: None,
+                    extra: Map::new(),
+                });
+                self.selection = Some(Selection::Disc(self.discs().len() - 1));
+            }
+            Tool::Plane => {
+                let normal = DVec2::from(self.new_plane_normal).normalize_or_zero();
+                self.planes().push(PlaneRaw {
+                    normal: normal.to_array(),
+                    dist: normal.dot(rounded).round(),
+                    b_coef: None,
+                    c_group: None,
+                    c_mask: None,
+                    hx_trait: None,
+                    extra: Map::new(),
+                });
+                self.selection = Some(Selection::Plane(self.planes().len() - 1));
+            }
+            Tool::Goal => match self.goal_start.take() {
+                Some(p0) => {
+                    let team = match self.new_goal_team {
+                        Team::Blue => "blue",
+                        _ => "red",
+                    };
+                    self.goals().push(GoalRaw {
+                        p0: p0.to_array(),
+                        p1: rounded.to_array(),
+                        team: team.to_string(),
+                        extra: Map::new(),
+                    });
+                    self.selection = Some(Selection::Goal(self.goals().len() - 1));
+                }
+                None => self.goal_start = Some(rounded),
+            },
+            Tool::RedSpawn | Tool::BlueSpawn => {
+                let team = match self.tool {
+                    Tool::BlueSpawn => Team::Blue,
+                    _ => Team::Red,
+                };
+                self.spawn_points(team).push(rounded.to_array().to_vec());
+                let i = self.spawn_points(team).len() - 1;
+                self.selection = Some(match team {
+                    Team::Blue => Selection::BlueSpawn(i),
+                    _ => Selection::RedSpawn(i),
+                });
+            }
+        }
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy)]
+struct EditorCamera;
+
+// a stadium with invalid traits is drawn without them
+fn parse_traits(raw: &StadiumRaw) -> HashMap<String, Trait> {
+    handle_traits(raw.traits.clone().unwrap_or(Value::Array(vec![]))).unwrap_or_default()
+}
+
+// segments using the vertex go away, the others are re-indexed
+fn remove_vertex(raw: &mut StadiumRaw, index: usize) {
+    let Some(vertexes) = raw.vertexes.as_mut().filter(|v| index < v.len()) else {
+        return;
+    };
+    vertexes.remove(index);
+    let segments = raw.segments.get_or_insert_with(Vec::new);
+    segments.retain(|s| s.v0 != index && s.v1 != index);
+    for segment in segments.iter_mut() {
+        if segment.v0 > index {
+            segment.v0 -= 1;
+        }
+        if segment.v1 > index {
+            segment.v1 -= 1;
+        }
+    }
+}
+
+fn traits_to_text(raw: &StadiumRaw) -> String {
+    match &raw.traits {
+        Some(Value::Object(map)) => serde_json::to_string_pretty(map).unwrap(),
+        _ => "{}".to_string(),
+    }
+}
+
+fn new_stadium() -> StadiumRaw {
+    serde_json::from_value(json!({
+        "name": "New stadium",
+        "width": 420,
+        "height": 200,
+        "spawnDistance": 170,
+        "bg": { "type": "grass", "width": 370, "height": 170, "kickOffRadius": 75 },
+        "traits": {},
+        "vertexes": [],
+        "segments": [],
+        "goals": [],
+        "discs": [],
+        "planes": [],
+        "redSpawnPoints": [],
+        "blueSpawnPoints": []
+    }))
+    .unwrap()
+}
+
+fn flattened_path(path: &str) -> String {
+    let path = Path::new(path);
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("stadium");
+    path.with_file_name(format!("{}.flat.hbs", stem))
+        .display()
+        .to_string()
+}
+
+fn vertex_positions(raw: &StadiumRaw) -> Vec<DVec2> {
+    raw.vertexes
+        .iter()
+        .flatten()
+        .map(|v| DVec2::new(v.x, v.y))
+        .collect()
+}
+
+fn to_vec2(v: DVec2) -> Vec2 {
+    Vec2::new(v.x as f32, v.y as f32)
+}
+
+fn setup_editor(
+    mut commands: Commands,
+    session: Option<Res<EditorSession>>,
+    data_assets: Option<Res<DataAssets>>,
+    stadium_assets: Res<Assets<StadiumAsset>>,
+    asset_server: Res<AssetServer>,
+) {
+    if session.is_none() {
+        let loaded = data_assets.as_ref().and_then(|data_assets| {
+            let asset = stadium_assets.get(&data_assets.stadium)?;
+            let path = asset_server
+                .get_handle_path(&data_assets.stadium)
+                .map(|p| format!("assets/{}", p.path().display()))
+                .unwrap_or_else(|| NEW_STADIUM_PATH.to_string());
+            Some(EditorSession::new(asset.1.clone(), path))
+        });
+        commands.insert_resource(
+            loaded.unwrap_or_else(|| EditorSession::new(new_stadium(), NEW_STADIUM_PATH.into())),
+        );
+    }
+
+    commands.spawn((
+        EditorCamera,
+        Camera2dBundle {
+            projection: OrthographicProjection {
+                scaling_mode: ScalingMode::WindowSize(1.0),
+                ..Default::default()
+            },
+            transform: Transform {
+                scale: Vec3::new(1.0, -1.0, -1.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    ));
+    commands.insert_resource(ClearColor(EDITOR_BG_COLOR));
+}
+
+fn cleanup_editor(mut commands: Commands, cameras: Query<Entity, With<EditorCamera>>) {
+    for entity in cameras.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.insert_resource(ClearColor::default());
+}
+
+fn optional_value(ui: &mut egui::Ui, label: &str, value: &mut Option<f64>, default: f64) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        let mut v = value.unwrap_or(default);
+        if ui.add(egui::DragValue::new(&mut v).speed(0.01)).changed() {
+            *value = Some(v);
+        }
+    });
+}
+
+fn position_value(ui: &mut egui::Ui, label: &str, x: &mut f64, y: &mut f64) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        ui.add(egui::DragValue::new(x));
+        ui.add(egui::DragValue::new(y));
+    });
+}
+
+fn color_value(ui: &mut egui::Ui, value: &mut Option<Value>, default: Color) {
+    ui.horizontal(|ui| {
+        ui.label("Color");
+        let color = value
+            .as_ref()
+            .and_then(|v| parse_color(v, true).ok())
+            .unwrap_or(default);
+        let [r, g, b, _] = color.as_rgba_u8();
+        let mut srgb = [r, g, b];
+        if ui.color_edit_button_srgb(&mut srgb).changed() {
+            let hex = format!("{:02X}{:02X}{:02X}", srgb[0], srgb[1], srgb[2]);
+            *value = Some(Value::String(hex));
+        }
+    });
+}
+
+fn trait_value(ui: &mut egui::Ui, value: &mut Option<String>, trait_names: &[String]) {
+    ui.horizontal(|ui| {
+        ui.label("Trait");
+        egui::ComboBox::from_id_source("selection_trait")
+            .selected_text(value.clone().unwrap_or_else(|| "none".to_string()))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(value, None, "none");
+                for name in trait_names {
+                    ui.selectable_value(value, Some(name.clone()), name);
+                }
+            });
+    });
+}
+
+fn selection_ui(ui: &mut egui::Ui, session: &mut EditorSession) {
+    let Some(selection) = session.selection else {
+        ui.label("Nothing selected");
+        return;
+    };
+    let mut trait_names = session.traits().keys().cloned().collect::<Vec<_>>();
+    trait_names.sort();
+    let vertex_count = session.raw.vertexes.as_ref().map_or(0, |v| v.len());
+
+    match selection {
+        Selection::Vertex(i) => {
+            let vertex = &mut session.vertexes()[i];
+            ui.label(format!("Vertex {}", i));
+            position_value(ui, "Position", &mut vertex.x, &mut vertex.y);
+            optional_value(ui, "bCoef", &mut vertex.b_coef, 1.0);
+            trait_value(ui, &mut vertex.hx_trait, &trait_names);
+        }
+        Selection::Segment(i) => {
+            let segment = &mut session.segments()[i];
+            ui.label(format!("Segment {}", i));
+            ui.horizontal(|ui| {
+                ui.label("Vertexes");
+                let max = vertex_count.saturating_sub(1);
+                ui.add(egui::DragValue::new(&mut segment.v0).clamp_range(0..=max));
+                ui.add(egui::DragValue::new(&mut segment.v1).clamp_range(0..=max));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Curve");
+                let mut curve = segment.curve.unwrap_or(0.0);
+                if ui
+                    .add(egui::Slider::new(&mut curve, -340.0..=340.0))
+                    .changed()
+                {
+                    segment.curve = (curve != 0.0).then_some(curve);
+                }
+            });
+            optional_value(ui, "Bias", &mut segment.bias, 0.0);
+            optional_value(ui, "bCoef", &mut segment.b_coef, 1.0);
+            let mut vis = segment.vis.unwrap_or(true);
+            if ui.checkbox(&mut vis, "Visible").changed() {
+                segment.vis = Some(vis);
+            }
+            color_value(ui, &mut segment.color, Color::BLACK);
+            trait_value(ui, &mut segment.hx_trait, &trait_names);
+        }
+        Selection::Disc(i) => {
+            let disc = &mut session.discs()[i];
+            ui.label(format!("Disc {}", i));
+            let [mut x, mut y] = disc.pos;
+            position_value(ui, "Position", &mut x, &mut y);
+            disc.pos = [x, y];
+            optional_value(ui, "Radius", &mut disc.radius, 10.0);
+            optional_value(ui, "invMass", &mut disc.inv_mass, 1.0);
+            optional_value(ui, "Damping", &mut disc.damping, 0.99);
+            optional_value(ui, "bCoef", &mut disc.b_coef, 0.5);
+            color_value(ui, &mut disc.color, Color::WHITE);
+            trait_value(ui, &mut disc.hx_trait, &trait_names);
+        }
+        Selection::Plane(i) => {
+            let plane = &mut session.planes()[i];
+            ui.label(format!("Plane {}", i));
+            let [mut x, mut y] = plane.normal;
+            position_value(ui, "Normal", &mut x, &mut y);
+            plane.normal = [x, y];
+            ui.horizontal(|ui| {
+                ui.label("Dist");
+                ui.add(egui::DragValue::new(&mut plane.dist));
+            });
+            optional_value(ui, "bCoef", &mut plane.b_coef, 1.0);
+            trait_value(ui, &mut plane.hx_trait, &trait_names);
+        }
+        Selection::Goal(i) => {
+            let goal = &mut session.goals()[i];
+            ui.label(format!("Goal {}", i));
+            let ([mut x0, mut y0], [mut x1, mut y1]) = (goal.p0, goal.p1);
+            position_value(ui, "P0", &mut x0, &mut y0);
+            position_value(ui, "P1", &mut x1, &mut y1);
+            (goal.p0, goal.p1) = ([x0, y0], [x1, y1]);
+            ui.horizontal(|ui| {
+                ui.label("Team");
+                ui.radio_value(&mut goal.team, "red".to_string(), "Red");
+                ui.radio_value(&mut goal.team, "blue".to_string(), "Blue");
+            });
+        }
+        Selection::RedSpawn(i) | Selection::BlueSpawn(i) => {
+            let team = match selection {
+                Selection::BlueSpawn(_) => Team::Blue,
+                _ => Team::Red,
+            };
+            let point = &mut session.spawn_points(team)[i];
+            ui.label(format!("{:?} spawn point {}", team, i));
+            let (mut x, mut y) = (point[0], point[1]);
+            position_value(ui, "Position", &mut x, &mut y);
+            *point = vec![x, y];
+        }
+    }
+
+    if ui.button("Delete").clicked() {
+        session.delete_selection();
+    }
+}
+
+fn transform_ui(ui: &mut egui::Ui, session: &mut EditorSession) {
+    let mut transformed = false;
+    ui.horizontal(|ui| {
+        let [x, y] = &mut session.transform_scale;
+        ui.label("Scale");
+        ui.add(
+            egui::DragValue::new(x)
+                .speed(0.01)
+                .clamp_range(0.01..=100.0),
+        );
+        ui.add(
+            egui::DragValue::new(y)
+                .speed(0.01)
+                .clamp_range(0.01..=100.0),
+        );
+        if ui.button("Apply").clicked() {
+            session.raw.scale(DVec2::from(session.transform_scale));
+            transformed = true;
+        }
+    });
+    ui.horizontal(|ui| {
+        let [x, y] = &mut session.transform_offset;
+        ui.label("Translate");
+        ui.add(egui::DragValue::new(x));
+        ui.add(egui::DragValue::new(y));
+        if ui.button("Apply").clicked() {
+            session.raw.translate(DVec2::from(session.transform_offset));
+            transformed = true;
+        }
+    });
+    ui.horizontal(|ui| {
+        if ui.button("Mirror ↔").clicked() {
+            session.raw.mirror(MirrorAxis::Horizontal);
+            transformed = true;
+        }
+        if ui.button("Mirror ↕").clicked() {
+            session.raw.mirror(MirrorAxis::Vertical);
+            transformed = true;
+        }
+        if ui.button("Rotate ↻").clicked() {
+            session.raw.rotate_90(true);
+            transformed = true;
+        }
+        if ui.button("Rotate ↺").clicked() {
+            session.raw.rotate_90(false);
+            transformed = true;
+        }
+    });
+    if transformed {
+        // mirroring changes the traits and the segment ends
+        session.refresh_traits();
+        session.selection = None;
+        session.segment_start = None;
+        session.goal_start = None;
+    }
+}
+
+fn editor_panel(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut session: ResMut<EditorSession>,
+    mut stadium_assets: ResMut<Assets<StadiumAsset>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let session = session.as_mut();
+
+    egui::SidePanel::left("editor_panel").show(contexts.ctx_mut(), |ui| {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.heading("Editor");
+            ui.horizontal(|ui| {
+                ui.label("Name");
+                ui.text_edit_singleline(&mut session.raw.name);
+            });
+
+            ui.separator();
+            ui.horizontal_wrapped(|ui| {
+                for tool in Tool::ALL {
+                    if ui
+                        .selectable_label(session.tool == tool, tool.get_name())
+                        .clicked()
+                    {
+                        session.tool = tool;
+                        session.segment_start = None;
+                        session.goal_start = None;
+                    }
+                }
+            });
+            match session.tool {
+                Tool::Segment => {
+                    ui.add(egui::Slider::new(&mut session.new_curve, -340.0..=340.0).text("Curve"));
+                }
+                Tool::Goal => {
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut session.new_goal_team, Team::Red, "Red");
+                        ui.radio_value(&mut session.new_goal_team, Team::Blue, "Blue");
+                    });
+                }
+                Tool::Plane => {
+                    let [x, y] = &mut session.new_plane_normal;
+                    ui.horizontal(|ui| {
+                        ui.label("Normal");
+                        ui.add(egui::DragValue::new(x).speed(0.1));
+                        ui.add(egui::DragValue::new(y).speed(0.1));
+                    });
+                }
+                _ => {}
+            }
+
+            ui.separator();
+            selection_ui(ui, session);
+
+            ui.separator();
+            ui.collapsing("Traits", |ui| {
+                ui.add(egui::TextEdit::multiline(&mut session.traits_text).code_editor());
+                if ui.button("Apply traits").clicked() {
+                    let parsed = serde_json::from_str::<serde_json::Map<String, Value>>(
+                        &session.traits_text,
+                    )
+                    .map_err(|e| e.to_string())
+                    .and_then(|map| {
+                        // make sure every trait can be used before replacing them
+                        for value in map.values() {
+                            serde_json::from_value::<Trait>(value.clone())
+                                .map_err(|e| e.to_string())?;
+                        }
+                        Ok(map)
+                    });
+                    match parsed {
+                        Ok(map) => {
+                            session.raw.traits = Some(Value::Object(map));
+                            session.refresh_traits();
+                            session.status = "Traits updated".to_string();
+                        }
+                        Err(e) => session.status = format!("Invalid traits: {}", e),
+                    }
+                }
+            });
+
+            ui.separator();
+            ui.collapsing("Transform", |ui| transform_ui(ui, session));
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("File");
+                ui.text_edit_singleline(&mut session.path);
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    // .hbsb files are saved in the binary format
+                    let bytes = stadium_file_bytes(Path::new(&session.path), &session.raw);
+                    session.status = match std::fs::write(&session.path, bytes) {
+                        Ok(()) => format!("Saved to {}", session.path),
+                        Err(e) => format!("Could not save: {}", e),
+                    };
+                }
+                if ui.button("Export flattened").clicked() {
+                    // traits and defaults resolved, next to the edited file
+                    let path = flattened_path(&session.path);
+                    session.status = match resolve_stadium(&session.raw) {
+                        Ok(stadium) => match std::fs::write(&path, stadium.to_hbs()) {
+                            Ok(()) => format!("Exported to {}", path),
+                            Err(e) => format!("Could not export: {}", e),
+                        },
+                        Err(e) => format!("Invalid stadium: {}", e),
+                    };
+                }
+                if ui.button("Test play").clicked() {
+                    match resolve_stadium(&session.raw) {
+                        Ok(stadium) => {
+                            let stadium =
+                                stadium_assets.add(StadiumAsset(stadium, session.raw.clone()));
+                            commands.insert_resource(DataAssets { stadium });
+                            next_state.set(AppState::InGame);
+                        }
+                        Err(e) => session.status = format!("Invalid stadium: {}", e),
+                    }
+                }
+                if ui.button("Back to menu").clicked() {
+                    commands.remove_resource::<EditorSession>();
+                    next_state.set(AppState::Menu);
+                }
+            });
+            ui.label(&session.status);
+        });
+    });
+}
+
+fn camera_controls(
+    mut contexts: EguiContexts,
+    buttons: Res<Input<MouseButton>>,
+    mut motion: EventReader<MouseMotion>,
+    mut wheel: EventReader<MouseWheel>,
+    mut cameras: Query<(&mut Transform, &mut OrthographicProjection), With<EditorCamera>>,
+) {
+    let Ok((mut transform, mut projection)) = cameras.get_single_mut() else {
+        return;
+    };
+    if contexts.ctx_mut().wants_pointer_input() {
+        motion.clear();
+        wheel.clear();
+        return;
+    }
+
+    for event in wheel.iter() {
+        projection.scale = (projection.scale * 1.1_f32.powf(-event.y)).clamp(0.05, 20.0);
+    }
+    if buttons.pressed(MouseButton::Right) {
+        for event in motion.iter() {
+            // the camera is flipped vertically
+            transform.translation.x -= event.delta.x * projection.scale;
+            transform.translation.y -= event.delta.y * projection.scale;
+        }
+    } else {
+        motion.clear();
+    }
+}
+
+fn handle_pointer(
+    mut contexts: EguiContexts,
+    mut session: ResMut<EditorSession>,
+    buttons: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform, &OrthographicProjection), With<EditorCamera>>,
+) {
+    if keys.just_pressed(KeyCode::Delete) && !contexts.ctx_mut().wants_keyboard_input() {
+        session.delete_selection();
+    }
+    if buttons.just_released(MouseButton::Left) {
+        session.dragging = false;
+    }
+
+    let Ok((camera, camera_transform, projection)) = cameras.get_single() else {
+        return;
+    };
+    let Some(cursor) = windows
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor))
+    else {
+        return;
+    };
+    let pos = DVec2::new(cursor.x as f64, cursor.y as f64);
+    let delta = pos - session.last_cursor;
+    session.last_cursor = pos;
+
+    if buttons.just_pressed(MouseButton::Left) && !contexts.ctx_mut().is_pointer_over_area() {
+        let tolerance = (PICK_DISTANCE * projection.scale) as f64;
+        session.click(pos, tolerance);
+    } else if session.dragging && buttons.pressed(MouseButton::Left) {
+        session.move_selection(pos, delta);
+    }
+}
+
+fn draw_stadium(mut gizmos: Gizmos, session: Res<EditorSession>) {
+    let raw = &session.raw;
+    let traits = session.traits();
+    let vertexes = vertex_positions(raw);
+    let selected = |s: Selection, color: Color| {
+        if session.selection == Some(s) {
+            SELECTION_COLOR
+        } else {
+            color
+        }
+    };
+
+    let (width, height) = (raw.bg.width.unwrap_or(0.0), raw.bg.height.unwrap_or(0.0));
+    if width > 0.0 && height > 0.0 {
+        gizmos.rect_2d(
+            Vec2::ZERO,
+            0.0,
+            Vec2::new(2.0 * width as f32, 2.0 * height as f32),
+            FIELD_COLOR,
+        );
+    }
+    if let Some(radius) = raw.bg.kick_off_radius.filter(|r| *r > 0.0) {
+        gizmos.circle_2d(Vec2::ZERO, radius as f32, FIELD_COLOR);
+    }
+
+    for (i, plane) in raw.planes.iter().flatten().enumerate() {
+        let normal = DVec2::from(plane.normal).normalize_or_zero();
+        let origin = normal * plane.dist;
+        let along = normal.perp() * 2.0 * raw.width.unwrap_or(1000.0).max(1000.0);
+        gizmos.line_2d(
+            to_vec2(origin - along),
+            to_vec2(origin + along),
+            selected(Selection::Plane(i), Color::GRAY),
+        );
+    }
+
+    for (i, segment) in raw.segments.iter().flatten().enumerate() {
+        let Ok(resolved) = segment.to_segment(traits) else {
+            continue;
+        };
+        let (vis, color) = match &resolved {
+            Segment::Straight(s) => (s.vis, s.color),
+            Segment::Curved(s) => (s.vis, s.color),
+        };
+        let color = if vis { color } else { HIDDEN_SEGMENT_COLOR };
+        let points = resolved.points(&vertexes, ARC_STEPS);
+        gizmos.linestrip_2d(
+            points.into_iter().map(to_vec2),
+            selected(Selection::Segment(i), color),
+        );
+    }
+
+    for (i, vertex) in vertexes.iter().enumerate() {
+        let color = if session.segment_start == Some(i) {
+            SELECTION_COLOR
+        } else {
+            selected(Selection::Vertex(i), Color::WHITE)
+        };
+        gizmos.circle_2d(to_vec2(*vertex), 3.0, color);
+    }
+
+    for (i, disc) in raw.discs.iter().flatten().enumerate() {
+        let Ok(disc) = disc.to_disc(traits) else {
+            continue;
+        };
+        gizmos.circle_2d(
+            to_vec2(disc.position),
+            disc.radius as f32,
+            selected(Selection::Disc(i), disc.color),
+        );
+    }
+
+    for (i, goal) in raw.goals.iter().flatten().enumerate() {
+        let color = if goal.team == "blue" {
+            team_color(Team::Blue)
+        } else {
+            team_color(Team::Red)
+        };
+        gizmos.line_2d(
+            to_vec2(DVec2::from(goal.p0)),
+            to_vec2(DVec2::from(goal.p1)),
+            selected(Selection::Goal(i), color),
+        );
+    }
+    if let Some(start) = session.goal_start {
+        gizmos.circle_2d(to_vec2(start), 4.0, SELECTION_COLOR);
+    }
+
+    for (team, points) in [
+        (Team::Red, &raw.red_spawn_points),
+        (Team::Blue, &raw.blue_spawn_points),
+    ] {
+        for (i, point) in points.iter().flatten().enumerate() {
+            let selection = match team {
+                Team::Blue => Selection::BlueSpawn(i),
+                _ => Selection::RedSpawn(i),
+            };
+            let color = team_color(team);
+            gizmos.circle_2d(
+                Vec2::new(point[0] as f32, point[1] as f32),
+                6.0,
+                selected(selection, color),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removing_a_vertex_removes_its_segments_and_shifts_the_others() {
+        let mut raw: StadiumRaw = serde_json::from_value(json!({
+            "name": "vertexes",
+            "bg": {},
+            "vertexes": [
+                { "x": 0, "y": 0 },
+                { "x": 1, "y": 0 },
+                { "x": 2, "y": 0 },
+                { "x": 3, "y": 0 }
+            ],
+            "segments": [
+                { "v0": 0, "v1": 1 },
+                { "v0": 1, "v1": 2 },
+                { "v0": 2, "v1": 3 },
+                { "v0": 3, "v1": 0 }
+            ]
+        }))
+        .unwrap();
+
+        remove_vertex(&mut raw, 1);
+
+        let xs = raw
+            .vertexes
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|v| v.x)
+            .collect::<Vec<_>>();
+        assert_eq!(xs, vec![0.0, 2.0, 3.0]);
+        let ends = raw
+            .segments
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|s| (s.v0, s.v1))
+            .collect::<Vec<_>>();
+        assert_eq!(ends, vec![(1, 2), (2, 0)]);
+
+        // an index past the end leaves the stadium as it is
+        remove_vertex(&mut raw, 3);
+        assert_eq!(raw.vertexes.as_ref().unwrap().len(), 3);
+        assert_eq!(raw.segments.as_ref().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,480 @@
+use std::sync::Arc;
+
+use bevy::math::DVec2;
+
+use crate::{
+    bot::{BotRegistry, DiscState, GameSnapshot, PlayerState},
+    game::{MatchPhase, MatchSettings, TICKS_PER_SECOND},
+    headless::HeadlessMatch,
+    lobby::{Controller, Lobby},
+    parser::{stadium::Stadium, utils::Team},
+    player::PlayerInput,
+};
+
+// what goes in the observation vector of an agent
+//
+// positions and velocities are in the frame of the team: the origin is the own goal,
+// x points to the opponent goal and 1 is the distance between the goals
+//
+// layout, 2 floats per vector:
+//   me         position, velocity if `velocities`
+//   ball       position, velocity if `velocities`
+//   teammates  `teammates` times: present flag, position, velocity if `velocities`
+//   opponents  `opponents` times: same as teammates
+//   match      if `match_info`: goal difference, elapsed fraction of the time limit, kickoff flag
+#[derive(Debug, Clone, Copy)]
+pub struct ObservationConfig {
+    pub velocities: bool,
+    // players further from the agent are left out, missing ones are zeros
+    pub teammates: usize,
+    pub opponents: usize,
+    pub match_info: bool,
+    // velocities are divided by this speed
+    pub velocity_scale: f64,
+}
+
+impl Default for ObservationConfig {
+    fn default() -> Self {
+        ObservationConfig {
+            velocities: true,
+            teammates: 0,
+            opponents: 1,
+            match_info: true,
+            // about the speed of a kicked ball
+            velocity_scale: 5.0,
+        }
+    }
+}
+
+impl ObservationConfig {
+    // number of floats in an observation
+    pub fn size(&self) -> usize {
+        let disc = if self.velocities { 4 } else { 2 };
+        let match_info = if self.match_info { 3 } else { 0 };
+        2 * disc + (self.teammates + self.opponents) * (disc + 1) + match_info
+    }
+
+    // write the observation of the player `me` into `out`, which must be `size()` long
+    pub fn observe_into(&self, snapshot: &GameSnapshot, me: usize, out: &mut [f32]) {
+        assert_eq!(out.len(), self.size(), "wrong observation buffer size");
+        out.fill(0.0);
+        let Some(player) = snapshot.player(me) else {
+            return;
+        };
+        let frame = TeamFrame::new(snapshot, player.team);
+        let mut out = out.iter_mut();
+        let mut push = |value: f64| {
+            if let Some(slot) = out.next() {
+                *slot = value as f32;
+            }
+        };
+        let push_disc = |push: &mut dyn FnMut(f64), disc: &DiscState| {
+            let position = frame.position(disc.position);
+            push(position.x);
+            push(position.y);
+            if self.velocities {
+                let velocity = frame.velocity(disc.velocity) / self.velocity_scale;
+                push(velocity.x);
+                push(velocity.y);
+            }
+        };
+
+        push_disc(&mut push, &player.disc);
+        push_disc(&mut push, &snapshot.ball);
+
+        let teammates = closest_first(snapshot.teammates(me), player.disc.position);
+        let opponents = closest_first(snapshot.opponents(me), player.disc.position);
+        for (players, count) in [(teammates, self.teammates), (opponents, self.opponents)] {
+            for index in 0..count {
+                match players.get(index) {
+                    Some(disc) => {
+                        push(1.0);
+                        push_disc(&mut push, disc);
+                    }
+                    None => {
+                        let floats = if self.velocities { 5 } else { 3 };
+                        (0..floats).for_each(|_| push(0.0));
+                    }
+                }
+            }
+        }
+
+        if self.match_info {
+            let (own, other) = match player.team {
+                Team::Blue => (snapshot.blue_score, snapshot.red_score),
+                _ => (snapshot.red_score, snapshot.blue_score),
+            };
+            push(own as f64 - other as f64);
+            let limit = snapshot.time_limit * 60 * TICKS_PER_SECOND;
+            push(if limit > 0 {
+                snapshot.ticks as f64 / limit as f64
+            } else {
+                0.0
+            });
+            push(match snapshot.phase {
+                MatchPhase::Kickoff(team) if team == player.team => 1.0,
+                MatchPhase::Kickoff(_) => -1.0,
+                _ => 0.0,
+            });
+        }
+    }
+
+    pub fn observe(&self, snapshot: &GameSnapshot, me: usize) -> Vec<f32> {
+        let mut observation = vec![0.0; self.size()];
+        self.observe_into(snapshot, me, &mut observation);
+        observation
+    }
+}
+
+fn closest_first<'a>(
+    players: impl Iterator<Item = &'a PlayerState>,
+    position: DVec2,
+) -> Vec<&'a DiscState> {
+    let mut discs = players.map(|player| &player.disc).collect::<Vec<_>>();
+    discs.sort_by(|a, b| {
+        let a = a.position.distance(position);
+        let b = b.position.distance(position);
+        a.total_cmp(&b)
+    });
+    discs
+}
+
+// coordinates relative to the goal of a team
+pub struct TeamFrame {
+    origin: DVec2,
+    // toward the opponent goal, its length is the inverse of the distance between the goals
+    axis: DVec2,
+}
+
+impl TeamFrame {
+    pub fn new(snapshot: &GameSnapshot, team: Team) -> TeamFrame {
+        let origin = snapshot.stadium.defend_point(team);
+        let to_goal = snapshot.stadium.attack_point(team) - origin;
+        let length = to_goal.length().max(1.0);
+        TeamFrame {
+            origin,
+            axis: to_goal / (length * length),
+        }
+    }
+
+    pub fn position(&self, position: DVec2) -> DVec2 {
+        self.velocity(position - self.origin)
+    }
+
+    // a vector, it is rotated and scaled but not moved
+    pub fn velocity(&self, vector: DVec2) -> DVec2 {
+        // the y axis is rotated with x, blue sees the stadium turned by half a turn
+        DVec2::new(vector.dot(self.axis), self.axis.perp_dot(vector))
+    }
+}
+
+// the reward of an agent for what happened between two snapshots
+pub trait Reward: Send + Sync {
+    fn reward(&self, previous: &GameSnapshot, current: &GameSnapshot, me: usize) -> f32;
+}
+
+fn goal_difference(snapshot: &GameSnapshot, team: Team) -> i64 {
+    let (red, blue) = (snapshot.red_score as i64, snapshot.blue_score as i64);
+    match team {
+        Team::Blue => blue - red,
+        _ => red - blue,
+    }
+}
+
+// 1 for a goal of the team, -1 for a goal of the opponents
+pub struct GoalReward;
+
+impl Reward for GoalReward {
+    fn reward(&self, previous: &GameSnapshot, current: &GameSnapshot, me: usize) -> f32 {
+        let Some(player) = current.player(me) else {
+            return 0.0;
+        };
+        (goal_difference(current, player.team) - goal_difference(previous, player.team)) as f32
+    }
+}
+
+// how much closer the ball got to the opponent goal, 1 for the distance between the goals
+pub struct BallProgressReward;
+
+impl Reward for BallProgressReward {
+    fn reward(&self, previous: &GameSnapshot, current: &GameSnapshot, me: usize) -> f32 {
+        let Some(player) = current.player(me) else {
+            return 0.0;
+        };
+        // the ball goes back to the center after a goal
+        if matches!(current.phase, MatchPhase::Kickoff(_)) {
+            return 0.0;
+        }
+        let frame = TeamFrame::new(current, player.team);
+        let progress =
+            frame.position(current.ball.position).x - frame.position(previous.ball.position).x;
+        progress as f32
+    }
+}
+
+// how much closer the agent got to the ball, in units of the distance between the goals
+pub struct BallProximityReward;
+
+impl Reward for BallProximityReward {
+    fn reward(&self, previous: &GameSnapshot, current: &GameSnapshot, me: usize) -> f32 {
+        let (Some(before), Some(after)) = (previous.player(me), current.player(me)) else {
+            return 0.0;
+        };
+        let frame = TeamFrame::new(current, after.team);
+        let distance = |player: DVec2, ball: DVec2| frame.velocity(ball - player).length();
+        (distance(before.disc.position, previous.ball.position)
+            - distance(after.disc.position, current.ball.position)) as f32
+    }
+}
+
+// the sum of several rewards, each with a weight
+pub struct WeightedReward(pub Vec<(f32, Arc<dyn Reward>)>);
+
+impl Reward for WeightedReward {
+    fn reward(&self, previous: &GameSnapshot, current: &GameSnapshot, me: usize) -> f32 {
+        self.0
+            .iter()
+            .map(|(weight, reward)| weight * reward.reward(previous, current, me))
+            .sum()
+    }
+}
+
+#[derive(Clone)]
+pub struct EnvConfig {
+    // the agents are the External slots, in lobby order, other slots are bots or idle
+    pub lobby: Lobby,
+    pub bots: BotRegistry,
+    pub observation: ObservationConfig,
+    pub reward: Arc<dyn Reward>,
+    // ticks simulated per step, the actions are held during all of them
+    pub frame_skip: u32,
+    // the episode is truncated after that many ticks
+    pub max_ticks: u32,
+    pub match_settings: MatchSettings,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        EnvConfig {
+            lobby: Lobby { slots: vec![] },
+            bots: BotRegistry::default(),
+            observation: ObservationConfig::default(),
+            reward: Arc::new(GoalReward),
+            frame_skip: 1,
+            max_ticks: 3 * 60 * TICKS_PER_SECOND,
+            match_settings: MatchSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StepInfo {
+    pub ticks: u32,
+    pub red_score: u32,
+    pub blue_score: u32,
+    // the match ended, `done` is also set when the episode is truncated
+    pub ended: bool,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Step {
+    // one per agent, in agent order
+    pub observations: Vec<Vec<f32>>,
+    pub rewards: Vec<f32>,
+    pub done: bool,
+    pub info: StepInfo,
+}
+
+// an environment for reinforcement learning, one headless match per episode
+pub struct Environment {
+    config: EnvConfig,
+    game: Option<HeadlessMatch>,
+    snapshot: Option<GameSnapshot>,
+    // player ids of the External slots
+    agents: Vec<usize>,
+    ticks: u32,
+}
+
+impl Environment {
+    pub fn new(config: EnvConfig) -> Environment {
+        let agents = config
+            .lobby
+            .slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| {
+                slot.controller == Controller::External && slot.team != Team::Spectator
+            })
+            .map(|(id, _)| id)
+            .collect();
+        Environment {
+            config,
+            game: None,
+            snapshot: None,
+            agents,
+            ticks: 0,
+        }
+    }
+
+    pub fn config(&self) -> &EnvConfig {
+        &self.config
+    }
+
+    pub fn agent_count(&self) -> usize {
+        self.agents.len()
+    }
+
+    pub fn observation_size(&self) -> usize {
+        self.config.observation.size()
+    }
+
+    // start a new episode, the same stadium and seed always play the same episode
+    pub fn reset(&mut self, stadium: Stadium, seed: u64) -> Vec<Vec<f32>> {
+        let mut game = HeadlessMatch::with_settings(
+            stadium,
+            self.config.lobby.clone(),
+            self.config.bots.clone(),
+            seed,
+            self.config.match_settings,
+        );
+
+        let snapshot = game.snapshot();
+        self.game = Some(game);
+        self.ticks = 0;
+        let observations = self.observations(&snapshot);
+        self.snapshot = Some(snapshot);
+        observations
+    }
+
+    fn observations(&self, snapshot: &GameSnapshot) -> Vec<Vec<f32>> {
+        self.agents
+            .iter()
+            .map(|&id| self.config.observation.observe(snapshot, id))
+            .collect()
+    }
+
+    // the observations of the agents written one after the other, for batched environments
+    pub fn observe_into(&self, out: &mut [f32]) {
+        let Some(snapshot) = &self.snapshot else {
+            out.fill(0.0);
+            return;
+        };
+        let size = self.observation_size();
+        for (&id, out) in self.agents.iter().zip(out.chunks_mut(size)) {
+            self.config.observation.observe_into(snapshot, id, out);
+        }
+    }
+
+    // the actions are the inputs of the agents, in agent order
+    pub fn step(&mut self, actions: &[PlayerInput]) -> Step {
+        assert_eq!(actions.len(), self.agents.len(), "one action per agent");
+        let (Some(game), Some(previous)) = (&mut self.game, self.snapshot.take()) else {
+            panic!("step called before reset");
+        };
+
+        for (&id, action) in self.agents.iter().zip(actions) {
+            game.set_input(id, *action);
+        }
+        for _ in 0..self.config.frame_skip.max(1) {
+            game.tick();
+            self.ticks += 1;
+            if game.match_state().phase == MatchPhase::Ended {
+                break;
+            }
+        }
+
+        let snapshot = game.snapshot();
+        let ended = snapshot.phase == MatchPhase::Ended;
+        let truncated = !ended && self.ticks >= self.config.max_ticks;
+        let rewards = self
+            .agents
+            .iter()
+            .map(|&id| self.config.reward.reward(&previous, &snapshot, id))
+            .collect();
+        let step = Step {
+            observations: self.observations(&snapshot),
+            rewards,
+            done: ended || truncated,
+            info: StepInfo {
+                ticks: self.ticks,
+                red_score: snapshot.red_score,
+                blue_score: snapshot.blue_score,
+                ended,
+                truncated,
+            },
+        };
+        self.snapshot = Some(snapshot);
+        step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lobby::LobbySlot, test_utils::classic};
+
+    fn agent(team: Team) -> LobbySlot {
+        LobbySlot {
+            controller: Controller::External,
+            ..LobbySlot::local("Agent", team)
+        }
+    }
+
+    #[test]
+    fn observations_are_symmetric() {
+        let mut env = Environment::new(EnvConfig {
+            lobby: Lobby {
+                slots: vec![agent(Team::Red), agent(Team::Blue)],
+            },
+            ..EnvConfig::default()
+        });
+        let observations = env.reset(classic(), 0);
+        assert_eq!(observations.len(), 2);
+        assert_eq!(observations[0].len(), env.observation_size());
+        // both teams see the same stadium from their own goal, except for the kickoff
+        let size = env.observation_size();
+        assert_eq!(observations[0][..size - 1], observations[1][..size - 1]);
+        assert!(observations[0][0] > 0.0 && observations[0][0] < 0.5);
+    }
+
+    #[test]
+    fn episodes_are_reproducible() {
+        let config = EnvConfig {
+            lobby: Lobby {
+                slots: vec![
+                    agent(Team::Red),
+                    LobbySlot::bot("Chaser (easy)", Team::Blue),
+                ],
+            },
+            reward: Arc::new(WeightedReward(vec![
+                (1.0, Arc::new(GoalReward)),
+                (0.1, Arc::new(BallProgressReward)),
+            ])),
+            frame_skip: 4,
+            max_ticks: 2000,
+            ..EnvConfig::default()
+        };
+        let run = || {
+            let mut env = Environment::new(config.clone());
+            env.reset(classic(), 3);
+            let action = PlayerInput {
+                direction: DVec2::X,
+                kick: true,
+            };
+            let mut total = 0.0;
+            loop {
+                let step = env.step(&[action]);
+                total += step.rewards[0];
+                if step.done {
+                    return (total, step.info);
+                }
+            }
+        };
+
+        let (total, info) = run();
+        assert!(info.ticks <= 2000);
+        assert!(info.ended || info.truncated);
+        assert_eq!((total, info), run());
+    }
+}
@@ -0,0 +1,480 @@
+use bevy::{ecs::query::Has, math::DVec2, prelude::*};
+
+use crate::{
+    parser::{
+        ball_physics::BallComp,
+        disc::{DiscComp, SpawnPosition, Velocity},
+        goal::GoalComp,
+        stadium::{KickoffReset, StadiumComp},
+        utils::{Collision, CollisionFlag, Position, Team},
+    },
+    physics::PhysicsSet,
+    player::{Player, PLAYER_MASK},
+    rng::Rng,
+    AppState, PauseState,
+};
+
+// HaxBall runs at 60 ticks per second
+pub const TICKS_PER_SECOND: u32 = 60;
+const GOAL_CELEBRATION_TICKS: u32 = 150;
+// vertical gap between players of the same team when the stadium has no spawn points
+const SPAWN_GAP: f64 = 55.0;
+
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GoalEvent>()
+            .add_event::<RestartMatch>()
+            .init_resource::<MatchSeed>()
+            .init_resource::<MatchSettings>()
+            .add_systems(OnEnter(AppState::InGame), setup_match)
+            .add_systems(OnExit(AppState::InGame), cleanup_match)
+            .add_systems(Update, restart_match.run_if(in_state(AppState::InGame)))
+            .add_systems(
+                FixedUpdate,
+                (
+                    apply_kickoff_barriers.before(PhysicsSet),
+                    (check_goals, update_match, reset_positions)
+                        .chain()
+                        .in_set(MatchSet)
+                        .after(PhysicsSet),
+                )
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(in_state(PauseState::Running)),
+            );
+    }
+}
+
+// goals, score and kickoff resets, after the physics of the tick
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MatchSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchPhase {
+    // waiting for the given team to touch the ball
+    Kickoff(Team),
+    Playing,
+    // a goal was scored, positions are reset once the timer runs out
+    GoalScored { scorer: Team, ticks_left: u32 },
+    Ended,
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct MatchState {
+    pub red_score: u32,
+    pub blue_score: u32,
+    pub ticks: u32,
+    // in minutes, 0 means no limit
+    pub time_limit: u32,
+    // 0 means no limit
+    pub score_limit: u32,
+    pub overtime: bool,
+    pub phase: MatchPhase,
+    // set when positions must be reset at the end of the tick
+    pub reset_pending: bool,
+}
+
+// the rules of the next match, read when it starts
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchSettings {
+    // in minutes, 0 means no limit
+    pub time_limit: u32,
+    // 0 means no limit
+    pub score_limit: u32,
+    // the players of the lobby are dealt to the teams at random, the team sizes are kept
+    pub shuffle_teams: bool,
+    // the ball is put anywhere on the center line inside of the kickoff circle
+    pub random_ball: bool,
+}
+
+impl Default for MatchSettings {
+    fn default() -> Self {
+        MatchSettings {
+            time_limit: 3,
+            score_limit: 3,
+            shuffle_teams: false,
+            random_ball: false,
+        }
+    }
+}
+
+impl Default for MatchState {
+    fn default() -> Self {
+        MatchState::new(&MatchSettings::default())
+    }
+}
+
+impl MatchState {
+    pub fn new(settings: &MatchSettings) -> MatchState {
+        MatchState {
+            red_score: 0,
+            blue_score: 0,
+            ticks: 0,
+            time_limit: settings.time_limit,
+            score_limit: settings.score_limit,
+            overtime: false,
+            phase: MatchPhase::Kickoff(Team::Red),
+            reset_pending: true,
+        }
+    }
+
+    pub fn seconds(&self) -> u32 {
+        self.ticks / TICKS_PER_SECOND
+    }
+
+    pub fn is_time_up(&self) -> bool {
+        self.time_limit > 0 && self.ticks >= self.time_limit * 60 * TICKS_PER_SECOND
+    }
+
+    pub fn winner(&self) -> Option<Team> {
+        match self.red_score.cmp(&self.blue_score) {
+            std::cmp::Ordering::Greater => Some(Team::Red),
+            std::cmp::Ordering::Less => Some(Team::Blue),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    fn add_goal(&mut self, scorer: Team) {
+        match scorer {
+            Team::Red => self.red_score += 1,
+            Team::Blue => self.blue_score += 1,
+            Team::Spectator => {}
+        }
+    }
+
+    fn is_score_limit_reached(&self) -> bool {
+        self.score_limit > 0
+            && (self.red_score >= self.score_limit || self.blue_score >= self.score_limit)
+    }
+
+    // one tick of the rules, after the physics
+    // `goal` is the team given a goal during the tick, goals outside of play are ignored
+    pub fn update(&mut self, goal: Option<Team>, ball_moved: bool) {
+        match self.phase {
+            MatchPhase::Kickoff(_) => {
+                // the kickoff ends as soon as the ball moves
+                if ball_moved {
+                    self.phase = MatchPhase::Playing;
+                }
+            }
+            MatchPhase::Playing => {
+                if let Some(scorer) = goal {
+                    self.add_goal(scorer);
+                    self.phase = MatchPhase::GoalScored {
+                        scorer,
+                        ticks_left: GOAL_CELEBRATION_TICKS,
+                    };
+                    return;
+                }
+
+                self.ticks += 1;
+                if self.is_time_up() {
+                    if self.winner().is_some() {
+                        self.phase = MatchPhase::Ended;
+                    } else {
+                        self.overtime = true;
+                    }
+                }
+            }
+            MatchPhase::GoalScored { scorer, ticks_left } => {
+                if ticks_left > 0 {
+                    self.phase = MatchPhase::GoalScored {
+                        scorer,
+                        ticks_left: ticks_left - 1,
+                    };
+                } else if self.is_score_limit_reached() || self.overtime {
+                    self.phase = MatchPhase::Ended;
+                } else {
+                    self.phase = MatchPhase::Kickoff(scorer.opponent());
+                    self.reset_pending = true;
+                }
+            }
+            MatchPhase::Ended => {}
+        }
+    }
+}
+
+// the seed of every random decision of the match, the same seed plays the same match
+// this is what replays and network handshakes have to share
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct MatchSeed(pub u64);
+
+// the only source of randomness during a match, seeded with the MatchSeed when it starts
+// draws must happen in a deterministic order, in FixedUpdate or in ordered systems
+#[derive(Resource, Debug, Clone)]
+pub struct MatchRng(pub Rng);
+
+// play the match again from the kickoff, with a new seed
+// the stats and the recording start over as well
+#[derive(Event, Debug, Clone, Copy)]
+pub struct RestartMatch;
+
+// sent when the ball crosses a goal line, `team` is the team that scored
+#[derive(Event, Debug, Clone, Copy)]
+pub struct GoalEvent {
+    pub team: Team,
+}
+
+// position of the n-th player of a team at kickoff
+pub fn spawn_position(
+    spawn_points: &[DVec2],
+    spawn_distance: f64,
+    team: Team,
+    slot: usize,
+) -> DVec2 {
+    if let Some(point) = spawn_points.get(slot).or(spawn_points.last()) {
+        return *point;
+    }
+
+    let x = match team {
+        Team::Blue => spawn_distance,
+        _ => -spawn_distance,
+    };
+    // 0, 55, -55, 110, -110...
+    let row = slot.div_ceil(2) as f64;
+    let y = if slot % 2 == 1 { row } else { -row } * SPAWN_GAP;
+    DVec2::new(x, y)
+}
+
+fn setup_match(mut commands: Commands, seed: Res<MatchSeed>, settings: Res<MatchSettings>) {
+    commands.insert_resource(MatchState::new(&settings));
+    commands.insert_resource(MatchRng(Rng::new(seed.0)));
+}
+
+// the players are despawned, they are spawned again with the new MatchRng
+fn restart_match(
+    mut commands: Commands,
+    mut restarts: EventReader<RestartMatch>,
+    mut seed: ResMut<MatchSeed>,
+    settings: Res<MatchSettings>,
+    players: Query<Entity, With<Player>>,
+) {
+    if restarts.is_empty() {
+        return;
+    }
+    restarts.clear();
+
+    // the next seed only depends on the previous one, the restarts can be replayed
+    seed.0 = Rng::new(seed.0).next_u64();
+    commands.insert_resource(MatchState::new(&settings));
+    commands.insert_resource(MatchRng(Rng::new(seed.0)));
+    for entity in players.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn cleanup_match(mut commands: Commands) {
+    commands.remove_resource::<MatchState>();
+    commands.remove_resource::<MatchRng>();
+}
+
+fn segments_intersect(a0: DVec2, a1: DVec2, b0: DVec2, b1: DVec2) -> bool {
+    let cross = |o: DVec2, p: DVec2, q: DVec2| (p - o).perp_dot(q - o);
+    let d1 = cross(b0, b1, a0);
+    let d2 = cross(b0, b1, a1);
+    let d3 = cross(a0, a1, b0);
+    let d4 = cross(a0, a1, b1);
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+}
+
+// the kickoff barriers of the team taking the kickoff apply to every player,
+// they close the center circle on the side of the other team only
+fn apply_kickoff_barriers(
+    match_state: Res<MatchState>,
+    mut players: Query<&mut Collision, With<Player>>,
+) {
+    let kickoff_flag = match match_state.phase {
+        MatchPhase::Kickoff(team) => team.kickoff_flag(),
+        _ => CollisionFlag::empty(),
+    };
+
+    for mut collision in players.iter_mut() {
+        collision.mask = PLAYER_MASK | kickoff_flag;
+    }
+}
+
+fn check_goals(
+    match_state: Res<MatchState>,
+    balls: Query<&Position, With<BallComp>>,
+    goals: Query<&GoalComp>,
+    mut previous: Local<Option<DVec2>>,
+    mut goal_events: EventWriter<GoalEvent>,
+) {
+    let Ok(ball_pos) = balls.get_single() else {
+        return;
+    };
+    if match_state.phase != MatchPhase::Playing {
+        *previous = None;
+        return;
+    }
+
+    if let Some(prev_pos) = *previous {
+        for goal in goals.iter() {
+            if segments_intersect(prev_pos, ball_pos.0, goal.p0, goal.p1) {
+                // the goal belongs to the team defending it
+                goal_events.send(GoalEvent {
+                    team: goal.team.opponent(),
+                });
+                break;
+            }
+        }
+    }
+    *previous = Some(ball_pos.0);
+}
+
+fn update_match(
+    mut match_state: ResMut<MatchState>,
+    mut goal_events: EventReader<GoalEvent>,
+    balls: Query<&Velocity, With<BallComp>>,
+) {
+    let goal = goal_events.iter().next().map(|goal| goal.team);
+    let ball_moved = balls.iter().any(|velocity| velocity.0 != DVec2::ZERO);
+    match_state.update(goal, ball_moved);
+    goal_events.clear();
+}
+
+type ResetDisc = (
+    &'static SpawnPosition,
+    &'static mut Position,
+    &'static mut Velocity,
+    Has<BallComp>,
+);
+
+fn reset_positions(
+    mut match_state: ResMut<MatchState>,
+    settings: Res<MatchSettings>,
+    mut rng: ResMut<MatchRng>,
+    stadium: Query<&StadiumComp>,
+    mut players: Query<(&Player, &mut Position, &mut Velocity)>,
+    mut discs: Query<ResetDisc, (With<DiscComp>, Without<Player>)>,
+) {
+    if !match_state.reset_pending {
+        return;
+    }
+    let Ok(stadium) = stadium.get_single() else {
+        return;
+    };
+    match_state.reset_pending = false;
+
+    for (spawn, mut position, mut velocity, is_ball) in discs.iter_mut() {
+        if is_ball || matches!(stadium.kick_off_reset, KickoffReset::Full) {
+            position.0 = spawn.0;
+            velocity.0 = DVec2::ZERO;
+        }
+        if is_ball && settings.random_ball {
+            position.0.y += rng.0.spread(stadium.kick_off_radius);
+        }
+    }
+
+    let mut sorted_players = players.iter_mut().collect::<Vec<_>>();
+    sorted_players.sort_by_key(|(player, _, _)| player.id);
+    let (mut red_slot, mut blue_slot) = (0, 0);
+    for (player, mut position, mut velocity) in sorted_players {
+        let (spawn_points, slot) = match player.team {
+            Team::Red => (&stadium.red_spawn_points, &mut red_slot),
+            Team::Blue => (&stadium.blue_spawn_points, &mut blue_slot),
+            Team::Spectator => continue,
+        };
+        position.0 = spawn_position(spawn_points, stadium.spawn_distance, player.team, *slot);
+        velocity.0 = DVec2::ZERO;
+        *slot += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playing(time_limit: u32, score_limit: u32) -> MatchState {
+        let mut match_state = MatchState::new(&MatchSettings {
+            time_limit,
+            score_limit,
+            ..default()
+        });
+        match_state.update(None, true);
+        assert_eq!(match_state.phase, MatchPhase::Playing);
+        match_state
+    }
+
+    // the goal, then the celebration until the next phase
+    fn score(match_state: &mut MatchState, scorer: Team) {
+        match_state.update(Some(scorer), false);
+        for _ in 0..=GOAL_CELEBRATION_TICKS {
+            assert!(matches!(match_state.phase, MatchPhase::GoalScored { .. }));
+            match_state.update(None, false);
+        }
+    }
+
+    #[test]
+    fn goals_lead_to_the_other_kickoff() {
+        let mut match_state = playing(3, 3);
+        match_state.update(None, false);
+        assert_eq!(match_state.ticks, 1);
+
+        match_state.reset_pending = false;
+        match_state.update(Some(Team::Red), false);
+        assert_eq!(match_state.red_score, 1);
+        assert_eq!(
+            match_state.phase,
+            MatchPhase::GoalScored {
+                scorer: Team::Red,
+                ticks_left: GOAL_CELEBRATION_TICKS
+            }
+        );
+        // the clock stops during the celebration
+        for _ in 0..GOAL_CELEBRATION_TICKS {
+            match_state.update(None, true);
+        }
+        assert_eq!(match_state.ticks, 1);
+        match_state.update(None, false);
+        assert_eq!(match_state.phase, MatchPhase::Kickoff(Team::Blue));
+        assert!(match_state.reset_pending);
+
+        // waits for the ball to move
+        match_state.update(None, false);
+        assert_eq!(match_state.phase, MatchPhase::Kickoff(Team::Blue));
+        match_state.update(None, true);
+        assert_eq!(match_state.phase, MatchPhase::Playing);
+    }
+
+    #[test]
+    fn score_limit_ends_the_match() {
+        let mut match_state = playing(0, 2);
+        score(&mut match_state, Team::Blue);
+        assert_eq!(match_state.phase, MatchPhase::Kickoff(Team::Red));
+        match_state.update(None, true);
+        score(&mut match_state, Team::Blue);
+        assert_eq!(match_state.phase, MatchPhase::Ended);
+        assert_eq!(match_state.winner(), Some(Team::Blue));
+
+        // nothing happens once ended
+        match_state.update(Some(Team::Red), true);
+        assert_eq!((match_state.red_score, match_state.blue_score), (0, 2));
+    }
+
+    #[test]
+    fn time_limit_ends_the_match_or_goes_to_overtime() {
+        let limit = 60 * TICKS_PER_SECOND;
+
+        let mut match_state = playing(1, 0);
+        score(&mut match_state, Team::Red);
+        match_state.update(None, true);
+        for _ in 0..limit {
+            match_state.update(None, false);
+        }
+        assert_eq!(match_state.phase, MatchPhase::Ended);
+        assert_eq!(match_state.winner(), Some(Team::Red));
+
+        // a draw goes to overtime, the next goal wins
+        let mut match_state = playing(1, 0);
+        for _ in 0..limit + 100 {
+            match_state.update(None, false);
+        }
+        assert!(match_state.overtime);
+        assert_eq!(match_state.phase, MatchPhase::Playing);
+        score(&mut match_state, Team::Blue);
+        assert_eq!(match_state.phase, MatchPhase::Ended);
+        assert_eq!(match_state.winner(), Some(Team::Blue));
+    }
+}
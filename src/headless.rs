@@ -0,0 +1,379 @@
+use bevy::{
+    ecs::{schedule::ExecutorKind, system::SystemState},
+    prelude::*,
+};
+
+use crate::{
+    bot::{BotPlugin, BotRegistry, GameSnapshot, SnapshotQuery},
+    game::{GamePlugin, MatchPhase, MatchSeed, MatchSettings, MatchState, RestartMatch},
+    lobby::Lobby,
+    parser::stadium::Stadium,
+    physics::PhysicsPlugin,
+    player::{Player, PlayerInput, PlayerPlugin},
+    recording::{MatchRecording, RecordingPlugin},
+    stats::{MatchStats, StatsPlugin},
+    AppState, PauseState,
+};
+
+// the stadium of a headless match, there are no assets without a window
+#[derive(Resource)]
+pub struct HeadlessStadium(pub Stadium);
+
+fn spawn_headless_stadium(mut commands: Commands, stadium: Res<HeadlessStadium>) {
+    stadium.0.spawn(&mut commands);
+}
+
+// a match simulated without window, renderer or real time, one fixed tick at a time
+// used by the command-line tools and to train bots
+pub struct HeadlessMatch {
+    app: App,
+    snapshot_state: SystemState<SnapshotQuery<'static, 'static>>,
+}
+
+impl HeadlessMatch {
+    pub fn new(stadium: Stadium, lobby: Lobby, bots: BotRegistry, seed: u64) -> HeadlessMatch {
+        HeadlessMatch::with_settings(stadium, lobby, bots, seed, MatchSettings::default())
+    }
+
+    pub fn with_settings(
+        stadium: Stadium,
+        lobby: Lobby,
+        bots: BotRegistry,
+        seed: u64,
+        settings: MatchSettings,
+    ) -> HeadlessMatch {
+        let mut app = App::new();
+        app.add_state::<AppState>()
+            .add_state::<PauseState>()
+            .insert_resource(HeadlessStadium(stadium))
+            .insert_resource(lobby)
+            .insert_resource(bots)
+            .add_plugins((
+                PhysicsPlugin,
+                GamePlugin,
+                PlayerPlugin,
+                BotPlugin,
+                StatsPlugin,
+                RecordingPlugin,
+            ))
+            .insert_resource(MatchSeed(seed))
+            .insert_resource(settings)
+            .add_systems(OnEnter(AppState::InGame), spawn_headless_stadium);
+        // the ticks must not depend on how systems are scheduled on threads
+        app.edit_schedule(FixedUpdate, |schedule| {
+            schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        });
+
+        // enter the game, the stadium then the players are spawned
+        app.world
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::InGame);
+        app.update();
+        let snapshot_state = SystemState::new(&mut app.world);
+        HeadlessMatch {
+            app,
+            snapshot_state,
+        }
+    }
+
+    // advance the match by one tick, 1/60 of a second
+    pub fn tick(&mut self) {
+        // swaps the event buffers, events are read during the next tick at the latest
+        self.app.world.run_schedule(First);
+        self.app.world.run_schedule(FixedUpdate);
+    }
+
+    // tick until the match ends, returns the number of ticks simulated
+    pub fn run_until_end(&mut self, max_ticks: u32) -> u32 {
+        for tick in 0..max_ticks {
+            if self.match_state().phase == MatchPhase::Ended {
+                return tick;
+            }
+            self.tick();
+        }
+        max_ticks
+    }
+
+    // like the pause menu, the players are spawned again the update after the restart
+    pub fn restart(&mut self) {
+        self.app.world.send_event(RestartMatch);
+        self.app.world.run_schedule(Update);
+        self.app.world.run_schedule(Update);
+    }
+
+    pub fn match_state(&self) -> &MatchState {
+        self.app.world.resource::<MatchState>()
+    }
+
+    pub fn stats(&self) -> &MatchStats {
+        self.app.world.resource::<MatchStats>()
+    }
+
+    pub fn recording(&self) -> &MatchRecording {
+        self.app.world.resource::<MatchRecording>()
+    }
+
+    // the match as seen by bots
+    pub fn snapshot(&mut self) -> GameSnapshot {
+        self.snapshot_state.get(&self.app.world).snapshot()
+    }
+
+    // the input of a player for the next ticks, bots overwrite their own
+    pub fn set_input(&mut self, id: usize, input: PlayerInput) {
+        let mut players = self.app.world.query::<(&Player, &mut PlayerInput)>();
+        for (player, mut player_input) in players.iter_mut(&mut self.app.world) {
+            if player.id == id {
+                *player_input = input;
+            }
+        }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.app.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.app.world
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::DVec2;
+
+    use super::*;
+    use crate::{
+        bot::Bot,
+        game::spawn_position,
+        lobby::{Controller, LobbySlot},
+        parser::{
+            ball_physics::BallComp,
+            disc::Velocity,
+            utils::{Position, Team},
+        },
+        player::PlayerInput,
+        test_utils::classic,
+    };
+
+    struct RunRight;
+
+    impl Bot for RunRight {
+        fn think(&mut self, _snapshot: &GameSnapshot, _me: usize) -> PlayerInput {
+            PlayerInput {
+                direction: DVec2::X,
+                kick: false,
+            }
+        }
+    }
+
+    struct Chase;
+
+    impl Bot for Chase {
+        fn think(&mut self, snapshot: &GameSnapshot, me: usize) -> PlayerInput {
+            let me = snapshot.player(me).unwrap();
+            PlayerInput {
+                direction: (snapshot.ball.position - me.disc.position).normalize_or_zero(),
+                kick: false,
+            }
+        }
+    }
+
+    // one chaser and one idle player, red kicks off
+    fn kickoff(chaser: Team) -> HeadlessMatch {
+        let mut bots = BotRegistry::default();
+        bots.register("Chase", || Box::new(Chase));
+        let (red, blue) = match chaser {
+            Team::Red => ("Chase", "Idle"),
+            _ => ("Idle", "Chase"),
+        };
+        let lobby = Lobby {
+            slots: vec![
+                LobbySlot::bot(red, Team::Red),
+                LobbySlot::bot(blue, Team::Blue),
+            ],
+        };
+        let mut game = HeadlessMatch::new(classic(), lobby, bots, 0);
+        for _ in 0..300 {
+            game.tick();
+        }
+        game
+    }
+
+    #[test]
+    fn bots_control_their_players() {
+        let mut bots = BotRegistry::default();
+        bots.register("RunRight", || Box::new(RunRight));
+        let lobby = Lobby {
+            slots: vec![
+                LobbySlot::bot("RunRight", Team::Red),
+                LobbySlot::bot("Idle", Team::Blue),
+                LobbySlot::bot("Idle", Team::Spectator),
+            ],
+        };
+        let mut game = HeadlessMatch::new(classic(), lobby, bots, 0);
+
+        let start = game.snapshot();
+        assert_eq!(start.players.len(), 2);
+        assert_eq!(start.players[0].team, Team::Red);
+        assert_eq!(start.players[1].team, Team::Blue);
+        for _ in 0..30 {
+            game.tick();
+        }
+        let end = game.snapshot();
+        assert!(end.players[0].disc.position.x > start.players[0].disc.position.x);
+        assert_eq!(end.players[1].disc.position, start.players[1].disc.position);
+        assert_eq!(end.stadium.goals.len(), 2);
+    }
+
+    #[test]
+    fn recordings_identify_the_match() {
+        let lobby = Lobby {
+            slots: vec![LobbySlot::bot("Idle", Team::Red)],
+        };
+        let mut game = HeadlessMatch::new(classic(), lobby, BotRegistry::default(), 7);
+        game.tick();
+        assert_eq!(game.recording().seed, 7);
+        assert_eq!(game.recording().hash, classic().content_hash());
+    }
+
+    #[test]
+    fn restarts_start_over() {
+        let mut game = kickoff(Team::Red);
+        assert_eq!(game.match_state().phase, MatchPhase::Playing);
+        assert!(!game.recording().ball.is_empty());
+        let seed = game.recording().seed;
+
+        game.restart();
+        assert_eq!(game.match_state().ticks, 0);
+        assert_eq!(game.match_state().phase, MatchPhase::Kickoff(Team::Red));
+        assert!(game.recording().ball.is_empty());
+        let snapshot = game.snapshot();
+        assert_eq!(snapshot.players.len(), 2);
+        let stadium = classic();
+        let spawn = spawn_position(
+            &stadium.red_spawn_points,
+            stadium.spawn_distance,
+            Team::Red,
+            0,
+        );
+        assert_eq!(snapshot.players[0].disc.position, spawn);
+
+        game.tick();
+        assert_eq!(game.stats().player(0).unwrap().kicks, 0);
+        assert_ne!(game.recording().seed, seed);
+    }
+
+    // the teams and the ball position at kickoff
+    fn random_kickoff(seed: u64) -> (Vec<Team>, DVec2) {
+        let lobby = Lobby {
+            slots: vec![
+                LobbySlot::bot("Idle", Team::Red),
+                LobbySlot::bot("Idle", Team::Red),
+                LobbySlot::bot("Idle", Team::Spectator),
+                LobbySlot::bot("Idle", Team::Blue),
+                LobbySlot::bot("Idle", Team::Blue),
+            ],
+        };
+        let settings = MatchSettings {
+            shuffle_teams: true,
+            random_ball: true,
+            ..default()
+        };
+        let mut game =
+            HeadlessMatch::with_settings(classic(), lobby, BotRegistry::default(), seed, settings);
+        game.tick();
+        let snapshot = game.snapshot();
+        let teams = snapshot.players.iter().map(|player| player.team).collect();
+        (teams, snapshot.ball.position)
+    }
+
+    #[test]
+    fn shuffled_teams_and_random_ball_follow_the_seed() {
+        let kickoffs = (0..20).map(random_kickoff).collect::<Vec<_>>();
+        assert_eq!(kickoffs[3], random_kickoff(3));
+
+        let radius = classic().bg.kick_off_radius;
+        for (teams, ball) in &kickoffs {
+            assert_eq!(teams.iter().filter(|team| **team == Team::Red).count(), 2);
+            assert_eq!(teams.iter().filter(|team| **team == Team::Blue).count(), 2);
+            assert_eq!(ball.x, 0.0);
+            assert!(ball.y.abs() <= radius);
+        }
+        assert!(kickoffs.iter().any(|(teams, _)| teams[0] == Team::Blue));
+        assert!(kickoffs.iter().any(|(_, ball)| ball.y != 0.0));
+    }
+
+    #[test]
+    fn kickoff_team_can_reach_the_ball() {
+        let game = kickoff(Team::Red);
+        assert_eq!(game.match_state().phase, MatchPhase::Playing);
+    }
+
+    #[test]
+    fn other_team_waits_outside_the_center_circle() {
+        let mut game = kickoff(Team::Blue);
+        assert_eq!(game.match_state().phase, MatchPhase::Kickoff(Team::Red));
+        let blue = game.snapshot().players[1];
+        // the kickoff radius of the classic stadium
+        assert!(blue.disc.position.length() > 75.0);
+    }
+
+    // the player stopped just left of the ball, close enough to kick it
+    fn ready_to_kick(game: &mut HeadlessMatch) {
+        let snapshot = game.snapshot();
+        let contact = snapshot.players[0].disc.radius + snapshot.ball.radius + 1.0;
+        let world = game.world_mut();
+        let mut players = world.query_filtered::<(&mut Position, &mut Velocity), With<Player>>();
+        for (mut position, mut velocity) in players.iter_mut(world) {
+            position.0 = DVec2::new(-contact, 0.0);
+            velocity.0 = DVec2::ZERO;
+        }
+        let mut balls = world.query_filtered::<(&mut Position, &mut Velocity), With<BallComp>>();
+        for (mut position, mut velocity) in balls.iter_mut(world) {
+            position.0 = DVec2::ZERO;
+            velocity.0 = DVec2::ZERO;
+        }
+    }
+
+    #[test]
+    fn holding_kick_kicks_once() {
+        let lobby = Lobby {
+            slots: vec![LobbySlot {
+                controller: Controller::External,
+                ..LobbySlot::local("Kicker", Team::Red)
+            }],
+        };
+        let mut game = HeadlessMatch::new(classic(), lobby, BotRegistry::default(), 0);
+        let id = game.snapshot().players[0].id;
+        let kicks = |game: &HeadlessMatch| game.stats().player(id).unwrap().kicks;
+        let kick = |kick: bool| PlayerInput {
+            direction: DVec2::ZERO,
+            kick,
+        };
+
+        // positions are reset at the end of the first tick
+        game.tick();
+        game.set_input(id, kick(true));
+        ready_to_kick(&mut game);
+        game.tick();
+        // the ball goes away from the player, the player is pushed back
+        let snapshot = game.snapshot();
+        assert!(snapshot.ball.velocity.x > 0.0);
+        assert!(snapshot.players[0].disc.velocity.x <= 0.0);
+
+        // still held, the ball is not kicked again
+        for _ in 0..10 {
+            ready_to_kick(&mut game);
+            game.tick();
+        }
+        assert_eq!(kicks(&game), 1);
+        assert_eq!(game.snapshot().ball.velocity, DVec2::ZERO);
+
+        game.set_input(id, kick(false));
+        game.tick();
+        game.set_input(id, kick(true));
+        ready_to_kick(&mut game);
+        game.tick();
+        assert_eq!(kicks(&game), 2);
+    }
+}
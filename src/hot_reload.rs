@@ -0,0 +1,248 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use bevy::{asset::FileAssetIo, math::DVec2, prelude::*};
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    game::spawn_position,
+    menu::{DataAssets, StadiumAsset},
+    parser::{
+        disc::{DiscComp, Velocity},
+        stadium::{parse_stadium_file, Stadium, StadiumComp, StadiumRaw},
+        utils::{Position, Team},
+    },
+    player::Player,
+    renderer::GameCamera,
+    AppState,
+};
+
+const POLL_INTERVAL_SECS: f32 = 0.5;
+const TOAST_SECS: f32 = 6.0;
+
+// watch the stadium file being played and respawn the stadium whenever it is saved
+pub struct HotReloadPlugin;
+
+impl Plugin for HotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<StadiumReloaded>()
+            .add_systems(OnEnter(AppState::InGame), watch_stadium)
+            .add_systems(OnExit(AppState::InGame), unwatch_stadium)
+            .add_systems(
+                Update,
+                (
+                    poll_stadium_file,
+                    keep_discs,
+                    respawn_stadium,
+                    restore_discs,
+                    reload_toast,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+#[derive(Resource)]
+struct WatchedStadium {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    timer: Timer,
+}
+
+#[derive(Event)]
+struct StadiumReloaded(Stadium, StadiumRaw);
+
+// the discs of the stadium before it was reloaded, by index, with their position and velocity
+#[derive(Resource)]
+struct KeptDiscs(Vec<(usize, DVec2, DVec2)>);
+
+#[derive(Resource)]
+struct ReloadToast {
+    message: String,
+    timer: Timer,
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn watch_stadium(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    data_assets: Res<DataAssets>,
+) {
+    let Some(asset_path) = asset_server.get_handle_path(&data_assets.stadium) else {
+        return;
+    };
+    let path = FileAssetIo::get_base_path()
+        .join("assets")
+        .join(asset_path.path());
+
+    commands.insert_resource(WatchedStadium {
+        modified: modified_time(&path),
+        path,
+        timer: Timer::from_seconds(POLL_INTERVAL_SECS, TimerMode::Repeating),
+    });
+}
+
+fn unwatch_stadium(mut commands: Commands) {
+    commands.remove_resource::<WatchedStadium>();
+    commands.remove_resource::<ReloadToast>();
+}
+
+fn poll_stadium_file(
+    mut commands: Commands,
+    time: Res<Time>,
+    watched: Option<ResMut<WatchedStadium>>,
+    mut reloaded: EventWriter<StadiumReloaded>,
+) {
+    let Some(mut watched) = watched else {
+        return;
+    };
+    if !watched.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let modified = modified_time(&watched.path);
+    if modified == watched.modified {
+        return;
+    }
+    watched.modified = modified;
+
+    match std::fs::read(&watched.path)
+        .map_err(bevy::asset::Error::from)
+        .and_then(|bytes| parse_stadium_file(&watched.path, &bytes))
+    {
+        Ok((stadium, stadium_raw)) => {
+            reloaded.send(StadiumReloaded(stadium, stadium_raw));
+            commands.insert_resource(ReloadToast {
+                message: format!("Reloaded {}", watched.path.display()),
+                timer: Timer::from_seconds(TOAST_SECS / 3.0, TimerMode::Once),
+            });
+        }
+        Err(e) => {
+            // keep playing on the previous version of the stadium
+            commands.insert_resource(ReloadToast {
+                message: format!("Could not reload {}: {}", watched.path.display(), e),
+                timer: Timer::from_seconds(TOAST_SECS, TimerMode::Once),
+            });
+        }
+    }
+}
+
+// the ball and the discs keep moving when only their properties changed
+fn keep_discs(
+    mut commands: Commands,
+    mut reloaded: EventReader<StadiumReloaded>,
+    discs: Query<(&DiscComp, &Position, &Velocity), Without<Player>>,
+) {
+    let Some(StadiumReloaded(stadium, _)) = reloaded.iter().last() else {
+        return;
+    };
+    let kept = discs
+        .iter()
+        .map(|(disc, position, velocity)| (disc.index, position.0, velocity.0))
+        .collect::<Vec<_>>();
+    // the ball is not in the discs of the stadium
+    if kept.len() == stadium.discs.len() + 1 {
+        commands.insert_resource(KeptDiscs(kept));
+    }
+}
+
+fn respawn_stadium(
+    mut commands: Commands,
+    mut reloaded: EventReader<StadiumReloaded>,
+    data_assets: Res<DataAssets>,
+    mut stadium_assets: ResMut<Assets<StadiumAsset>>,
+    stadiums: Query<Entity, With<StadiumComp>>,
+    mut cameras: Query<&mut OrthographicProjection, With<GameCamera>>,
+    mut players: Query<(&Player, &mut Position)>,
+) {
+    let Some(StadiumReloaded(stadium, stadium_raw)) = reloaded.iter().last() else {
+        return;
+    };
+
+    for entity in stadiums.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    stadium.spawn(&mut commands);
+
+    for mut projection in cameras.iter_mut() {
+        projection.scale = stadium.width as f32;
+    }
+
+    // players keep their position unless it is now outside of the stadium
+    // a stadium without a size has no outside
+    let sized = stadium.width > 0.0 && stadium.height > 0.0;
+    let mut slots = [0, 0];
+    for (player, mut position) in players.iter_mut() {
+        if !sized || (position.0.x.abs() <= stadium.width && position.0.y.abs() <= stadium.height) {
+            continue;
+        }
+        let (spawn_points, slot) = match player.team {
+            Team::Red => (&stadium.red_spawn_points, &mut slots[0]),
+            Team::Blue => (&stadium.blue_spawn_points, &mut slots[1]),
+            Team::Spectator => continue,
+        };
+        position.0 = spawn_position(spawn_points, stadium.spawn_distance, player.team, *slot);
+        *slot += 1;
+    }
+
+    if let Some(asset) = stadium_assets.get_mut(&data_assets.stadium) {
+        asset.0 = stadium.clone();
+        asset.1 = stadium_raw.clone();
+    }
+}
+
+type RespawnedDisc = (
+    &'static DiscComp,
+    &'static mut Position,
+    &'static mut Velocity,
+);
+
+// the new discs are spawned by then
+fn restore_discs(
+    mut commands: Commands,
+    kept: Option<Res<KeptDiscs>>,
+    mut discs: Query<RespawnedDisc, (Added<DiscComp>, Without<Player>)>,
+) {
+    let Some(kept) = kept else {
+        return;
+    };
+    for (disc, mut position, mut velocity) in discs.iter_mut() {
+        if let Some((_, kept_position, kept_velocity)) =
+            kept.0.iter().find(|(index, _, _)| *index == disc.index)
+        {
+            position.0 = *kept_position;
+            velocity.0 = *kept_velocity;
+        }
+    }
+    commands.remove_resource::<KeptDiscs>();
+}
+
+fn reload_toast(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    time: Res<Time>,
+    toast: Option<ResMut<ReloadToast>>,
+) {
+    let Some(mut toast) = toast else {
+        return;
+    };
+    if toast.timer.tick(time.delta()).finished() {
+        commands.remove_resource::<ReloadToast>();
+        return;
+    }
+
+    egui::Window::new("Stadium reload")
+        .collapsible(false)
+        .resizable(false)
+        .title_bar(false)
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::Vec2::new(-10.0, -10.0))
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(&toast.message);
+        });
+}
@@ -0,0 +1,219 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{game::MatchSeed, recording::MatchRecording};
+use crate::{
+    game::{MatchPhase, MatchState},
+    parser::{stadium::StadiumComp, utils::Team},
+    stats::MatchStats,
+    team_colors::TeamColors,
+    AppState,
+};
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (scoreboard, match_summary)
+                .chain()
+                .run_if(in_state(AppState::InGame).and_then(resource_exists::<MatchState>())),
+        );
+    }
+}
+
+pub fn egui_color(color: Color) -> egui::Color32 {
+    let [r, g, b, a] = color.as_rgba_u8();
+    egui::Color32::from_rgba_unmultiplied(r, g, b, a)
+}
+
+pub fn format_clock(seconds: u32) -> String {
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn team_name(team: Team) -> &'static str {
+    match team {
+        Team::Red => "Red",
+        Team::Blue => "Blue",
+        Team::Spectator => "Spectators",
+    }
+}
+
+fn scoreboard(
+    mut contexts: EguiContexts,
+    match_state: Res<MatchState>,
+    team_colors: Res<TeamColors>,
+    stadium: Query<&StadiumComp>,
+) {
+    let red_color = egui_color(team_colors.red.fill_color());
+    let blue_color = egui_color(team_colors.blue.fill_color());
+
+    egui::TopBottomPanel::top("hud").show(contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.colored_label(red_color, match_state.red_score.to_string());
+            ui.label("-");
+            ui.colored_label(blue_color, match_state.blue_score.to_string());
+            ui.separator();
+
+            let clock = format_clock(match_state.seconds());
+            if match_state.overtime {
+                ui.colored_label(egui::Color32::RED, clock);
+                ui.colored_label(egui::Color32::RED, "OVERTIME");
+            } else {
+                ui.label(clock);
+            }
+
+            if let MatchPhase::GoalScored { scorer, .. } = match_state.phase {
+                ui.separator();
+                ui.label(format!("{} scores!", team_name(scorer)));
+            }
+
+            if let Ok(stadium) = stadium.get_single() {
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.label(&stadium.name);
+                });
+            }
+        });
+    });
+}
+
+fn match_summary(
+    mut contexts: EguiContexts,
+    match_state: Res<MatchState>,
+    stats: Option<Res<MatchStats>>,
+    // only used by the exports, there is no file system on the web
+    #[cfg(not(target_arch = "wasm32"))] recording: Option<Res<MatchRecording>>,
+    #[cfg(not(target_arch = "wasm32"))] seed: Res<MatchSeed>,
+    // result of the last export
+    mut export_status: Local<Option<String>>,
+) {
+    if match_state.phase != MatchPhase::Ended {
+        *export_status = None;
+        return;
+    }
+
+    egui::Window::new("Match summary")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(contexts.ctx_mut(), |ui| {
+            match match_state.winner() {
+                Some(team) => ui.heading(format!("{} team wins!", team_name(team))),
+                None => ui.heading("Draw"),
+            };
+            ui.label(format!(
+                "Red {} - {} Blue",
+                match_state.red_score, match_state.blue_score
+            ));
+            let mut duration = format!("Duration {}", format_clock(match_state.seconds()));
+            if match_state.overtime {
+                duration.push_str(" (overtime)");
+            }
+            ui.label(duration);
+
+            let Some(stats) = stats else {
+                return;
+            };
+            ui.separator();
+            team_stats_table(ui, &stats);
+            ui.separator();
+            player_stats_table(ui, &stats);
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                // there is no file system to save to on the web
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button("Export JSON").clicked() {
+                    let path = format!("match-{:016x}.json", seed.0);
+                    let json = serde_json::to_string_pretty(&stats.to_json())
+                        .expect("stats can always be serialized");
+                    *export_status = Some(match std::fs::write(&path, json) {
+                        Ok(()) => format!("Saved {}", path),
+                        Err(e) => format!("Failed to save {}: {}", path, e),
+                    });
+                }
+                // for the heatmaps and trajectories of haxbevy-render
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(recording) = &recording {
+                    if ui.button("Export recording").clicked() {
+                        let path = format!("match-{:016x}-recording.json", seed.0);
+                        let json = serde_json::to_vec(&**recording)
+                            .expect("recordings can always be serialized");
+                        *export_status = Some(match std::fs::write(&path, json) {
+                            Ok(()) => format!("Saved {}", path),
+                            Err(e) => format!("Failed to save {}: {}", path, e),
+                        });
+                    }
+                }
+                if let Some(status) = &*export_status {
+                    ui.label(status);
+                }
+            });
+        });
+}
+
+// the value of a team in the table
+type TeamStat = fn(&MatchStats, Team) -> String;
+
+fn team_stats_table(ui: &mut egui::Ui, stats: &MatchStats) {
+    egui::Grid::new("team_stats").striped(true).show(ui, |ui| {
+        ui.label("");
+        ui.label("Red");
+        ui.label("Blue");
+        ui.end_row();
+
+        let rows: [(&str, TeamStat); 3] = [
+            ("Possession", |stats, team| {
+                format!("{:.0}%", stats.possession(team))
+            }),
+            ("Shots on target", |stats, team| {
+                stats
+                    .team(team)
+                    .map_or(0, |team| team.shots_on_target)
+                    .to_string()
+            }),
+            ("Passes completed", |stats, team| {
+                let team = stats.team(team).cloned().unwrap_or_default();
+                format!("{}/{}", team.passes_completed, team.passes)
+            }),
+        ];
+        for (name, value) in rows {
+            ui.label(name);
+            ui.label(value(stats, Team::Red));
+            ui.label(value(stats, Team::Blue));
+            ui.end_row();
+        }
+    });
+}
+
+fn player_stats_table(ui: &mut egui::Ui, stats: &MatchStats) {
+    egui::Grid::new("player_stats")
+        .striped(true)
+        .show(ui, |ui| {
+            for header in [
+                "Player",
+                "Team",
+                "Goals",
+                "Assists",
+                "Own goals",
+                "On target",
+                "Passes",
+            ] {
+                ui.strong(header);
+            }
+            ui.end_row();
+
+            for player in &stats.players {
+                ui.label(&player.name);
+                ui.label(team_name(player.team));
+                ui.label(player.goals.to_string());
+                ui.label(player.assists.to_string());
+                ui.label(player.own_goals.to_string());
+                ui.label(player.shots_on_target.to_string());
+                ui.label(format!("{}/{}", player.passes_completed, player.passes));
+                ui.end_row();
+            }
+        });
+}
@@ -0,0 +1,232 @@
+use std::{collections::BTreeMap, f32::consts::FRAC_PI_4};
+
+use bevy::{ecs::system::SystemParam, math::DVec2, prelude::*};
+use serde::{Deserialize, Serialize};
+
+// how far a stick must be pushed to move
+const STICK_DEADZONE: f32 = 0.35;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Kick,
+    Chat,
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::Kick,
+        Action::Chat,
+        Action::Pause,
+    ];
+}
+
+// a key or a gamepad button, any gamepad triggers it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Button(GamepadButtonType),
+}
+
+impl Binding {
+    pub fn name(&self) -> String {
+        match self {
+            Binding::Key(key) => format!("{:?}", key),
+            Binding::Button(button) => format!("Pad {:?}", button),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Stick {
+    Left,
+    Right,
+}
+
+impl Stick {
+    fn axes(&self) -> (GamepadAxisType, GamepadAxisType) {
+        match self {
+            Stick::Left => (GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY),
+            Stick::Right => (GamepadAxisType::RightStickX, GamepadAxisType::RightStickY),
+        }
+    }
+}
+
+// the keys and buttons of each action of the local player
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputMap {
+    pub bindings: BTreeMap<Action, Vec<Binding>>,
+    // moves like the direction buttons, snapped to 8 directions like a keyboard
+    pub stick: Option<Stick>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use {Binding::*, GamepadButtonType::*};
+
+        let bindings = [
+            (Action::Up, vec![Key(KeyCode::Up), Button(DPadUp)]),
+            (Action::Down, vec![Key(KeyCode::Down), Button(DPadDown)]),
+            (Action::Left, vec![Key(KeyCode::Left), Button(DPadLeft)]),
+            (Action::Right, vec![Key(KeyCode::Right), Button(DPadRight)]),
+            (
+                Action::Kick,
+                vec![Key(KeyCode::X), Key(KeyCode::Space), Button(South)],
+            ),
+            (Action::Chat, vec![Key(KeyCode::Return)]),
+            (Action::Pause, vec![Key(KeyCode::Escape), Button(Start)]),
+        ];
+        InputMap {
+            bindings: bindings.into_iter().collect(),
+            stick: Some(Stick::Left),
+        }
+    }
+}
+
+impl InputMap {
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn bind(&mut self, action: Action, binding: Binding) {
+        let bindings = self.bindings.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        self.bindings.remove(&action);
+    }
+}
+
+// the closest of the 8 directions of a keyboard, zero inside the deadzone
+// y points up, like the gamepad axes
+pub fn quantize_stick(stick: Vec2) -> IVec2 {
+    if stick.length() < STICK_DEADZONE {
+        return IVec2::ZERO;
+    }
+    let angle = (stick.y.atan2(stick.x) / FRAC_PI_4).round() * FRAC_PI_4;
+    IVec2::new(angle.cos().round() as i32, angle.sin().round() as i32)
+}
+
+// the state of the actions, from the keyboard and every gamepad
+#[derive(SystemParam)]
+pub struct ActionInput<'w> {
+    map: Res<'w, InputMap>,
+    keys: Res<'w, Input<KeyCode>>,
+    buttons: Res<'w, Input<GamepadButton>>,
+    axes: Res<'w, Axis<GamepadAxis>>,
+    gamepads: Res<'w, Gamepads>,
+}
+
+impl ActionInput<'_> {
+    fn any(
+        &self,
+        action: Action,
+        keys: impl Fn(KeyCode) -> bool,
+        buttons: impl Fn(GamepadButton) -> bool,
+    ) -> bool {
+        self.map
+            .bindings(action)
+            .iter()
+            .any(|binding| match *binding {
+                Binding::Key(key) => keys(key),
+                Binding::Button(button_type) => self
+                    .gamepads
+                    .iter()
+                    .any(|gamepad| buttons(GamepadButton::new(gamepad, button_type))),
+            })
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.any(
+            action,
+            |key| self.keys.pressed(key),
+            |button| self.buttons.pressed(button),
+        )
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.any(
+            action,
+            |key| self.keys.just_pressed(key),
+            |button| self.buttons.just_pressed(button),
+        )
+    }
+
+    // the stick of the first gamepad pushed out of the deadzone
+    fn stick_direction(&self) -> IVec2 {
+        let Some(stick) = self.map.stick else {
+            return IVec2::ZERO;
+        };
+        let (x_axis, y_axis) = stick.axes();
+        self.gamepads
+            .iter()
+            .map(|gamepad| {
+                let x = self.axes.get(GamepadAxis::new(gamepad, x_axis));
+                let y = self.axes.get(GamepadAxis::new(gamepad, y_axis));
+                quantize_stick(Vec2::new(x.unwrap_or(0.0), y.unwrap_or(0.0)))
+            })
+            .find(|direction| *direction != IVec2::ZERO)
+            .unwrap_or(IVec2::ZERO)
+    }
+
+    // in stadium coordinates, y points down
+    pub fn direction(&self) -> DVec2 {
+        let stick = self.stick_direction();
+        let mut direction = DVec2::new(stick.x as f64, -stick.y as f64);
+        if self.pressed(Action::Up) {
+            direction.y -= 1.0;
+        }
+        if self.pressed(Action::Down) {
+            direction.y += 1.0;
+        }
+        if self.pressed(Action::Left) {
+            direction.x -= 1.0;
+        }
+        if self.pressed(Action::Right) {
+            direction.x += 1.0;
+        }
+        direction.clamp(DVec2::NEG_ONE, DVec2::ONE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sticks_snap_to_eight_directions() {
+        assert_eq!(quantize_stick(Vec2::new(0.2, 0.1)), IVec2::ZERO);
+        assert_eq!(quantize_stick(Vec2::new(0.9, 0.2)), IVec2::new(1, 0));
+        assert_eq!(quantize_stick(Vec2::new(0.6, 0.5)), IVec2::new(1, 1));
+        assert_eq!(quantize_stick(Vec2::new(-0.1, -0.8)), IVec2::new(0, -1));
+        assert_eq!(quantize_stick(Vec2::new(-0.7, 0.6)), IVec2::new(-1, 1));
+        assert_eq!(quantize_stick(Vec2::new(-1.0, -0.05)), IVec2::new(-1, 0));
+    }
+
+    #[test]
+    fn input_maps_round_trip() {
+        let mut map = InputMap::default();
+        map.clear(Action::Chat);
+        map.bind(Action::Kick, Binding::Button(GamepadButtonType::East));
+        map.bind(Action::Kick, Binding::Button(GamepadButtonType::East));
+        map.stick = None;
+
+        let json = serde_json::to_string(&map).unwrap();
+        let loaded: InputMap = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, map);
+        assert!(loaded.bindings(Action::Chat).is_empty());
+        assert_eq!(loaded.bindings(Action::Kick).len(), 4);
+    }
+}
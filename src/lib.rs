@@ -0,0 +1,38 @@
+// the parts of the game that do not need a window, shared with the command-line tools
+
+use bevy::prelude::*;
+
+pub mod bot;
+pub mod builtin_bots;
+pub mod env;
+pub mod game;
+pub mod headless;
+pub mod input;
+pub mod lint;
+pub mod lobby;
+pub mod parser;
+pub mod physics;
+pub mod player;
+pub mod preview;
+pub mod recording;
+pub mod rng;
+pub mod stats;
+#[cfg(test)]
+mod test_utils;
+pub mod vec_env;
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
+pub enum AppState {
+    #[default]
+    Menu,
+    InGame,
+    Editor,
+}
+
+// only meaningful while in game, the simulation is frozen when paused
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
@@ -0,0 +1,523 @@
+use std::{collections::HashSet, path::Path};
+
+use bevy::math::DVec2;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::parser::{
+    disc::{Disc, DiscRaw},
+    hx_trait::Trait,
+    segment::Segment,
+    stadium::{parse_stadium_raw_file, resolve_stadium, Stadium, StadiumRaw},
+    utils::{distance_to_polyline, parse_collision, parse_color, CollisionFlag},
+};
+
+const ARC_STEPS: usize = 64;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Issue {
+    pub severity: Severity,
+    // where the problem is, e.g. "segments[3].v1", empty for the whole file
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Report {
+    pub file: String,
+    pub issues: Vec<Issue>,
+}
+
+impl Report {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == severity)
+            .count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+}
+
+#[derive(Default)]
+struct Issues(Vec<Issue>);
+
+impl Issues {
+    fn error(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.push(Severity::Error, path.into(), message.into());
+    }
+
+    fn warning(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.push(Severity::Warning, path.into(), message.into());
+    }
+
+    fn push(&mut self, severity: Severity, path: String, message: String) {
+        self.0.push(Issue {
+            severity,
+            path,
+            message,
+        });
+    }
+
+    fn has_errors(&self) -> bool {
+        self.0.iter().any(|issue| issue.severity == Severity::Error)
+    }
+}
+
+pub fn lint_file(path: &Path) -> Report {
+    let file = path.display().to_string();
+    let issues = match std::fs::read(path)
+        .map_err(bevy::asset::Error::from)
+        .and_then(|bytes| parse_stadium_raw_file(path, &bytes))
+    {
+        Ok(raw) => lint_stadium(&raw),
+        Err(e) => vec![Issue {
+            severity: Severity::Error,
+            path: String::new(),
+            message: format!("could not parse the stadium: {}", e),
+        }],
+    };
+    Report { file, issues }
+}
+
+pub fn lint_stadium(raw: &StadiumRaw) -> Vec<Issue> {
+    let mut issues = Issues::default();
+
+    for field in raw.unknown_fields() {
+        issues.warning(field, "unknown property, kept but ignored by the game");
+    }
+    check_fallbacks(raw, &mut issues);
+    check_traits(raw, &mut issues);
+    check_objects(raw, &mut issues);
+    check_spawn_points(raw, &mut issues);
+
+    // the other checks need a stadium the parser can resolve
+    if !issues.has_errors() {
+        match resolve_stadium(raw) {
+            Ok(stadium) => check_overlaps(raw, &stadium, &mut issues),
+            Err(e) => issues.error("", format!("could not resolve the stadium: {}", e)),
+        }
+    }
+
+    issues.0
+}
+
+fn check_fallback(
+    issues: &mut Issues,
+    path: &str,
+    value: &Option<String>,
+    allowed: &[&str],
+    fallback: &str,
+) {
+    if let Some(value) = value {
+        if !allowed.contains(&value.as_str()) {
+            issues.warning(
+                path,
+                format!(
+                    "\"{}\" is not one of {:?}, \"{}\" is used instead",
+                    value, allowed, fallback
+                ),
+            );
+        }
+    }
+}
+
+fn check_fallbacks(raw: &StadiumRaw, issues: &mut Issues) {
+    check_fallback(
+        issues,
+        "cameraFollow",
+        &raw.camera_follow,
+        &["player", "ball"],
+        "ball",
+    );
+    check_fallback(
+        issues,
+        "kickOffReset",
+        &raw.kick_off_reset,
+        &["partial", "full"],
+        "partial",
+    );
+    check_fallback(
+        issues,
+        "bg.type",
+        &raw.bg.bg_type,
+        &["none", "grass", "hockey"],
+        "none",
+    );
+}
+
+fn check_color(issues: &mut Issues, path: String, color: &Option<Value>, transparent: bool) {
+    // the same check as when the stadium is loaded
+    let Some(Err(e)) = color.as_ref().map(|color| parse_color(color, transparent)) else {
+        return;
+    };
+    let expected = if transparent {
+        "\"RRGGBB\", [r, g, b] or \"transparent\""
+    } else {
+        "\"RRGGBB\" or [r, g, b]"
+    };
+    issues.error(path, format!("{}, expected {}", e, expected));
+}
+
+fn check_collision(issues: &mut Issues, path: String, flags: &Option<Vec<String>>) {
+    // each flag on its own, to report all the unknown ones
+    for flag in flags.iter().flatten() {
+        if let Err(e) = parse_collision(std::slice::from_ref(flag)) {
+            issues.error(path.clone(), e);
+        }
+    }
+}
+
+fn check_traits(raw: &StadiumRaw, issues: &mut Issues) {
+    let mut defined = vec![];
+    match &raw.traits {
+        None => {}
+        Some(Value::Array(traits)) if traits.is_empty() => {}
+        Some(Value::Object(traits)) => {
+            for (name, value) in traits {
+                let path = format!("traits.{}", name);
+                match serde_json::from_value::<Trait>(value.clone()) {
+                    Ok(hx_trait) => {
+                        check_color(issues, format!("{}.color", path), &hx_trait.color, true);
+                        check_collision(issues, format!("{}.cGroup", path), &hx_trait.c_group);
+                        check_collision(issues, format!("{}.cMask", path), &hx_trait.c_mask);
+                    }
+                    Err(e) => issues.error(path, format!("invalid trait: {}", e)),
+                }
+                defined.push(name.clone());
+            }
+        }
+        Some(_) => issues.error("traits", "traits must be an object"),
+    }
+
+    let mut used = HashSet::new();
+    let mut references = vec![];
+    let vertexes = raw.vertexes.iter().flatten().map(|v| &v.hx_trait);
+    let segments = raw.segments.iter().flatten().map(|s| &s.hx_trait);
+    let discs = raw.discs.iter().flatten().map(|d| &d.hx_trait);
+    let planes = raw.planes.iter().flatten().map(|p| &p.hx_trait);
+    for (kind, names) in [
+        ("vertexes", vertexes.collect::<Vec<_>>()),
+        ("segments", segments.collect()),
+        ("discs", discs.collect()),
+        ("planes", planes.collect()),
+    ] {
+        for (i, name) in names.into_iter().enumerate() {
+            if let Some(name) = name {
+                references.push((format!("{}[{}].trait", kind, i), name.clone()));
+            }
+        }
+    }
+    if let Some(Value::Object(ball)) = &raw.ball_physics {
+        if let Some(Value::String(name)) = ball.get("trait") {
+            references.push(("ballPhysics.trait".to_string(), name.clone()));
+        }
+    }
+    if let Some(name) = raw
+        .player_physics
+        .as_ref()
+        .and_then(|p| p.hx_trait.as_ref())
+    {
+        references.push(("playerPhysics.trait".to_string(), name.clone()));
+    }
+
+    for (path, name) in references {
+        if defined.contains(&name) {
+            used.insert(name);
+        } else {
+            issues.warning(
+                path,
+                format!("trait \"{}\" is not defined and is ignored", name),
+            );
+        }
+    }
+    for name in defined {
+        if !used.contains(&name) {
+            issues.warning(format!("traits.{}", name), "trait is never used");
+        }
+    }
+}
+
+fn check_disc(issues: &mut Issues, path: &str, disc: &DiscRaw) {
+    check_color(issues, format!("{}.color", path), &disc.color, true);
+    check_collision(issues, format!("{}.cGroup", path), &disc.c_group);
+    check_collision(issues, format!("{}.cMask", path), &disc.c_mask);
+}
+
+fn check_objects(raw: &StadiumRaw, issues: &mut Issues) {
+    check_color(issues, "bg.color".to_string(), &raw.bg.color, false);
+
+    for (i, vertex) in raw.vertexes.iter().flatten().enumerate() {
+        check_collision(issues, format!("vertexes[{}].cGroup", i), &vertex.c_group);
+        check_collision(issues, format!("vertexes[{}].cMask", i), &vertex.c_mask);
+    }
+
+    let vertex_count = raw.vertexes.as_ref().map_or(0, |v| v.len());
+    for (i, segment) in raw.segments.iter().flatten().enumerate() {
+        for (name, index) in [("v0", segment.v0), ("v1", segment.v1)] {
+            if index >= vertex_count {
+                issues.error(
+                    format!("segments[{}].{}", i, name),
+                    format!(
+                        "vertex {} is out of range, the stadium has {} vertexes",
+                        index, vertex_count
+                    ),
+                );
+            }
+        }
+        check_color(
+            issues,
+            format!("segments[{}].color", i),
+            &segment.color,
+            false,
+        );
+        check_collision(issues, format!("segments[{}].cGroup", i), &segment.c_group);
+        check_collision(issues, format!("segments[{}].cMask", i), &segment.c_mask);
+    }
+
+    for (i, goal) in raw.goals.iter().flatten().enumerate() {
+        if goal.team != "red" && goal.team != "blue" {
+            issues.error(
+                format!("goals[{}].team", i),
+                format!(
+                    "unknown team \"{}\", expected \"red\" or \"blue\"",
+                    goal.team
+                ),
+            );
+        }
+    }
+
+    for (i, disc) in raw.discs.iter().flatten().enumerate() {
+        check_disc(issues, &format!("discs[{}]", i), disc);
+    }
+
+    for (i, plane) in raw.planes.iter().flatten().enumerate() {
+        check_collision(issues, format!("planes[{}].cGroup", i), &plane.c_group);
+        check_collision(issues, format!("planes[{}].cMask", i), &plane.c_mask);
+    }
+
+    if let Some(player_physics) = &raw.player_physics {
+        check_collision(
+            issues,
+            "playerPhysics.cGroup".to_string(),
+            &player_physics.c_group,
+        );
+    }
+
+    match &raw.ball_physics {
+        None => {}
+        Some(Value::String(s))
+            if s == "disc0" && raw.discs.as_ref().is_none_or(|d| d.is_empty()) =>
+        {
+            issues.error("ballPhysics", "\"disc0\" is used but there are no discs");
+        }
+        Some(Value::String(s)) if s == "disc0" => {}
+        Some(Value::Object(ball)) => {
+            // the ball is a disc without position
+            let mut ball = ball.clone();
+            ball.insert("pos".to_string(), Value::from(vec![0.0, 0.0]));
+            match serde_json::from_value::<DiscRaw>(Value::Object(ball)) {
+                Ok(disc) => check_disc(issues, "ballPhysics", &disc),
+                Err(e) => issues.error("ballPhysics", format!("invalid ball: {}", e)),
+            }
+        }
+        Some(_) => issues.error("ballPhysics", "the ball must be \"disc0\" or a disc object"),
+    }
+}
+
+fn check_spawn_points(raw: &StadiumRaw, issues: &mut Issues) {
+    let width = raw.width.unwrap_or(0.0);
+    let height = raw.height.unwrap_or(0.0);
+
+    for (name, points) in [
+        ("redSpawnPoints", &raw.red_spawn_points),
+        ("blueSpawnPoints", &raw.blue_spawn_points),
+    ] {
+        for (i, point) in points.iter().flatten().enumerate() {
+            let path = format!("{}[{}]", name, i);
+            if point.len() != 2 {
+                issues.error(path, "a spawn point must be [x, y]");
+                continue;
+            }
+            // a stadium without size has no field to check against
+            let outside_x = width > 0.0 && point[0].abs() > width;
+            let outside_y = height > 0.0 && point[1].abs() > height;
+            if outside_x || outside_y {
+                issues.warning(
+                    path,
+                    format!(
+                        "spawn point ({}, {}) is outside of the field ({} x {})",
+                        point[0],
+                        point[1],
+                        2.0 * width,
+                        2.0 * height
+                    ),
+                );
+            }
+        }
+    }
+}
+
+fn collides(disc: &Disc, group: CollisionFlag, mask: CollisionFlag) -> bool {
+    disc.c_mask.intersects(group) && mask.intersects(disc.c_group)
+}
+
+fn check_overlaps(raw: &StadiumRaw, stadium: &Stadium, issues: &mut Issues) {
+    // the ball is removed from the discs when it is disc0
+    let ball_is_disc0 = matches!(&raw.ball_physics, Some(Value::String(s)) if s == "disc0");
+    let ball_path = if ball_is_disc0 {
+        "discs[0]"
+    } else {
+        "ballPhysics"
+    };
+    let mut discs = vec![(ball_path.to_string(), &stadium.ball_physics.0)];
+    let offset = usize::from(ball_is_disc0);
+    for (i, disc) in stadium.discs.iter().enumerate() {
+        discs.push((format!("discs[{}]", i + offset), disc));
+    }
+
+    let vertexes = stadium
+        .vertexes
+        .iter()
+        .map(|v| v.position)
+        .collect::<Vec<DVec2>>();
+
+    for (path, disc) in discs {
+        // fixed discs like goal posts are usually placed on the walls on purpose
+        if disc.inv_mass == 0.0 {
+            continue;
+        }
+        for (i, segment) in stadium.segments.iter().enumerate() {
+            let (group, mask) = match segment {
+                Segment::Straight(s) => (s.c_group, s.c_mask),
+                Segment::Curved(s) => (s.c_group, s.c_mask),
+            };
+            if !collides(disc, group, mask) {
+                continue;
+            }
+            let points = segment.points(&vertexes, ARC_STEPS);
+            if distance_to_polyline(disc.position, &points) < disc.radius {
+                issues.warning(
+                    path.clone(),
+                    format!("disc overlaps segments[{}] at spawn", i),
+                );
+            }
+        }
+
+        for (i, plane) in stadium.planes.iter().enumerate() {
+            if !collides(disc, plane.c_group, plane.c_mask) {
+                continue;
+            }
+            let normal = plane.normal.normalize_or_zero();
+            if normal.dot(disc.position) - plane.dist < disc.radius {
+                issues.warning(
+                    path.clone(),
+                    format!("disc overlaps planes[{}] at spawn", i),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::stadium::parse_stadium_raw;
+
+    #[test]
+    fn reports_broken_stadium() {
+        let hbs = r#"{
+            "name": "Broken",
+            "width": 100,
+            "height": 50,
+            "cameraFollow": "players",
+            "bg": { "type": "grass", "color": "transparent" },
+            "traits": { "wall": { "bCoef": 0.1 }, "unused": {} },
+            "vertexes": [{ "x": -10, "y": 0, "trait": "wall" }, { "x": 10, "y": 0 }],
+            "segments": [{ "v0": 0, "v1": 2, "trait": "missing" }],
+            "goals": [{ "p0": [0, 0], "p1": [0, 10], "team": "green" }],
+            "discs": [{ "pos": [0, 0], "color": "FFF" }],
+            "redSpawnPoints": [[150, 0]]
+        }"#;
+        let raw = parse_stadium_raw(hbs.as_bytes()).unwrap();
+        let issues = lint_stadium(&raw);
+        let paths = |severity| {
+            let mut paths = issues
+                .iter()
+                .filter(|issue| issue.severity == severity)
+                .map(|issue| issue.path.as_str())
+                .collect::<Vec<_>>();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(
+            paths(Severity::Error),
+            vec![
+                "bg.color",
+                "discs[0].color",
+                "goals[0].team",
+                "segments[0].v1",
+            ]
+        );
+        assert_eq!(
+            paths(Severity::Warning),
+            vec![
+                "cameraFollow",
+                "redSpawnPoints[0]",
+                "segments[0].trait",
+                "traits.unused"
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_overlapping_disc() {
+        let hbs = r#"{
+            "name": "Overlap",
+            "bg": {},
+            "vertexes": [{ "x": -50, "y": 5 }, { "x": 50, "y": 5 }],
+            "segments": [{ "v0": 0, "v1": 1 }],
+            "discs": [{ "pos": [0, 0], "radius": 10 }, { "pos": [0, -30], "radius": 10 }]
+        }"#;
+        let raw = parse_stadium_raw(hbs.as_bytes()).unwrap();
+        let issues = lint_stadium(&raw);
+        // the default ball spawns at the center too
+        let paths = issues.iter().map(|i| i.path.as_str()).collect::<Vec<_>>();
+        assert_eq!(paths, vec!["ballPhysics", "discs[0]"]);
+        assert!(issues.iter().all(|i| i.severity == Severity::Warning));
+    }
+
+    #[test]
+    fn colors_match_the_loader() {
+        for (color, valid) in [
+            (r#""FFCC00""#, true),
+            ("[255, 204, 0]", true),
+            (r#""transparent""#, true),
+            (r#""FFF""#, false),
+            (r#""FFCC0G""#, false),
+            ("[256, 0, 0]", false),
+            ("[255, 0]", false),
+        ] {
+            let hbs = format!(
+                r#"{{ "name": "Colors", "bg": {{}}, "discs": [{{ "pos": [0, 0], "color": {} }}] }}"#,
+                color
+            );
+            let raw = parse_stadium_raw(hbs.as_bytes()).unwrap();
+            let lint_valid = !lint_stadium(&raw)
+                .iter()
+                .any(|issue| issue.path == "discs[0].color");
+            assert_eq!(lint_valid, valid, "{}", color);
+            assert_eq!(resolve_stadium(&raw).is_ok(), valid, "{}", color);
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+
+use crate::parser::utils::Team;
+
+// who sends the inputs of a player
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Controller {
+    // the keyboard of this client
+    Local,
+    // a bot from the BotRegistry, by name
+    Bot(String),
+    // inputs written by code, like a learning agent
+    External,
+}
+
+#[derive(Debug, Clone)]
+pub struct LobbySlot {
+    pub name: String,
+    pub avatar: String,
+    pub team: Team,
+    pub controller: Controller,
+}
+
+impl LobbySlot {
+    pub fn local(name: &str, team: Team) -> LobbySlot {
+        LobbySlot {
+            name: name.to_string(),
+            avatar: "1".to_string(),
+            team,
+            controller: Controller::Local,
+        }
+    }
+
+    pub fn bot(bot: &str, team: Team) -> LobbySlot {
+        LobbySlot {
+            name: bot.to_string(),
+            avatar: "B".to_string(),
+            team,
+            controller: Controller::Bot(bot.to_string()),
+        }
+    }
+}
+
+// the players of the next match, in spawn order
+#[derive(Resource, Debug, Clone)]
+pub struct Lobby {
+    pub slots: Vec<LobbySlot>,
+}
+
+impl Default for Lobby {
+    fn default() -> Self {
+        Lobby {
+            slots: vec![LobbySlot::local("Player", Team::Red)],
+        }
+    }
+}
+
+impl Lobby {
+    // number of players already in the team
+    pub fn team_size(&self, team: Team) -> usize {
+        self.slots.iter().filter(|slot| slot.team == team).count()
+    }
+}
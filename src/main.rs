@@ -1,29 +1,59 @@
 use bevy::prelude::*;
-use bevy::window::PresentMode;
 use bevy_egui::EguiPlugin;
 use bevy_prototype_lyon::prelude::*;
+use bot::BotPlugin;
 use debug::DebugPlugin;
+use editor::EditorPlugin;
+use game::GamePlugin;
+use haxbevy::{
+    bot, game, input, lobby, parser, physics, player, preview, recording, rng, stats, AppState,
+    PauseState,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use hot_reload::HotReloadPlugin;
+use hud::HudPlugin;
 use menu::MenuPlugin;
+use pause_menu::PauseMenuPlugin;
 use physics::PhysicsPlugin;
+use player::PlayerPlugin;
+use recording::RecordingPlugin;
 use renderer::RendererPlugin;
+use settings::{Settings, SettingsPlugin};
+use sound::SoundPlugin;
+use stats::StatsPlugin;
+use team_colors::TeamColorsPlugin;
 
 mod debug;
+mod editor;
+#[cfg(not(target_arch = "wasm32"))]
+mod hot_reload;
+mod hud;
 mod menu;
-mod parser;
-mod physics;
+mod pause_menu;
 mod renderer;
+mod settings;
+mod sound;
+mod team_colors;
 
 fn main() {
     // When building for WASM, print panics to the browser console
     #[cfg(target_arch = "wasm32")]
     console_error_panic_hook::set_once();
 
-    App::new()
-        .add_state::<AppState>()
+    // the window is created with the saved settings
+    let settings = Settings::load();
+    let (width, height) = settings.resolution;
+
+    let mut app = App::new();
+    app.add_state::<AppState>()
+        .add_state::<PauseState>()
         .insert_resource(FixedTime::new_from_secs(1.0 / 60.0))
+        .insert_resource(settings.clone())
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
-                present_mode: PresentMode::AutoVsync,
+                present_mode: settings.present_mode(),
+                mode: settings.window_mode(),
+                resolution: (width, height).into(),
                 // Tells wasm to resize the window according to the available canvas
                 fit_canvas_to_parent: true,
                 // Tells wasm not to override default event handling, like F5, Ctrl+R etc.
@@ -32,20 +62,33 @@ fn main() {
             }),
             ..default()
         }))
+        // the presentation
         .add_plugins((
             EguiPlugin,
             ShapePlugin,
             DebugPlugin,
             MenuPlugin,
+            PauseMenuPlugin,
+            EditorPlugin,
             RendererPlugin,
-            PhysicsPlugin,
+            HudPlugin,
+            SoundPlugin,
+            SettingsPlugin,
+            TeamColorsPlugin,
         ))
-        .run();
-}
+        // the simulation, also run by headless matches
+        .add_plugins((
+            PhysicsPlugin,
+            GamePlugin,
+            PlayerPlugin,
+            BotPlugin,
+            StatsPlugin,
+            RecordingPlugin,
+        ));
+
+    // stadium files can only be watched on native
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_plugins(HotReloadPlugin);
 
-#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
-enum AppState {
-    #[default]
-    Menu,
-    InGame,
+    app.run();
 }
@@ -1,9 +1,19 @@
 use bevy::{asset::*, prelude::*, reflect::*};
-use bevy_egui::{egui, EguiContexts, EguiSettings};
-use jsonc_parser::{parse_to_serde_value, ParseOptions};
+use bevy_egui::{egui, EguiContexts};
+use haxbevy::preview::Scene;
 
 use crate::{
-    parser::stadium::{Stadium, StadiumRaw},
+    bot::BotRegistry,
+    editor::EditorSession,
+    game::{MatchSeed, MatchSettings},
+    lobby::{Controller, Lobby, LobbySlot},
+    parser::{
+        binary,
+        stadium::{format_hash, parse_stadium_file, Stadium, StadiumRaw},
+        utils::Team,
+    },
+    rng::Rng,
+    settings::SettingsOpener,
     AppState,
 };
 
@@ -16,16 +26,26 @@ impl Plugin for MenuPlugin {
             .add_systems(OnEnter(AppState::Menu), setup_menu)
             .add_systems(
                 Update,
-                (menu, load_to_ingame).run_if(in_state(AppState::Menu)),
+                (stadium_preview, lobby_panel, menu, load_to_ingame)
+                    .chain()
+                    .run_if(in_state(AppState::Menu)),
             )
             .add_systems(OnExit(AppState::Menu), cleanup_menu);
     }
 }
 
-#[derive(Resource, Default)]
+// width of the stadium thumbnail, in points
+const PREVIEW_WIDTH: f32 = 280.0;
+
+#[derive(Resource)]
 struct MenuData {
     base_stadium_info: BaseStadiumInfo,
     custom_stadium_info: CustomStadiumInfo,
+    // asset path of the stadium shown in the preview panel
+    preview_path: &'static str,
+    preview_handle: Option<Handle<StadiumAsset>>,
+    preview_texture: Option<(&'static str, egui::TextureHandle)>,
+    preview_hash: Option<(&'static str, u64)>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -44,7 +64,20 @@ enum BaseStadiumInfo {
 }
 
 impl BaseStadiumInfo {
-    fn get_name(&self) -> &str {
+    const ALL: [BaseStadiumInfo; 10] = [
+        BaseStadiumInfo::Classic,
+        BaseStadiumInfo::Easy,
+        BaseStadiumInfo::Small,
+        BaseStadiumInfo::Big,
+        BaseStadiumInfo::Rounded,
+        BaseStadiumInfo::Hockey,
+        BaseStadiumInfo::BigHockey,
+        BaseStadiumInfo::BigEasy,
+        BaseStadiumInfo::BigRounded,
+        BaseStadiumInfo::Huge,
+    ];
+
+    fn get_name(&self) -> &'static str {
         match self {
             BaseStadiumInfo::Classic => "Classic",
             BaseStadiumInfo::Easy => "Easy",
@@ -59,7 +92,7 @@ impl BaseStadiumInfo {
         }
     }
 
-    fn get_path(&self) -> &str {
+    fn get_path(&self) -> &'static str {
         match self {
             BaseStadiumInfo::Classic => "stadiums/base/classic.json5",
             BaseStadiumInfo::Easy => "stadiums/base/easy.json5",
@@ -86,7 +119,15 @@ enum CustomStadiumInfo {
 }
 
 impl CustomStadiumInfo {
-    fn get_name(&self) -> &str {
+    const ALL: [CustomStadiumInfo; 5] = [
+        CustomStadiumInfo::FutsalClassic,
+        CustomStadiumInfo::FutsalBig,
+        CustomStadiumInfo::PenaltySoccer,
+        CustomStadiumInfo::ObstacleWinky,
+        CustomStadiumInfo::FightingSingle,
+    ];
+
+    fn get_name(&self) -> &'static str {
         match self {
             CustomStadiumInfo::FutsalClassic => "Futsal Classic",
             CustomStadiumInfo::FutsalBig => "Futsal Big",
@@ -96,7 +137,7 @@ impl CustomStadiumInfo {
         }
     }
 
-    fn get_path(&self) -> &str {
+    fn get_path(&self) -> &'static str {
         match self {
             CustomStadiumInfo::FutsalClassic => "stadiums/custom/futsal-classic.json5",
             CustomStadiumInfo::FutsalBig => "stadiums/custom/futsal-big.json5",
@@ -107,8 +148,23 @@ impl CustomStadiumInfo {
     }
 }
 
+// names and asset paths of every stadium shipped with the game
+pub fn stadium_list() -> Vec<(&'static str, &'static str)> {
+    let base = BaseStadiumInfo::ALL
+        .iter()
+        .map(|s| (s.get_name(), s.get_path()));
+    let custom = CustomStadiumInfo::ALL
+        .iter()
+        .map(|s| (s.get_name(), s.get_path()));
+    base.chain(custom).collect()
+}
+
 #[derive(Resource, Default)]
-struct AssetsLoading(Vec<HandleUntyped>);
+pub struct AssetsLoading(pub Vec<HandleUntyped>);
+
+// the state to switch to once the assets are loaded, the game by default
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct LoadTarget(pub AppState);
 
 #[derive(Debug, Resource)]
 pub struct DataAssets {
@@ -117,7 +173,7 @@ pub struct DataAssets {
 
 #[derive(Debug, TypeUuid, TypePath)]
 #[uuid = "ff866d71-0c0e-4af0-8437-a4177ed03f2c"]
-pub struct StadiumAsset(pub Stadium);
+pub struct StadiumAsset(pub Stadium, pub StadiumRaw);
 
 #[derive(Default)]
 pub struct StadiumLoader;
@@ -129,27 +185,28 @@ impl AssetLoader for StadiumLoader {
         load_context: &'a mut LoadContext,
     ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
         Box::pin(async move {
-            let data_str = std::str::from_utf8(bytes)?;
-            let stadium_value = parse_to_serde_value(data_str, &ParseOptions::default())?;
-            let stadium_raw: StadiumRaw = serde_json::from_value(stadium_value.unwrap())?;
-            let stadium = stadium_raw.to_stadium();
-            let asset = StadiumAsset(stadium);
+            let (stadium, stadium_raw) = parse_stadium_file(load_context.path(), bytes)?;
+            let asset = StadiumAsset(stadium, stadium_raw);
             load_context.set_default_asset(LoadedAsset::new(asset));
             Ok(())
         })
     }
 
     fn extensions(&self) -> &[&str] {
-        &["json5", "json", "hbs"]
+        &["json5", "json", "hbs", binary::EXTENSION]
     }
 }
 
-fn setup_menu(mut commands: Commands, mut egui_settings: ResMut<EguiSettings>) {
-    egui_settings.scale_factor = 2.0;
-    commands.insert_resource(AssetsLoading::default());
+fn setup_menu(mut commands: Commands) {
+    // a stadium may already be loading when coming back from a game
+    commands.init_resource::<AssetsLoading>();
     commands.insert_resource(MenuData {
         base_stadium_info: BaseStadiumInfo::default(),
         custom_stadium_info: CustomStadiumInfo::default(),
+        preview_path: BaseStadiumInfo::default().get_path(),
+        preview_handle: None,
+        preview_texture: None,
+        preview_hash: None,
     });
 }
 
@@ -159,7 +216,12 @@ fn menu(
     mut menu_data: ResMut<MenuData>,
     asset_server: Res<AssetServer>,
     mut loading: ResMut<AssetsLoading>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut settings: SettingsOpener,
 ) {
+    let (base_stadium, custom_stadium) =
+        (menu_data.base_stadium_info, menu_data.custom_stadium_info);
+
     egui::CentralPanel::default().show(contexts.ctx_mut(), |ui| {
         ui.heading("Stadiums");
 
@@ -225,6 +287,14 @@ fn menu(
                 let stadium = asset_server.load(menu_data.base_stadium_info.get_path());
                 loading.0.push(stadium.clone_untyped());
                 commands.insert_resource(DataAssets { stadium });
+                commands.remove_resource::<EditorSession>();
+            }
+            if ui.button("Edit").clicked() {
+                let stadium = asset_server.load(menu_data.base_stadium_info.get_path());
+                loading.0.push(stadium.clone_untyped());
+                commands.insert_resource(DataAssets { stadium });
+                commands.insert_resource(LoadTarget(AppState::Editor));
+                commands.remove_resource::<EditorSession>();
             }
         });
 
@@ -267,15 +337,254 @@ fn menu(
                 let stadium = asset_server.load(menu_data.custom_stadium_info.get_path());
                 loading.0.push(stadium.clone_untyped());
                 commands.insert_resource(DataAssets { stadium });
+                commands.remove_resource::<EditorSession>();
+            }
+            if ui.button("Edit").clicked() {
+                let stadium = asset_server.load(menu_data.custom_stadium_info.get_path());
+                loading.0.push(stadium.clone_untyped());
+                commands.insert_resource(DataAssets { stadium });
+                commands.insert_resource(LoadTarget(AppState::Editor));
+                commands.remove_resource::<EditorSession>();
             }
         });
+
+        ui.add_space(4.0);
+
+        ui.heading("Editor");
+        if ui.button("New stadium").clicked() {
+            commands.remove_resource::<DataAssets>();
+            commands.remove_resource::<EditorSession>();
+            next_state.set(AppState::Editor);
+        }
+
+        ui.add_space(4.0);
+
+        if ui.button("Settings").clicked() {
+            settings.open();
+        }
+    });
+
+    // preview the last stadium picked in either list
+    if menu_data.base_stadium_info != base_stadium {
+        menu_data.preview_path = menu_data.base_stadium_info.get_path();
+    } else if menu_data.custom_stadium_info != custom_stadium {
+        menu_data.preview_path = menu_data.custom_stadium_info.get_path();
+    }
+}
+
+// the players of the next match, bots can take any slot
+fn lobby_panel(
+    mut contexts: EguiContexts,
+    mut lobby: ResMut<Lobby>,
+    mut match_settings: ResMut<MatchSettings>,
+    bots: Res<BotRegistry>,
+) {
+    egui::SidePanel::left("lobby").show(contexts.ctx_mut(), |ui| {
+        ui.heading("Players");
+
+        let mut removed = None;
+        for (index, slot) in lobby.slots.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut slot.name).desired_width(80.0));
+                egui::ComboBox::from_id_source(("lobby_team", index))
+                    .width(60.0)
+                    .selected_text(format!("{:?}", slot.team))
+                    .show_ui(ui, |ui| {
+                        for team in [Team::Red, Team::Blue, Team::Spectator] {
+                            ui.selectable_value(&mut slot.team, team, format!("{:?}", team));
+                        }
+                    });
+                let controller = match &slot.controller {
+                    Controller::Local => "Human",
+                    Controller::Bot(name) => name.as_str(),
+                    Controller::External => "External",
+                };
+                egui::ComboBox::from_id_source(("lobby_controller", index))
+                    .width(80.0)
+                    .selected_text(controller.to_string())
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut slot.controller, Controller::Local, "Human");
+                        for bot in &bots.bots {
+                            ui.selectable_value(
+                                &mut slot.controller,
+                                Controller::Bot(bot.name.to_string()),
+                                bot.name,
+                            );
+                        }
+                    });
+                if ui.small_button("x").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+        if let Some(index) = removed {
+            lobby.slots.remove(index);
+        }
+
+        ui.horizontal(|ui| {
+            let bot = bots.bots.first().map(|bot| bot.name);
+            for team in [Team::Red, Team::Blue] {
+                let label = format!("Add {:?} bot", team);
+                if ui
+                    .add_enabled(bot.is_some(), egui::Button::new(label))
+                    .clicked()
+                {
+                    lobby.slots.push(LobbySlot::bot(bot.unwrap(), team));
+                }
+            }
+        });
+
+        ui.separator();
+        ui.heading("Rules");
+        // 0 means no limit
+        egui::Grid::new("match_settings").show(ui, |ui| {
+            ui.label("Time limit");
+            let time_limit = egui::DragValue::new(&mut match_settings.time_limit)
+                .clamp_range(0..=14)
+                .suffix(" min");
+            ui.add(time_limit);
+            ui.end_row();
+
+            ui.label("Score limit");
+            ui.add(egui::DragValue::new(&mut match_settings.score_limit).clamp_range(0..=14));
+            ui.end_row();
+
+            ui.label("Shuffle teams");
+            ui.checkbox(&mut match_settings.shuffle_teams, "");
+            ui.end_row();
+
+            ui.label("Random kickoff ball");
+            ui.checkbox(&mut match_settings.random_ball, "");
+            ui.end_row();
+        });
+    });
+}
+
+fn stadium_preview(
+    mut contexts: EguiContexts,
+    mut menu_data: ResMut<MenuData>,
+    asset_server: Res<AssetServer>,
+    stadium_assets: Res<Assets<StadiumAsset>>,
+) {
+    let path = menu_data.preview_path;
+    // keep the handle so that the stadium stays loaded while it is previewed
+    let handle = asset_server.load::<StadiumAsset, _>(path);
+    menu_data.preview_handle = Some(handle.clone());
+    let ctx = contexts.ctx_mut().clone();
+
+    egui::SidePanel::right("stadium_preview").show(&ctx, |ui| {
+        let Some(StadiumAsset(stadium, _)) = stadium_assets.get(&handle) else {
+            match asset_server.get_load_state(&handle) {
+                LoadState::Failed => ui.label("Could not load the stadium"),
+                _ => ui.label("Loading..."),
+            };
+            return;
+        };
+
+        ui.heading(&stadium.name);
+        if menu_data
+            .preview_texture
+            .as_ref()
+            .is_none_or(|(p, _)| *p != path)
+        {
+            // rendered at twice the displayed size to stay sharp with the menu scale
+            let scene = Scene::from_stadium(stadium);
+            let scale = 2.0 * PREVIEW_WIDTH as f64 / scene.size().x;
+            menu_data.preview_texture = scene.to_pixmap(scale).map(|pixmap| {
+                let size = [pixmap.width() as usize, pixmap.height() as usize];
+                let image = egui::ColorImage::from_rgba_premultiplied(size, pixmap.data());
+                let texture =
+                    ctx.load_texture("stadium_preview", image, egui::TextureOptions::LINEAR);
+                (path, texture)
+            });
+        }
+        if let Some((_, texture)) = &menu_data.preview_texture {
+            let size = texture.size_vec2();
+            ui.image(texture.id(), size * (PREVIEW_WIDTH / size.x));
+        }
+
+        let hash = match menu_data.preview_hash {
+            Some((p, hash)) if p == path => hash,
+            _ => {
+                let hash = stadium.content_hash();
+                menu_data.preview_hash = Some((path, hash));
+                hash
+            }
+        };
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            stadium_metadata(ui, stadium, hash);
+        });
+    });
+}
+
+fn stadium_metadata(ui: &mut egui::Ui, stadium: &Stadium, hash: u64) {
+    let row = |ui: &mut egui::Ui, label: &str, value: String| {
+        ui.label(label);
+        ui.label(value);
+        ui.end_row();
+    };
+
+    egui::Grid::new("stadium_metadata")
+        .num_columns(2)
+        .striped(true)
+        .show(ui, |ui| {
+            row(ui, "Width", stadium.width.to_string());
+            row(ui, "Height", stadium.height.to_string());
+            row(ui, "Vertexes", stadium.vertexes.len().to_string());
+            row(ui, "Segments", stadium.segments.len().to_string());
+            row(ui, "Discs", stadium.discs.len().to_string());
+            row(ui, "Planes", stadium.planes.len().to_string());
+            row(ui, "Goals", stadium.goals.len().to_string());
+            row(ui, "Spawn distance", stadium.spawn_distance.to_string());
+            row(ui, "Camera follow", format!("{:?}", stadium.camera_follow));
+            row(ui, "Kickoff reset", format!("{:?}", stadium.kick_off_reset));
+            row(ui, "Can be stored", stadium.can_be_stored.to_string());
+            row(ui, "Hash", format_hash(hash));
+        });
+
+    let player = &stadium.player_physics;
+    ui.collapsing("Player physics", |ui| {
+        egui::Grid::new("player_physics")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                row(ui, "Radius", player.radius.to_string());
+                row(ui, "Inverse mass", player.inv_mass.to_string());
+                row(ui, "Bouncing", player.b_coef.to_string());
+                row(ui, "Damping", player.damping.to_string());
+                row(ui, "Acceleration", player.acceleration.to_string());
+                row(
+                    ui,
+                    "Kicking acceleration",
+                    player.kicking_acceleration.to_string(),
+                );
+                row(ui, "Kicking damping", player.kicking_damping.to_string());
+                row(ui, "Kick strength", player.kick_strength.to_string());
+                row(ui, "Kickback", player.kickback.to_string());
+            });
+    });
+
+    let ball = &stadium.ball_physics.0;
+    ui.collapsing("Ball physics", |ui| {
+        egui::Grid::new("ball_physics")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                row(ui, "Radius", ball.radius.to_string());
+                row(ui, "Inverse mass", ball.inv_mass.to_string());
+                row(ui, "Bouncing", ball.b_coef.to_string());
+                row(ui, "Damping", ball.damping.to_string());
+            });
     });
 }
 
 fn load_to_ingame(
     mut commands: Commands,
+    time: Res<Time>,
     server: Res<AssetServer>,
     loading: Res<AssetsLoading>,
+    target: Option<Res<LoadTarget>>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
     if loading.0.is_empty() {
@@ -289,7 +598,11 @@ fn load_to_ingame(
         LoadState::Loaded => {
             println!("Assets loaded");
             commands.remove_resource::<AssetsLoading>();
-            next_state.set(AppState::InGame);
+            commands.remove_resource::<LoadTarget>();
+            // every match gets a new seed, the time it took to start is random enough
+            let nanos = time.raw_elapsed().as_nanos() as u64;
+            commands.insert_resource(MatchSeed(Rng::new(nanos).next_u64()));
+            next_state.set(target.map_or(AppState::InGame, |t| t.0));
         }
         _ => {}
     }
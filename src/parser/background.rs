@@ -1,15 +1,15 @@
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
 
-use super::utils::parse_color;
+use super::utils::{color_to_value, parse_color};
 
-const GRASS_BORDER_COLOR: Color = Color::rgb(0.78, 0.9, 0.74);
-const GRASS_FILL_COLOR: Color = Color::rgb(0.44, 0.55, 0.35);
+pub const GRASS_BORDER_COLOR: Color = Color::rgb(0.78, 0.9, 0.74);
+pub const GRASS_FILL_COLOR: Color = Color::rgb(0.44, 0.55, 0.35);
 
-const HOCKEY_BORDER_COLOR: Color = Color::rgb(0.91, 0.8, 0.43);
-const HOCKEY_FILL_COLOR: Color = Color::rgb(0.33, 0.33, 0.33);
+pub const HOCKEY_BORDER_COLOR: Color = Color::rgb(0.91, 0.8, 0.43);
+pub const HOCKEY_FILL_COLOR: Color = Color::rgb(0.33, 0.33, 0.33);
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum BackgroundType {
@@ -22,13 +22,15 @@ pub enum BackgroundType {
 #[serde(rename_all = "camelCase")]
 pub struct BackgroundRaw {
     #[serde(rename = "type")]
-    bg_type: Option<String>,
-    width: Option<f64>,
-    height: Option<f64>,
-    kick_off_radius: Option<f64>,
-    corner_radius: Option<f64>,
-    goal_line: Option<f64>,
-    color: Option<Value>,
+    pub bg_type: Option<String>,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    pub kick_off_radius: Option<f64>,
+    pub corner_radius: Option<f64>,
+    pub goal_line: Option<f64>,
+    pub color: Option<Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl Default for BackgroundRaw {
@@ -41,12 +43,13 @@ impl Default for BackgroundRaw {
             corner_radius: Some(0.0),
             goal_line: Some(0.0),
             color: Some(Value::String("718C5A".to_string())),
+            extra: Map::new(),
         }
     }
 }
 
 impl BackgroundRaw {
-    pub fn to_background(&self) -> Background {
+    pub fn to_background(&self) -> Result<Background, String> {
         let background_raw = BackgroundRaw::default();
         let bg_type = match &self.bg_type {
             Some(t) => match t.as_str() {
@@ -54,10 +57,7 @@ impl BackgroundRaw {
                 "hockey" => BackgroundType::Hockey,
                 _ => BackgroundType::None,
             },
-            None => match background_raw.bg_type.unwrap().as_str() {
-                "none" => BackgroundType::None,
-                _ => panic!("Invalid default background type"),
-            },
+            None => BackgroundType::None,
         };
         let width = match self.width {
             Some(w) => w,
@@ -80,10 +80,10 @@ impl BackgroundRaw {
             None => background_raw.goal_line.unwrap(),
         };
         let color = match &self.color {
-            Some(c) => parse_color(c, false),
-            None => parse_color(&background_raw.color.unwrap(), false),
+            Some(c) => parse_color(c, false)?,
+            None => parse_color(&background_raw.color.unwrap(), false)?,
         };
-        Background {
+        Ok(Background {
             bg_type,
             width,
             height,
@@ -91,7 +91,7 @@ impl BackgroundRaw {
             corner_radius,
             goal_line,
             color,
-        }
+        })
     }
 }
 
@@ -109,6 +109,24 @@ pub struct Background {
 }
 
 impl Background {
+    pub fn to_raw(&self) -> BackgroundRaw {
+        let bg_type = match self.bg_type {
+            BackgroundType::None => "none",
+            BackgroundType::Grass => "grass",
+            BackgroundType::Hockey => "hockey",
+        };
+        BackgroundRaw {
+            bg_type: Some(bg_type.to_string()),
+            width: Some(self.width),
+            height: Some(self.height),
+            kick_off_radius: Some(self.kick_off_radius),
+            corner_radius: Some(self.corner_radius),
+            goal_line: Some(self.goal_line),
+            color: Some(color_to_value(self.color)),
+            extra: Map::new(),
+        }
+    }
+
     fn draw_limit(&self, stadium_parent: &mut ChildBuilder) {
         match self.bg_type {
             BackgroundType::Grass => {
@@ -9,7 +9,10 @@ use super::{
 };
 
 #[derive(Debug, Clone)]
-pub struct Ball(Disc);
+pub struct Ball(pub Disc);
+
+#[derive(Component, Debug, Clone, Copy)]
+pub struct BallComp;
 
 impl Default for Ball {
     fn default() -> Self {
@@ -22,27 +25,33 @@ impl Default for Ball {
             damping: 0.99,
             b_coef: 0.5,
             color: Color::WHITE,
-            c_group: CollisionFlag::BALL,
+            c_group: CollisionFlag::BALL | CollisionFlag::KICK | CollisionFlag::SCORE,
             c_mask: CollisionFlag::ALL,
+            vis: true,
         };
         Ball(ball_disc)
     }
 }
 
+impl Ball {
+    pub fn spawn(&self, stadium_parent: &mut ChildBuilder) {
+        stadium_parent.spawn((BallComp, self.0.bundle(0)));
+    }
+}
+
 pub fn handle_ball(
     ball: &Option<Value>,
     discs: &mut Vec<Disc>,
     traits: &HashMap<String, Trait>,
-) -> Ball {
+) -> Result<Ball, String> {
+    let invalid = || "ball must be either \"disc0\" or a disc object".to_string();
     match ball.as_ref() {
-        None => Ball::default(),
-        Some(Value::String(s)) => {
-            if s == "disc0" {
-                let disc = discs.remove(0);
-                Ball(disc)
-            } else {
-                panic!("ball must be either \"disc0\" or a disc object")
+        None => Ok(Ball::default()),
+        Some(Value::String(s)) if s == "disc0" => {
+            if discs.is_empty() {
+                return Err("\"disc0\" is used but there are no discs".to_string());
             }
+            Ok(Ball(discs.remove(0)))
         }
         Some(Value::Object(o)) => {
             // ball_physics never contains a "pos" field, which is mandatory
@@ -52,11 +61,12 @@ pub fn handle_ball(
                 "pos".to_string(),
                 Value::Array(vec![0.0.into(), 0.0.into()]),
             );
-            let disc_raw: DiscRaw = serde_json::from_value(Value::Object(o_mut)).unwrap();
-            let mut disc = disc_raw.to_disc(traits);
+            let disc_raw: DiscRaw =
+                serde_json::from_value(Value::Object(o_mut)).map_err(|e| e.to_string())?;
+            let mut disc = disc_raw.to_disc(traits)?;
             disc.c_group |= CollisionFlag::KICK | CollisionFlag::SCORE;
-            Ball(disc)
+            Ok(Ball(disc))
         }
-        _ => panic!("ball must be either \"disc0\" or a disc object"),
+        _ => Err(invalid()),
     }
 }
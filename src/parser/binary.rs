@@ -0,0 +1,152 @@
+use std::{
+    io::{Read, Write},
+    path::Path,
+};
+
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+
+use super::stadium::{resolve_stadium, Stadium, StadiumRaw};
+
+// .hbsb files: the stadium as compressed MessagePack, for network transfer and caches
+//
+// layout, integers in little endian:
+//   magic     4 bytes  "HBSB"
+//   version   u16
+//   checksum  u32      CRC-32 of the uncompressed payload
+//   payload            deflate compressed MessagePack of a StadiumRaw
+pub const EXTENSION: &str = "hbsb";
+pub const MAGIC: &[u8; 4] = b"HBSB";
+// bump when the layout or StadiumRaw change in an incompatible way
+pub const VERSION: u16 = 1;
+const HEADER_LEN: usize = 10;
+// far above any real stadium, a small file must not inflate into all of the memory
+const MAX_PAYLOAD_BYTES: u64 = 16 * 1024 * 1024;
+
+pub fn is_binary_path(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == EXTENSION)
+}
+
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+fn encode_payload(stadium_raw: &StadiumRaw) -> Vec<u8> {
+    // named fields, the flattened unknown properties need a map
+    rmp_serde::to_vec_named(stadium_raw).expect("a stadium can always be encoded")
+}
+
+// the stadium as written, with its traits and unset properties
+pub fn encode_raw(stadium_raw: &StadiumRaw) -> Vec<u8> {
+    let payload = encode_payload(stadium_raw);
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len() / 4);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+    let mut encoder = DeflateEncoder::new(bytes, Compression::best());
+    encoder
+        .write_all(&payload)
+        .and_then(|_| encoder.finish())
+        .expect("writing to a vector does not fail")
+}
+
+// the resolved stadium, loading it doesn't need traits or defaults
+pub fn encode_stadium(stadium: &Stadium) -> Vec<u8> {
+    encode_raw(&stadium.to_raw())
+}
+
+// the checksum of the header, identifies the content without decoding it
+pub fn checksum(bytes: &[u8]) -> Option<u32> {
+    if !is_binary(bytes) || bytes.len() < HEADER_LEN {
+        return None;
+    }
+    Some(u32::from_le_bytes(bytes[6..10].try_into().unwrap()))
+}
+
+// the checksum the stadium would have once encoded
+pub fn stadium_checksum(stadium_raw: &StadiumRaw) -> u32 {
+    crc32fast::hash(&encode_payload(stadium_raw))
+}
+
+pub fn decode_raw(bytes: &[u8]) -> Result<StadiumRaw, bevy::asset::Error> {
+    if !is_binary(bytes) || bytes.len() < HEADER_LEN {
+        return Err(bevy::asset::Error::msg("Not a binary stadium"));
+    }
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    if version != VERSION {
+        return Err(bevy::asset::Error::msg(format!(
+            "Unsupported binary stadium version {} (expected {})",
+            version, VERSION
+        )));
+    }
+    let expected = checksum(bytes).unwrap();
+
+    let mut payload = vec![];
+    DeflateDecoder::new(&bytes[HEADER_LEN..])
+        .take(MAX_PAYLOAD_BYTES + 1)
+        .read_to_end(&mut payload)?;
+    if payload.len() as u64 > MAX_PAYLOAD_BYTES {
+        return Err(bevy::asset::Error::msg(format!(
+            "Binary stadium payload is larger than {} bytes",
+            MAX_PAYLOAD_BYTES
+        )));
+    }
+    let actual = crc32fast::hash(&payload);
+    if actual != expected {
+        return Err(bevy::asset::Error::msg(format!(
+            "Corrupted binary stadium (checksum {:08x}, expected {:08x})",
+            actual, expected
+        )));
+    }
+    Ok(rmp_serde::from_slice(&payload)?)
+}
+
+pub fn decode_stadium(bytes: &[u8]) -> Result<Stadium, bevy::asset::Error> {
+    resolve_stadium(&decode_raw(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::stadium::parse_stadium, test_utils::stadium_files};
+
+    #[test]
+    fn binary_round_trip() {
+        for path in stadium_files() {
+            let json = std::fs::read(&path).unwrap();
+            let (stadium, raw) = parse_stadium(&json).unwrap();
+
+            let bytes = encode_raw(&raw);
+            assert!(bytes.len() < json.len(), "{}", path.display());
+            assert_eq!(checksum(&bytes), Some(stadium_checksum(&raw)));
+            let decoded = decode_raw(&bytes).unwrap();
+            assert_eq!(decoded.to_json(), raw.to_json(), "{}", path.display());
+
+            let decoded = decode_stadium(&encode_stadium(&stadium)).unwrap();
+            assert_eq!(decoded.to_hbs(), stadium.to_hbs(), "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn invalid_binaries_are_rejected() {
+        let (_, raw) = parse_stadium(br#"{ "name": "Small", "bg": {} }"#).unwrap();
+        let bytes = encode_raw(&raw);
+
+        assert!(decode_raw(b"{}").is_err());
+        let mut future = bytes.clone();
+        future[4] = 99;
+        assert!(decode_raw(&future).is_err());
+        let mut corrupted = bytes.clone();
+        corrupted[6] ^= 1;
+        assert!(decode_raw(&corrupted).is_err());
+
+        let mut oversized = bytes[..HEADER_LEN].to_vec();
+        let mut encoder = DeflateEncoder::new(&mut oversized, Compression::best());
+        encoder
+            .write_all(&vec![0; MAX_PAYLOAD_BYTES as usize + 1])
+            .unwrap();
+        encoder.finish().unwrap();
+        let error = decode_raw(&oversized).unwrap_err().to_string();
+        assert!(error.contains("larger than"), "{}", error);
+    }
+}
@@ -1,12 +1,15 @@
 use bevy::{math::DVec2, prelude::*};
 use bevy_prototype_lyon::prelude::*;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
 use std::collections::HashMap;
 
 use super::{
     hx_trait::{Trait, Traitable},
-    utils::{parse_collision, parse_color, BouncingCoef, Collision, CollisionFlag, Position},
+    utils::{
+        collision_to_strings, color_to_value, parse_collision, parse_color, BouncingCoef,
+        Collision, CollisionFlag, Position,
+    },
 };
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -22,8 +25,11 @@ pub struct DiscRaw {
     pub color: Option<Value>,
     pub c_group: Option<Vec<String>>,
     pub c_mask: Option<Vec<String>>,
+    pub vis: Option<bool>,
     #[serde(rename = "trait")]
     pub hx_trait: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl Default for DiscRaw {
@@ -39,39 +45,14 @@ impl Default for DiscRaw {
             color: Some(Value::String("FFFFFF".to_string())),
             c_group: Some(vec!["all".to_string()]),
             c_mask: Some(vec!["all".to_string()]),
+            vis: Some(true),
             hx_trait: None,
+            extra: Map::new(),
         }
     }
 }
 
-impl Traitable for DiscRaw {
-    fn apply_trait(&self, traits: &HashMap<String, Trait>) -> DiscRaw {
-        let tr_def = Trait::default();
-        let tr_d = match &self.hx_trait {
-            Some(tr_name) => traits.get(tr_name).unwrap_or(&tr_def),
-            None => &tr_def,
-        };
-        let radius = self.radius.or(tr_d.radius);
-        let inv_mass = self.inv_mass.or(tr_d.inv_mass);
-        let damping = self.damping.or(tr_d.damping);
-        let b_coef = self.b_coef.or(tr_d.b_coef);
-        let color = self.color.as_ref().or(tr_d.color.as_ref()).cloned();
-        let c_group = self.c_group.as_ref().or(tr_d.c_group.as_ref()).cloned();
-        let c_mask = self.c_mask.as_ref().or(tr_d.c_mask.as_ref()).cloned();
-        let hx_trait = self.hx_trait.clone();
-        DiscRaw {
-            radius,
-            inv_mass,
-            damping,
-            b_coef,
-            color,
-            c_group,
-            c_mask,
-            hx_trait,
-            ..*self
-        }
-    }
-}
+impl Traitable for DiscRaw {}
 
 impl DiscRaw {
     pub fn apply_default(&self) -> DiscRaw {
@@ -87,12 +68,14 @@ impl DiscRaw {
             color: self.color.as_ref().or(d_def.color.as_ref()).cloned(),
             c_group: self.c_group.as_ref().or(d_def.c_group.as_ref()).cloned(),
             c_mask: self.c_mask.as_ref().or(d_def.c_mask.as_ref()).cloned(),
+            vis: self.vis.or(d_def.vis),
             hx_trait: self.hx_trait.clone(),
+            extra: self.extra.clone(),
         }
     }
 
-    pub fn to_disc(&self, traits: &HashMap<String, Trait>) -> Disc {
-        let disc_raw = self.apply_trait(traits).apply_default();
+    pub fn to_disc(&self, traits: &HashMap<String, Trait>) -> Result<Disc, String> {
+        let disc_raw = self.apply_trait(traits)?.apply_default();
         let position = DVec2::from(disc_raw.pos);
         let speed = DVec2::from(disc_raw.speed.unwrap());
         let gravity = DVec2::from(disc_raw.gravity.unwrap());
@@ -100,10 +83,11 @@ impl DiscRaw {
         let inv_mass = disc_raw.inv_mass.unwrap();
         let damping = disc_raw.damping.unwrap();
         let b_coef = disc_raw.b_coef.unwrap();
-        let color = parse_color(&disc_raw.color.unwrap(), true);
-        let c_group = parse_collision(&disc_raw.c_group.unwrap());
-        let c_mask = parse_collision(&disc_raw.c_mask.unwrap());
-        Disc {
+        let color = parse_color(&disc_raw.color.unwrap(), true)?;
+        let c_group = parse_collision(&disc_raw.c_group.unwrap())?;
+        let c_mask = parse_collision(&disc_raw.c_mask.unwrap())?;
+        let vis = disc_raw.vis.unwrap();
+        Ok(Disc {
             position,
             speed,
             gravity,
@@ -114,7 +98,8 @@ impl DiscRaw {
             color,
             c_group,
             c_mask,
-        }
+            vis,
+        })
     }
 }
 
@@ -130,6 +115,7 @@ pub struct Disc {
     pub color: Color,
     pub c_group: CollisionFlag,
     pub c_mask: CollisionFlag,
+    pub vis: bool,
 }
 
 #[derive(Component, Debug, Clone, Copy)]
@@ -137,6 +123,10 @@ pub struct DiscComp {
     pub index: usize,
 }
 
+// position of the disc when the stadium was loaded, used for kickoff resets
+#[derive(Component, Debug, Clone, Copy)]
+pub struct SpawnPosition(pub DVec2);
+
 #[derive(Component, Debug, Clone, Copy)]
 pub struct Radius(pub f64);
 
@@ -153,10 +143,28 @@ pub struct InverseMass(pub f64);
 pub struct Damping(pub f64);
 
 impl Disc {
-    pub fn spawn(&self, stadium_parent: &mut ChildBuilder, index: usize) {
+    pub fn to_raw(self) -> DiscRaw {
+        DiscRaw {
+            pos: self.position.to_array(),
+            speed: Some(self.speed.to_array()),
+            gravity: Some(self.gravity.to_array()),
+            radius: Some(self.radius),
+            inv_mass: Some(self.inv_mass),
+            damping: Some(self.damping),
+            b_coef: Some(self.b_coef),
+            color: Some(color_to_value(self.color)),
+            c_group: Some(collision_to_strings(self.c_group)),
+            c_mask: Some(collision_to_strings(self.c_mask)),
+            vis: Some(self.vis),
+            hx_trait: None,
+            extra: Map::new(),
+        }
+    }
+
+    pub fn bundle(&self, index: usize) -> impl Bundle {
         let z = 0.3 + index as f32 * 0.001;
 
-        stadium_parent.spawn((
+        (
             DiscComp { index },
             (
                 ShapeBundle {
@@ -169,12 +177,18 @@ impl Disc {
                         self.position.y as f32,
                         z,
                     ),
+                    visibility: if self.vis {
+                        Visibility::Inherited
+                    } else {
+                        Visibility::Hidden
+                    },
                     ..default()
                 },
                 Fill::color(self.color),
                 Stroke::new(Color::BLACK, 1.5),
             ),
             Position(self.position),
+            SpawnPosition(self.position),
             Velocity(self.speed),
             Gravity(self.gravity),
             Radius(self.radius),
@@ -185,6 +199,10 @@ impl Disc {
                 group: self.c_group,
                 mask: self.c_mask,
             },
-        ));
+        )
+    }
+
+    pub fn spawn(&self, stadium_parent: &mut ChildBuilder, index: usize) {
+        stadium_parent.spawn(self.bundle(index));
     }
 }
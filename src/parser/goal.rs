@@ -2,25 +2,29 @@ use super::utils::Team;
 use bevy::math::DVec2;
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GoalRaw {
-    p0: [f64; 2],
-    p1: [f64; 2],
-    team: String,
+    pub p0: [f64; 2],
+    pub p1: [f64; 2],
+    pub team: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl GoalRaw {
-    pub fn to_goal(&self) -> Goal {
-        Goal {
+    pub fn to_goal(&self) -> Result<Goal, String> {
+        let team = match self.team.as_str() {
+            "red" => Team::Red,
+            "blue" => Team::Blue,
+            team => return Err(format!("unknown team \"{}\"", team)),
+        };
+        Ok(Goal {
             p0: DVec2::from(self.p0),
             p1: DVec2::from(self.p1),
-            team: match self.team.as_str() {
-                "red" => Team::Red,
-                "blue" => Team::Blue,
-                _ => panic!("Invalid team name"),
-            },
-        }
+            team,
+        })
     }
 }
 
@@ -39,6 +43,19 @@ pub struct GoalComp {
 }
 
 impl Goal {
+    pub fn to_raw(&self) -> GoalRaw {
+        GoalRaw {
+            p0: self.p0.to_array(),
+            p1: self.p1.to_array(),
+            team: match self.team {
+                Team::Blue => "blue",
+                _ => "red",
+            }
+            .to_string(),
+            extra: Map::new(),
+        }
+    }
+
     pub fn spawn(&self, stadium_parent: &mut ChildBuilder) {
         stadium_parent.spawn(GoalComp {
             p0: self.p0,
@@ -1,5 +1,5 @@
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::collections::HashMap;
 
 // in the game files, the trait can have any properties
@@ -21,30 +21,236 @@ pub struct Trait {
     pub bias: Option<f64>,
     pub curve: Option<f64>,
     pub curve_f: Option<f64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-pub fn handle_traits(hx_traits: Value) -> HashMap<String, Trait> {
+// fails when a property of a trait has the wrong type, e.g. "bCoef": "hard"
+pub fn handle_traits(hx_traits: Value) -> Result<HashMap<String, Trait>, String> {
     match hx_traits {
-        Value::Object(map) => map.into_iter().fold(HashMap::new(), |mut acc, (k, v)| {
-            acc.insert(k, serde_json::from_value(v).unwrap());
-            acc
-        }),
-        Value::Array(sequence) => {
-            // Handle empty sequence case
-            if sequence.is_empty() {
-                HashMap::new()
-            } else {
-                println!("Invalid property format");
-                HashMap::new()
-            }
-        }
-        _ => {
-            println!("Invalid property format");
-            HashMap::new()
+        Value::Object(map) => map
+            .into_iter()
+            .map(|(k, v)| match serde_json::from_value(v) {
+                Ok(hx_trait) => Ok((k, hx_trait)),
+                Err(e) => Err(format!("traits.{}: {}", k, e)),
+            })
+            .collect(),
+        // Handle empty sequence case
+        Value::Array(sequence) if sequence.is_empty() => Ok(HashMap::new()),
+        _ => Err("traits: expected an object".to_string()),
+    }
+}
+
+// properties that set the same value, when the object sets one of them
+// the others are not taken from the trait
+const LINKED_PROPERTIES: [&[&str]; 1] = [&["curve", "curveF"]];
+
+// the properties of the trait named by the object are used as defaults,
+// the properties of the object override them
+pub fn merge_trait(object: &mut Map<String, Value>, traits: &HashMap<String, Trait>) {
+    let Some(hx_trait) = object
+        .get("trait")
+        .and_then(Value::as_str)
+        .and_then(|name| traits.get(name))
+    else {
+        return;
+    };
+    let Ok(Value::Object(properties)) = serde_json::to_value(hx_trait) else {
+        return;
+    };
+    let is_set = |key: &str| object.get(key).is_some_and(|v| !v.is_null());
+    let skipped = properties
+        .keys()
+        .filter(|key| {
+            is_set(key)
+                || LINKED_PROPERTIES.iter().any(|linked| {
+                    linked.contains(&key.as_str()) && linked.iter().any(|k| is_set(k))
+                })
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    for (key, value) in properties {
+        if !value.is_null() && !skipped.contains(&key) {
+            object.insert(key, value);
         }
     }
 }
 
-pub trait Traitable {
-    fn apply_trait(&self, traits: &HashMap<String, Trait>) -> Self;
+// the merge goes through JSON, so every property of the raw object can come from a trait
+pub trait Traitable: Serialize + DeserializeOwned {
+    // fails when a property of the trait has the wrong type for this object
+    fn apply_trait(&self, traits: &HashMap<String, Trait>) -> Result<Self, String> {
+        let Ok(Value::Object(mut object)) = serde_json::to_value(self) else {
+            return Err("a raw object must serialize to a JSON object".to_string());
+        };
+        merge_trait(&mut object, traits);
+        serde_json::from_value(Value::Object(object)).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::{math::DVec2, prelude::Color};
+    use serde_json::json;
+
+    use crate::parser::{
+        segment::Segment,
+        stadium::{resolve_stadium, Stadium, StadiumRaw},
+        utils::CollisionFlag,
+    };
+
+    // a stadium using the trait "t" on the object under test
+    fn resolve(traits: Value, key: &str, objects: Value) -> Stadium {
+        let mut stadium = json!({
+            "name": "Traits",
+            "bg": {},
+            "traits": { "t": traits },
+            "vertexes": [{ "x": 0, "y": 0 }, { "x": 10, "y": 0 }],
+        });
+        stadium[key] = objects;
+        let raw: StadiumRaw = serde_json::from_value(stadium).unwrap();
+        resolve_stadium(&raw).unwrap()
+    }
+
+    fn curve(segment: &Segment) -> Option<f64> {
+        match segment {
+            Segment::Curved(curved) => Some(curved.curve),
+            Segment::Straight(_) => None,
+        }
+    }
+
+    #[test]
+    fn invalid_traits_are_errors() {
+        let stadium = json!({
+            "name": "Traits",
+            "bg": {},
+            "traits": { "t": { "bCoef": "hard" } },
+        });
+        let raw: StadiumRaw = serde_json::from_value(stadium).unwrap();
+        let error = resolve_stadium(&raw).unwrap_err().to_string();
+        assert!(error.starts_with("traits.t: "), "{}", error);
+
+        let stadium = json!({ "name": "Traits", "bg": {}, "traits": 5 });
+        let raw: StadiumRaw = serde_json::from_value(stadium).unwrap();
+        let error = resolve_stadium(&raw).unwrap_err().to_string();
+        assert_eq!(error, "traits: expected an object");
+    }
+
+    #[test]
+    fn vertex_properties() {
+        let traits = json!({ "bCoef": 0.2, "cGroup": ["red"], "cMask": ["ball"] });
+        let vertexes = json!([{ "x": 0, "y": 0, "trait": "t" }, { "x": 0, "y": 0, "trait": "t", "bCoef": 0.5 }]);
+        let stadium = resolve(traits, "vertexes", vertexes);
+        let vertex = &stadium.vertexes[0];
+        assert_eq!(vertex.b_coef, 0.2);
+        assert_eq!(vertex.c_group, CollisionFlag::RED);
+        assert_eq!(vertex.c_mask, CollisionFlag::BALL);
+        assert_eq!(stadium.vertexes[1].b_coef, 0.5);
+    }
+
+    #[test]
+    fn segment_properties() {
+        let traits = json!({
+            "bCoef": 0.1, "bias": 5, "vis": false, "color": "FF0000",
+            "cGroup": ["blue"], "cMask": ["red"], "curve": 90
+        });
+        let segments = json!([
+            { "v0": 0, "v1": 1, "trait": "t" },
+            { "v0": 0, "v1": 1, "trait": "t", "bias": -5, "vis": true, "curve": 0 }
+        ]);
+        let stadium = resolve(traits, "segments", segments);
+        let Segment::Curved(segment) = &stadium.segments[0] else {
+            panic!("the curve of the trait is not applied");
+        };
+        assert!((segment.curve - 1.0).abs() < 1e-9);
+        assert_eq!(segment.b_coef, 0.1);
+        assert_eq!(segment.bias, 5.0);
+        assert!(!segment.vis);
+        assert_eq!(segment.color, Color::rgb_u8(255, 0, 0));
+        assert_eq!(segment.c_group, CollisionFlag::BLUE);
+        assert_eq!(segment.c_mask, CollisionFlag::RED);
+
+        let Segment::Straight(segment) = &stadium.segments[1] else {
+            panic!("the curve of the object does not override the trait");
+        };
+        assert_eq!(segment.bias, -5.0);
+        assert!(segment.vis);
+    }
+
+    #[test]
+    fn segment_curve_f() {
+        let segments = json!([
+            { "v0": 0, "v1": 1, "trait": "t" },
+            { "v0": 0, "v1": 1, "trait": "t", "curve": 90 },
+            { "v0": 0, "v1": 1, "trait": "t", "curveF": 2 }
+        ]);
+        let stadium = resolve(json!({ "curveF": 0.5 }), "segments", segments.clone());
+        assert_eq!(curve(&stadium.segments[0]), Some(0.5));
+        // the curve of the object replaces the curveF of the trait
+        assert!((curve(&stadium.segments[1]).unwrap() - 1.0).abs() < 1e-9);
+        assert_eq!(curve(&stadium.segments[2]), Some(2.0));
+
+        // and the curveF of the object replaces the curve of the trait
+        let stadium = resolve(json!({ "curve": 90 }), "segments", segments);
+        assert_eq!(curve(&stadium.segments[2]), Some(2.0));
+    }
+
+    #[test]
+    fn disc_properties() {
+        let traits = json!({
+            "radius": 3, "invMass": 0, "damping": 0.5, "bCoef": 0.1, "speed": [1, 2],
+            "gravity": [0, 0.1], "color": "00FF00", "cGroup": ["wall"], "cMask": ["blue"],
+            "vis": false
+        });
+        let discs = json!([
+            { "pos": [0, 0], "trait": "t" },
+            { "pos": [0, 0], "trait": "t", "radius": 6, "speed": [0, 0] }
+        ]);
+        let stadium = resolve(traits, "discs", discs);
+        let disc = &stadium.discs[0];
+        assert_eq!(disc.radius, 3.0);
+        assert_eq!(disc.inv_mass, 0.0);
+        assert_eq!(disc.damping, 0.5);
+        assert_eq!(disc.b_coef, 0.1);
+        assert_eq!(disc.speed, DVec2::new(1.0, 2.0));
+        assert_eq!(disc.gravity, DVec2::new(0.0, 0.1));
+        assert_eq!(disc.color, Color::rgb_u8(0, 255, 0));
+        assert_eq!(disc.c_group, CollisionFlag::WALL);
+        assert_eq!(disc.c_mask, CollisionFlag::BLUE);
+        assert!(!disc.vis);
+        assert_eq!(stadium.discs[1].radius, 6.0);
+        assert_eq!(stadium.discs[1].speed, DVec2::ZERO);
+    }
+
+    #[test]
+    fn plane_properties() {
+        let traits = json!({ "bCoef": 0.3, "cGroup": ["c0"], "cMask": ["c1"] });
+        let planes = json!([{ "normal": [0, 1], "dist": 0, "trait": "t" }]);
+        let stadium = resolve(traits, "planes", planes);
+        let plane = &stadium.planes[0];
+        assert_eq!(plane.b_coef, 0.3);
+        assert_eq!(plane.c_group, CollisionFlag::C0);
+        assert_eq!(plane.c_mask, CollisionFlag::C1);
+    }
+
+    #[test]
+    fn ball_physics_properties() {
+        let traits = json!({ "radius": 7, "bCoef": 0.4, "speed": [1, 0] });
+        let ball = json!({ "trait": "t", "bCoef": 0.6 });
+        let ball = resolve(traits, "ballPhysics", ball).ball_physics.0;
+        assert_eq!(ball.radius, 7.0);
+        assert_eq!(ball.b_coef, 0.6);
+        assert_eq!(ball.speed, DVec2::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn player_physics_properties() {
+        let traits = json!({ "radius": 12, "kickStrength": 7, "acceleration": 0.2 });
+        let player = json!({ "trait": "t", "acceleration": 0.12 });
+        let player = resolve(traits, "playerPhysics", player).player_physics;
+        assert_eq!(player.radius, 12.0);
+        assert_eq!(player.kick_strength, 7.0);
+        assert_eq!(player.acceleration, 0.12);
+    }
 }
@@ -1,5 +1,6 @@
 pub mod background;
 pub mod ball_physics;
+pub mod binary;
 pub mod disc;
 pub mod goal;
 pub mod hx_trait;
@@ -7,5 +8,6 @@ pub mod plane;
 pub mod player_physics;
 pub mod segment;
 pub mod stadium;
+pub mod transform;
 pub mod utils;
 pub mod vertex;
@@ -1,23 +1,26 @@
 use bevy::math::DVec2;
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::collections::HashMap;
 
 use super::{
     hx_trait::{Trait, Traitable},
-    utils::{parse_collision, BouncingCoef, Collision, CollisionFlag},
+    utils::{collision_to_strings, parse_collision, BouncingCoef, Collision, CollisionFlag},
 };
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct PlaneRaw {
-    normal: [f64; 2],
-    dist: f64,
-    b_coef: Option<f64>,
-    c_group: Option<Vec<String>>,
-    c_mask: Option<Vec<String>>,
+    pub normal: [f64; 2],
+    pub dist: f64,
+    pub b_coef: Option<f64>,
+    pub c_group: Option<Vec<String>>,
+    pub c_mask: Option<Vec<String>>,
     #[serde(rename = "trait")]
-    hx_trait: Option<String>,
+    pub hx_trait: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl Default for PlaneRaw {
@@ -29,30 +32,12 @@ impl Default for PlaneRaw {
             c_group: Some(vec!["wall".to_string()]),
             c_mask: Some(vec!["all".to_string()]),
             hx_trait: None,
+            extra: Map::new(),
         }
     }
 }
 
-impl Traitable for PlaneRaw {
-    fn apply_trait(&self, traits: &HashMap<String, Trait>) -> PlaneRaw {
-        let tr_def = Trait::default();
-        let tr_d = match &self.hx_trait {
-            Some(tr_name) => traits.get(tr_name).unwrap_or(&tr_def),
-            None => &tr_def,
-        };
-        let b_coef = self.b_coef.or(tr_d.b_coef);
-        let c_group = self.c_group.as_ref().or(tr_d.c_group.as_ref()).cloned();
-        let c_mask = self.c_mask.as_ref().or(tr_d.c_mask.as_ref()).cloned();
-        let hx_trait = self.hx_trait.clone();
-        PlaneRaw {
-            b_coef,
-            c_group,
-            c_mask,
-            hx_trait,
-            ..*self
-        }
-    }
-}
+impl Traitable for PlaneRaw {}
 
 impl PlaneRaw {
     pub fn apply_default(&self) -> PlaneRaw {
@@ -64,23 +49,24 @@ impl PlaneRaw {
             c_group: self.c_group.as_ref().or(default.c_group.as_ref()).cloned(),
             c_mask: self.c_mask.as_ref().or(default.c_mask.as_ref()).cloned(),
             hx_trait: self.hx_trait.clone().or(default.hx_trait),
+            extra: self.extra.clone(),
         }
     }
 
-    pub fn to_plane(&self, traits: &HashMap<String, Trait>) -> Plane {
-        let plane_raw = self.apply_trait(traits).apply_default();
+    pub fn to_plane(&self, traits: &HashMap<String, Trait>) -> Result<Plane, String> {
+        let plane_raw = self.apply_trait(traits)?.apply_default();
         let normal = DVec2::from(plane_raw.normal);
         let dist = plane_raw.dist;
         let b_coef = plane_raw.b_coef.unwrap();
-        let c_group = parse_collision(plane_raw.c_group.as_ref().unwrap());
-        let c_mask = parse_collision(plane_raw.c_mask.as_ref().unwrap());
-        Plane {
+        let c_group = parse_collision(plane_raw.c_group.as_ref().unwrap())?;
+        let c_mask = parse_collision(plane_raw.c_mask.as_ref().unwrap())?;
+        Ok(Plane {
             normal,
             dist,
             b_coef,
             c_group,
             c_mask,
-        }
+        })
     }
 }
 
@@ -100,6 +86,18 @@ pub struct PlaneComp {
 }
 
 impl Plane {
+    pub fn to_raw(&self) -> PlaneRaw {
+        PlaneRaw {
+            normal: self.normal.to_array(),
+            dist: self.dist,
+            b_coef: Some(self.b_coef),
+            c_group: Some(collision_to_strings(self.c_group)),
+            c_mask: Some(collision_to_strings(self.c_mask)),
+            hx_trait: None,
+            extra: Map::new(),
+        }
+    }
+
     pub fn spawn(&self, stadium_parent: &mut ChildBuilder) {
         stadium_parent.spawn((
             PlaneComp {
@@ -1,7 +1,12 @@
 use bevy::math::DVec2;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
 
-use super::utils::{parse_collision, CollisionFlag};
+use super::{
+    hx_trait::{Trait, Traitable},
+    utils::{collision_to_strings, parse_collision, CollisionFlag},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -17,6 +22,10 @@ pub struct PlayerPhysicsRaw {
     pub kicking_damping: Option<f64>,
     pub kick_strength: Option<f64>,
     pub kickback: Option<f64>,
+    #[serde(rename = "trait")]
+    pub hx_trait: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl Default for PlayerPhysicsRaw {
@@ -33,10 +42,14 @@ impl Default for PlayerPhysicsRaw {
             kicking_damping: Some(0.96),
             kick_strength: Some(5.0),
             kickback: Some(0.0),
+            hx_trait: None,
+            extra: Map::new(),
         }
     }
 }
 
+impl Traitable for PlayerPhysicsRaw {}
+
 impl PlayerPhysicsRaw {
     pub fn apply_default(&self) -> PlayerPhysicsRaw {
         let pp_def = PlayerPhysicsRaw::default();
@@ -52,23 +65,28 @@ impl PlayerPhysicsRaw {
             kicking_damping: self.kicking_damping.or(pp_def.kicking_damping),
             kick_strength: self.kick_strength.or(pp_def.kick_strength),
             kickback: self.kickback.or(pp_def.kickback),
+            hx_trait: self.hx_trait.clone(),
+            extra: self.extra.clone(),
         }
     }
 
-    pub fn to_player_physics(&self) -> PlayerPhysics {
-        let pp_def = self.apply_default();
+    pub fn to_player_physics(
+        &self,
+        traits: &HashMap<String, Trait>,
+    ) -> Result<PlayerPhysics, String> {
+        let pp_def = self.apply_trait(traits)?.apply_default();
         let gravity = DVec2::from(pp_def.gravity.unwrap());
         let radius = pp_def.radius.unwrap();
         let inv_mass = pp_def.inv_mass.unwrap();
         let b_coef = pp_def.b_coef.unwrap();
         let damping = pp_def.damping.unwrap();
-        let c_group = parse_collision(&pp_def.c_group.unwrap());
+        let c_group = parse_collision(&pp_def.c_group.unwrap())?;
         let acceleration = pp_def.acceleration.unwrap();
         let kicking_acceleration = pp_def.kicking_acceleration.unwrap();
         let kicking_damping = pp_def.kicking_damping.unwrap();
         let kick_strength = pp_def.kick_strength.unwrap();
         let kickback = pp_def.kickback.unwrap();
-        PlayerPhysics {
+        Ok(PlayerPhysics {
             gravity,
             radius,
             inv_mass,
@@ -80,7 +98,7 @@ impl PlayerPhysicsRaw {
             kicking_damping,
             kick_strength,
             kickback,
-        }
+        })
     }
 }
 
@@ -98,3 +116,23 @@ pub struct PlayerPhysics {
     pub kick_strength: f64,
     pub kickback: f64,
 }
+
+impl PlayerPhysics {
+    pub fn to_raw(&self) -> PlayerPhysicsRaw {
+        PlayerPhysicsRaw {
+            gravity: Some(self.gravity.to_array()),
+            radius: Some(self.radius),
+            inv_mass: Some(self.inv_mass),
+            b_coef: Some(self.b_coef),
+            damping: Some(self.damping),
+            c_group: Some(collision_to_strings(self.c_group)),
+            acceleration: Some(self.acceleration),
+            kicking_acceleration: Some(self.kicking_acceleration),
+            kicking_damping: Some(self.kicking_damping),
+            kick_strength: Some(self.kick_strength),
+            kickback: Some(self.kickback),
+            hx_trait: None,
+            extra: Map::new(),
+        }
+    }
+}
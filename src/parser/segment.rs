@@ -7,29 +7,34 @@ use std::{
 use bevy::{math::DVec2, prelude::*};
 use bevy_prototype_lyon::prelude::*;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
 
 use super::{
     hx_trait::{Trait, Traitable},
-    utils::{arc, parse_collision, parse_color, BouncingCoef, Collision, CollisionFlag},
+    utils::{
+        arc, collision_to_strings, color_to_value, parse_collision, parse_color, BouncingCoef,
+        Collision, CollisionFlag,
+    },
     vertex::Vertex,
 };
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SegmentRaw {
-    v0: usize,
-    v1: usize,
-    b_coef: Option<f64>,
-    curve: Option<f64>,
-    curve_f: Option<f64>,
-    bias: Option<f64>,
-    c_group: Option<Vec<String>>,
-    c_mask: Option<Vec<String>>,
-    vis: Option<bool>,
-    color: Option<Value>,
+    pub v0: usize,
+    pub v1: usize,
+    pub b_coef: Option<f64>,
+    pub curve: Option<f64>,
+    pub curve_f: Option<f64>,
+    pub bias: Option<f64>,
+    pub c_group: Option<Vec<String>>,
+    pub c_mask: Option<Vec<String>>,
+    pub vis: Option<bool>,
+    pub color: Option<Value>,
     #[serde(rename = "trait")]
-    hx_trait: Option<String>,
+    pub hx_trait: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl Default for SegmentRaw {
@@ -46,40 +51,12 @@ impl Default for SegmentRaw {
             vis: Some(true),
             color: Some(Value::String("000000".to_string())),
             hx_trait: None,
+            extra: Map::new(),
         }
     }
 }
 
-impl Traitable for SegmentRaw {
-    fn apply_trait(&self, traits: &HashMap<String, Trait>) -> SegmentRaw {
-        let tr_def = Trait::default();
-        let tr_d = match &self.hx_trait {
-            Some(tr_name) => traits.get(tr_name).unwrap_or(&tr_def),
-            None => &tr_def,
-        };
-        let b_coef = self.b_coef.or(tr_d.b_coef);
-        let curve = self.curve.or(tr_d.curve);
-        let curve_f = self.curve_f.or(tr_d.curve_f);
-        let bias = self.bias.or(tr_d.bias);
-        let c_group = self.c_group.as_ref().or(tr_d.c_group.as_ref()).cloned();
-        let c_mask = self.c_mask.as_ref().or(tr_d.c_mask.as_ref()).cloned();
-        let vis = self.vis.or(tr_d.vis);
-        let color = self.color.as_ref().or(tr_d.color.as_ref()).cloned();
-        let hx_trait = self.hx_trait.clone();
-        SegmentRaw {
-            b_coef,
-            curve,
-            curve_f,
-            bias,
-            c_group,
-            c_mask,
-            vis,
-            color,
-            hx_trait,
-            ..*self
-        }
-    }
-}
+impl Traitable for SegmentRaw {}
 
 impl SegmentRaw {
     pub fn apply_default(&self) -> SegmentRaw {
@@ -94,20 +71,22 @@ impl SegmentRaw {
             vis: self.vis.or(s_def.vis),
             color: self.color.as_ref().or(s_def.color.as_ref()).cloned(),
             hx_trait: self.hx_trait.clone(),
+            extra: self.extra.clone(),
             ..*self
         }
     }
 
-    fn to_straight(&self, traits: &HashMap<String, Trait>) -> StraightSegment {
-        let segment_raw = self.apply_trait(traits).apply_default();
+    // the trait is already merged by to_segment
+    fn to_straight(&self) -> Result<StraightSegment, String> {
+        let segment_raw = self.apply_default();
         let vertex_indices = (segment_raw.v0, segment_raw.v1);
         let b_coef = segment_raw.b_coef.unwrap();
         let bias = segment_raw.bias.unwrap();
-        let c_group = parse_collision(&segment_raw.c_group.unwrap());
-        let c_mask = parse_collision(&segment_raw.c_mask.unwrap());
+        let c_group = parse_collision(&segment_raw.c_group.unwrap())?;
+        let c_mask = parse_collision(&segment_raw.c_mask.unwrap())?;
         let vis = segment_raw.vis.unwrap();
-        let color = parse_color(&segment_raw.color.unwrap(), false);
-        StraightSegment {
+        let color = parse_color(&segment_raw.color.unwrap(), false)?;
+        Ok(StraightSegment {
             vertex_indices,
             b_coef,
             bias,
@@ -115,21 +94,23 @@ impl SegmentRaw {
             c_mask,
             vis,
             color,
-        }
+        })
     }
 
-    fn to_curved(&self, traits: &HashMap<String, Trait>) -> CurvedSegment {
-        CurvedSegment::new(self, traits)
+    fn to_curved(&self) -> Result<CurvedSegment, String> {
+        CurvedSegment::new(self)
     }
 
-    pub fn to_segment(&self, traits: &HashMap<String, Trait>) -> Segment {
-        match self.curve_f {
-            Some(curve_f) if curve_f != 0.0 => Segment::Curved(self.to_curved(traits)),
-            _ => match self.curve {
-                Some(curve) if curve != 0.0 => Segment::Curved(self.to_curved(traits)),
-                _ => Segment::Straight(self.to_straight(traits)),
+    pub fn to_segment(&self, traits: &HashMap<String, Trait>) -> Result<Segment, String> {
+        // the curve can come from the trait
+        let segment_raw = self.apply_trait(traits)?;
+        Ok(match segment_raw.curve_f {
+            Some(curve_f) if curve_f != 0.0 => Segment::Curved(segment_raw.to_curved()?),
+            _ => match segment_raw.curve {
+                Some(curve) if curve != 0.0 => Segment::Curved(segment_raw.to_curved()?),
+                _ => Segment::Straight(segment_raw.to_straight()?),
             },
-        }
+        })
     }
 }
 
@@ -156,6 +137,23 @@ pub struct Bias(pub f64);
 pub struct Curve(pub f64);
 
 impl StraightSegment {
+    pub fn to_raw(&self) -> SegmentRaw {
+        SegmentRaw {
+            v0: self.vertex_indices.0,
+            v1: self.vertex_indices.1,
+            b_coef: Some(self.b_coef),
+            curve: None,
+            curve_f: None,
+            bias: Some(self.bias),
+            c_group: Some(collision_to_strings(self.c_group)),
+            c_mask: Some(collision_to_strings(self.c_mask)),
+            vis: Some(self.vis),
+            color: Some(color_to_value(self.color)),
+            hx_trait: None,
+            extra: Map::new(),
+        }
+    }
+
     fn spawn(&self, stadium_parent: &mut ChildBuilder, vertexes: &[Vertex], index: usize) {
         let v0 = vertexes.get(self.vertex_indices.0).unwrap();
         let v1 = vertexes.get(self.vertex_indices.1).unwrap();
@@ -238,11 +236,29 @@ impl CurvedUtils {
         }
         (angle_0, angle_1)
     }
+
+    // points along the arc from vertex 0 to vertex 1, for drawing outside of the game
+    pub fn arc_points(
+        vertex_0_pos: DVec2,
+        vertex_1_pos: DVec2,
+        curve: f64,
+        steps: usize,
+    ) -> Vec<DVec2> {
+        let center = CurvedUtils::circle_center(vertex_0_pos, vertex_1_pos, curve);
+        let radius = CurvedUtils::circle_radius(vertex_0_pos, vertex_1_pos, curve);
+        let (angle_0, angle_1) = CurvedUtils::circle_angles(vertex_0_pos, vertex_1_pos, curve);
+        (0..=steps)
+            .map(|step| {
+                let angle = angle_0 + (angle_1 - angle_0) * step as f64 / steps as f64;
+                center + DVec2::new(angle.cos(), angle.sin()) * radius
+            })
+            .collect()
+    }
 }
 
 impl CurvedSegment {
-    pub fn new(raw_segment: &SegmentRaw, traits: &HashMap<String, Trait>) -> CurvedSegment {
-        let base = raw_segment.to_straight(traits);
+    pub fn new(raw_segment: &SegmentRaw) -> Result<CurvedSegment, String> {
+        let base = raw_segment.to_straight()?;
         let mut curved_segment = CurvedSegment { base, curve: 0.0 };
 
         let curve = raw_segment.curve.unwrap_or(0.0);
@@ -250,7 +266,7 @@ impl CurvedSegment {
         let curve_final = curved_segment.get_curve(curve, curve_f);
         curved_segment.curve = curve_final;
 
-        curved_segment
+        Ok(curved_segment)
     }
 
     fn get_curve(&mut self, curve: f64, curve_f: f64) -> f64 {
@@ -328,6 +344,33 @@ pub enum Segment {
 }
 
 impl Segment {
+    // the shape of the segment, arcs are split in `steps` lines
+    pub fn points(&self, vertexes: &[DVec2], steps: usize) -> Vec<DVec2> {
+        let (indices, curve) = match self {
+            Segment::Straight(s) => (s.vertex_indices, None),
+            Segment::Curved(s) => (s.vertex_indices, Some(s.curve)),
+        };
+        let (Some(&p0), Some(&p1)) = (vertexes.get(indices.0), vertexes.get(indices.1)) else {
+            return vec![];
+        };
+
+        match curve {
+            Some(curve) => CurvedUtils::arc_points(p0, p1, curve, steps),
+            None => vec![p0, p1],
+        }
+    }
+
+    // the resolved curve is written as curveF, with the vertexes and bias already swapped
+    pub fn to_raw(&self) -> SegmentRaw {
+        match self {
+            Segment::Straight(segment) => segment.to_raw(),
+            Segment::Curved(segment) => SegmentRaw {
+                curve_f: Some(segment.curve),
+                ..segment.base.to_raw()
+            },
+        }
+    }
+
     pub fn spawn(&self, stadium_parent: &mut ChildBuilder, vertexes: &[Vertex], index: usize) {
         match self {
             Segment::Straight(segment) => segment.spawn(stadium_parent, vertexes, index),
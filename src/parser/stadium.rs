@@ -1,16 +1,21 @@
 use bevy::math::DVec2;
 use bevy::prelude::*;
+use jsonc_parser::{parse_to_serde_value, ParseOptions};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Map, Value};
+
+use std::path::Path;
 
 use super::background::{Background, BackgroundRaw};
 use super::ball_physics::{handle_ball, Ball};
+use super::binary;
 use super::disc::{Disc, DiscRaw};
 use super::goal::{Goal, GoalRaw};
-use super::hx_trait::handle_traits;
+use super::hx_trait::{handle_traits, Trait};
 use super::plane::{Plane, PlaneRaw};
 use super::player_physics::{PlayerPhysics, PlayerPhysicsRaw};
 use super::segment::{Segment, SegmentRaw};
+use super::utils::{fnv1a, strip_nulls};
 use super::vertex::{Vertex, VertexRaw};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -25,30 +30,32 @@ pub enum KickoffReset {
     Full,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct StadiumRaw {
-    name: String,
-    bg: BackgroundRaw,
-    width: Option<f64>,
-    height: Option<f64>,
-    camera_width: Option<f64>,
-    camera_height: Option<f64>,
-    max_view_width: Option<f64>,
-    camera_follow: Option<String>,
-    spawn_distance: Option<f64>,
-    can_be_stored: Option<bool>,
-    kick_off_reset: Option<String>,
-    traits: Option<Value>,
-    vertexes: Option<Vec<VertexRaw>>,
-    segments: Option<Vec<SegmentRaw>>,
-    goals: Option<Vec<GoalRaw>>,
-    discs: Option<Vec<DiscRaw>>,
-    planes: Option<Vec<PlaneRaw>>,
-    red_spawn_points: Option<Vec<Vec<f64>>>,
-    blue_spawn_points: Option<Vec<Vec<f64>>>,
-    player_physics: Option<PlayerPhysicsRaw>,
-    ball_physics: Option<Value>,
+    pub name: String,
+    pub bg: BackgroundRaw,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    pub camera_width: Option<f64>,
+    pub camera_height: Option<f64>,
+    pub max_view_width: Option<f64>,
+    pub camera_follow: Option<String>,
+    pub spawn_distance: Option<f64>,
+    pub can_be_stored: Option<bool>,
+    pub kick_off_reset: Option<String>,
+    pub traits: Option<Value>,
+    pub vertexes: Option<Vec<VertexRaw>>,
+    pub segments: Option<Vec<SegmentRaw>>,
+    pub goals: Option<Vec<GoalRaw>>,
+    pub discs: Option<Vec<DiscRaw>>,
+    pub planes: Option<Vec<PlaneRaw>>,
+    pub red_spawn_points: Option<Vec<Vec<f64>>>,
+    pub blue_spawn_points: Option<Vec<Vec<f64>>>,
+    pub player_physics: Option<PlayerPhysicsRaw>,
+    pub ball_physics: Option<Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl Default for StadiumRaw {
@@ -75,11 +82,64 @@ impl Default for StadiumRaw {
             blue_spawn_points: Some(vec![]),
             player_physics: Some(PlayerPhysicsRaw::default()),
             ball_physics: None,
+            extra: Map::new(),
         }
     }
 }
 
 impl StadiumRaw {
+    // serialize back to the HaxBall format, leaving out the unset properties
+    pub fn to_json(&self) -> Value {
+        let mut value = serde_json::to_value(self).unwrap();
+        strip_nulls(&mut value);
+        value
+    }
+
+    // paths of the properties the parser does not know about, e.g. "segments[3].goalPost"
+    pub fn unknown_fields(&self) -> Vec<String> {
+        let mut fields = extra_keys("", &self.extra);
+        fields.extend(extra_keys("bg", &self.bg.extra));
+        if let Some(Value::Object(traits)) = &self.traits {
+            for (name, value) in traits {
+                if let Ok(hx_trait) = serde_json::from_value::<Trait>(value.clone()) {
+                    fields.extend(extra_keys(&format!("traits.{}", name), &hx_trait.extra));
+                }
+            }
+        }
+        for (i, v) in self.vertexes.iter().flatten().enumerate() {
+            fields.extend(extra_keys(&format!("vertexes[{}]", i), &v.extra));
+        }
+        for (i, s) in self.segments.iter().flatten().enumerate() {
+            fields.extend(extra_keys(&format!("segments[{}]", i), &s.extra));
+        }
+        for (i, g) in self.goals.iter().flatten().enumerate() {
+            fields.extend(extra_keys(&format!("goals[{}]", i), &g.extra));
+        }
+        for (i, d) in self.discs.iter().flatten().enumerate() {
+            fields.extend(extra_keys(&format!("discs[{}]", i), &d.extra));
+        }
+        for (i, p) in self.planes.iter().flatten().enumerate() {
+            fields.extend(extra_keys(&format!("planes[{}]", i), &p.extra));
+        }
+        if let Some(player_physics) = &self.player_physics {
+            fields.extend(extra_keys("playerPhysics", &player_physics.extra));
+        }
+        if let Some(Value::Object(ball)) = &self.ball_physics {
+            // the ball is a disc without position
+            let mut ball = ball.clone();
+            ball.insert("pos".to_string(), json!([0.0, 0.0]));
+            if let Ok(disc) = serde_json::from_value::<DiscRaw>(Value::Object(ball)) {
+                fields.extend(extra_keys("ballPhysics", &disc.extra));
+            }
+        }
+        fields
+    }
+
+    // the .hbs text of the stadium, traits and unset properties are kept as written
+    pub fn to_hbs(&self) -> String {
+        serde_json::to_string_pretty(&self.to_json()).unwrap()
+    }
+
     pub fn apply_default(&self) -> StadiumRaw {
         let s_def = StadiumRaw::default();
         StadiumRaw {
@@ -104,13 +164,15 @@ impl StadiumRaw {
             blue_spawn_points: self.blue_spawn_points.clone().or(s_def.blue_spawn_points),
             player_physics: self.player_physics.clone().or(s_def.player_physics),
             ball_physics: self.ball_physics.clone().or(s_def.ball_physics),
+            extra: self.extra.clone(),
         }
     }
 
-    pub fn to_stadium(&self) -> Stadium {
+    // fails on the values HaxBall would reject, e.g. an unknown color, team or collision flag
+    pub fn to_stadium(&self) -> Result<Stadium, String> {
         let s_default = self.apply_default();
-        let traits = handle_traits(s_default.traits.unwrap());
-        let bg = self.bg.to_background();
+        let traits = handle_traits(s_default.traits.unwrap())?;
+        let bg = self.bg.to_background().map_err(|e| format!("bg: {}", e))?;
         let width = s_default.width.unwrap();
         let height = s_default.height.unwrap();
         let camera_width = s_default.camera_width.unwrap();
@@ -128,58 +190,33 @@ impl StadiumRaw {
             "full" => KickoffReset::Full,
             _ => KickoffReset::Partial,
         };
-        let vertexes = s_default
-            .vertexes
-            .clone()
-            .unwrap()
-            .iter()
-            .map(|v| v.to_vertex(&traits))
-            .collect();
-        let segments = s_default
-            .segments
-            .clone()
-            .unwrap()
-            .iter()
-            .map(|s| s.to_segment(&traits))
-            .collect();
-        let mut discs: Vec<Disc> = s_default
-            .discs
-            .clone()
-            .unwrap()
-            .iter()
-            .map(|d| d.to_disc(&traits))
-            .collect();
-        let goals = s_default
-            .goals
-            .clone()
-            .unwrap()
-            .iter()
-            .map(|g| g.to_goal())
-            .collect();
-        let planes = s_default
-            .planes
-            .clone()
-            .unwrap()
-            .iter()
-            .map(|p| p.to_plane(&traits))
-            .collect();
-        let red_spawn_points = s_default
-            .red_spawn_points
-            .clone()
+        let vertexes: Vec<Vertex> =
+            resolve_all("vertexes", &s_default.vertexes, |v| v.to_vertex(&traits))?;
+        let segments = resolve_all("segments", &s_default.segments, |s| {
+            if s.v0.max(s.v1) >= vertexes.len() {
+                return Err(format!(
+                    "vertex {} is out of range, the stadium has {} vertexes",
+                    s.v0.max(s.v1),
+                    vertexes.len()
+                ));
+            }
+            s.to_segment(&traits)
+        })?;
+        let mut discs = resolve_all("discs", &s_default.discs, |d| d.to_disc(&traits))?;
+        let goals = resolve_all("goals", &s_default.goals, |g| g.to_goal())?;
+        let planes = resolve_all("planes", &s_default.planes, |p| p.to_plane(&traits))?;
+        let red_spawn_points =
+            resolve_all("redSpawnPoints", &s_default.red_spawn_points, spawn_point)?;
+        let blue_spawn_points =
+            resolve_all("blueSpawnPoints", &s_default.blue_spawn_points, spawn_point)?;
+        let player_physics = s_default
+            .player_physics
             .unwrap()
-            .iter()
-            .map(|p| DVec2::new(p[0], p[1]))
-            .collect();
-        let blue_spawn_points = s_default
-            .blue_spawn_points
-            .clone()
-            .unwrap()
-            .iter()
-            .map(|p| DVec2::new(p[0], p[1]))
-            .collect();
-        let player_physics = s_default.player_physics.unwrap().to_player_physics();
-        let ball_physics = handle_ball(&s_default.ball_physics, &mut discs, &traits);
-        Stadium {
+            .to_player_physics(&traits)
+            .map_err(|e| format!("playerPhysics: {}", e))?;
+        let ball_physics = handle_ball(&s_default.ball_physics, &mut discs, &traits)
+            .map_err(|e| format!("ballPhysics: {}", e))?;
+        Ok(Stadium {
             name: self.name.clone(),
             bg,
             width,
@@ -200,10 +237,41 @@ impl StadiumRaw {
             blue_spawn_points,
             player_physics,
             ball_physics,
-        }
+        })
+    }
+}
+
+// resolves every object of a list, the error tells which one is invalid, e.g. "discs[2]: ..."
+fn resolve_all<R, T>(
+    key: &str,
+    objects: &Option<Vec<R>>,
+    resolve: impl Fn(&R) -> Result<T, String>,
+) -> Result<Vec<T>, String> {
+    objects
+        .iter()
+        .flatten()
+        .enumerate()
+        .map(|(i, object)| resolve(object).map_err(|e| format!("{}[{}]: {}", key, i, e)))
+        .collect()
+}
+
+fn spawn_point(point: &Vec<f64>) -> Result<DVec2, String> {
+    match point.as_slice() {
+        [x, y] => Ok(DVec2::new(*x, *y)),
+        _ => Err("a spawn point must be [x, y]".to_string()),
     }
 }
 
+fn extra_keys(path: &str, extra: &Map<String, Value>) -> Vec<String> {
+    extra
+        .keys()
+        .map(|key| match path {
+            "" => key.clone(),
+            _ => format!("{}.{}", path, key),
+        })
+        .collect()
+}
+
 #[derive(Resource, Debug, Clone)]
 pub struct Stadium {
     pub name: String,
@@ -233,23 +301,132 @@ pub struct StadiumComp {
     pub name: String,
     pub width: f64,
     pub height: f64,
-    pub can_be_stored: bool,
     pub red_spawn_points: Vec<DVec2>,
     pub blue_spawn_points: Vec<DVec2>,
     pub player_physics: PlayerPhysics,
     pub spawn_distance: f64,
     pub kick_off_reset: KickoffReset,
+    pub kick_off_radius: f64,
+    // content hash of the stadium, see Stadium::content_hash
+    pub hash: u64,
 }
 
-#[derive(Component, Debug, Clone)]
-pub struct StadiumCamera {
-    pub camera_width: f64,
-    pub camera_height: f64,
-    pub max_view_width: f64,
-    pub camera_follow: CameraFollow,
+// -0.0 and 0.0 are the same value but not the same text
+fn normalize_zeros(value: &mut Value) {
+    match value {
+        Value::Number(n) if n.as_f64() == Some(0.0) => *value = json!(0.0),
+        Value::Object(map) => map.values_mut().for_each(normalize_zeros),
+        Value::Array(arr) => arr.iter_mut().for_each(normalize_zeros),
+        _ => {}
+    }
+}
+
+// JSON with the keys of every object sorted, whatever the order of the map
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut keys = map.keys().collect::<Vec<_>>();
+            keys.sort();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(&map[key], out);
+            }
+            out.push('}');
+        }
+        Value::Array(arr) => {
+            out.push('[');
+            for (i, item) in arr.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        _ => out.push_str(&value.to_string()),
+    }
+}
+
+// the hash as shown to players
+pub fn format_hash(hash: u64) -> String {
+    format!("{:016x}", hash)
 }
 
 impl Stadium {
+    // identifies the map: the same geometry and physics give the same hash, whatever
+    // the formatting, the order of the properties, the traits used or the name
+    pub fn content_hash(&self) -> u64 {
+        let mut value = self.to_raw().to_json();
+        if let Value::Object(map) = &mut value {
+            map.remove("name");
+        }
+        normalize_zeros(&mut value);
+        let mut canonical = String::new();
+        write_canonical(&value, &mut canonical);
+        fnv1a(canonical.as_bytes())
+    }
+
+    // every property is written explicitly, without traits
+    // the unknown properties only live in StadiumRaw and are not part of the flattened stadium
+    pub fn to_raw(&self) -> StadiumRaw {
+        let camera_follow = match self.camera_follow {
+            CameraFollow::Player => "player",
+            CameraFollow::Ball => "ball",
+        };
+        let kick_off_reset = match self.kick_off_reset {
+            KickoffReset::Partial => "partial",
+            KickoffReset::Full => "full",
+        };
+        // the ball is written as the first disc so that its position is kept
+        let discs = std::iter::once(&self.ball_physics.0)
+            .chain(&self.discs)
+            .map(|d| d.to_raw())
+            .collect();
+        StadiumRaw {
+            name: self.name.clone(),
+            bg: self.bg.to_raw(),
+            width: Some(self.width),
+            height: Some(self.height),
+            camera_width: Some(self.camera_width),
+            camera_height: Some(self.camera_height),
+            max_view_width: Some(self.max_view_width),
+            camera_follow: Some(camera_follow.to_string()),
+            spawn_distance: Some(self.spawn_distance),
+            can_be_stored: Some(self.can_be_stored),
+            kick_off_reset: Some(kick_off_reset.to_string()),
+            traits: None,
+            vertexes: Some(self.vertexes.iter().map(|v| v.to_raw()).collect()),
+            segments: Some(self.segments.iter().map(|s| s.to_raw()).collect()),
+            goals: Some(self.goals.iter().map(|g| g.to_raw()).collect()),
+            discs: Some(discs),
+            planes: Some(self.planes.iter().map(|p| p.to_raw()).collect()),
+            red_spawn_points: Some(
+                self.red_spawn_points
+                    .iter()
+                    .map(|p| p.to_array().to_vec())
+                    .collect(),
+            ),
+            blue_spawn_points: Some(
+                self.blue_spawn_points
+                    .iter()
+                    .map(|p| p.to_array().to_vec())
+                    .collect(),
+            ),
+            player_physics: Some(self.player_physics.to_raw()),
+            ball_physics: Some(Value::String("disc0".to_string())),
+            extra: Map::new(),
+        }
+    }
+
+    pub fn to_hbs(&self) -> String {
+        self.to_raw().to_hbs()
+    }
+
     pub fn spawn(&self, commands: &mut Commands) {
         commands
             .spawn((
@@ -258,18 +435,13 @@ impl Stadium {
                     name: self.name.clone(),
                     width: self.width,
                     height: self.height,
-                    can_be_stored: self.can_be_stored,
                     red_spawn_points: self.red_spawn_points.clone(),
                     blue_spawn_points: self.blue_spawn_points.clone(),
                     player_physics: self.player_physics.clone(),
                     spawn_distance: self.spawn_distance,
+                    kick_off_radius: self.bg.kick_off_radius,
                     kick_off_reset: self.kick_off_reset.clone(),
-                },
-                StadiumCamera {
-                    camera_width: self.camera_width,
-                    camera_height: self.camera_height,
-                    max_view_width: self.max_view_width,
-                    camera_follow: self.camera_follow.clone(),
+                    hash: self.content_hash(),
                 },
             ))
             .with_children(|parent| {
@@ -287,8 +459,11 @@ impl Stadium {
                     goal.spawn(parent);
                 }
 
+                // the ball always comes first, like in HaxBall
+                self.ball_physics.spawn(parent);
+
                 for (index, disc) in self.discs.iter().enumerate() {
-                    disc.spawn(parent, index);
+                    disc.spawn(parent, index + 1);
                 }
 
                 for plane in &self.planes {
@@ -301,6 +476,221 @@ impl Stadium {
 }
 
 // stadium properties component
-// stadium camera properties component
 // stadium bundle
 // the rest will be a child of the stadium bundle
+
+// parse a HaxBall stadium file, failing instead of panicking on invalid content
+pub fn parse_stadium(bytes: &[u8]) -> Result<(Stadium, StadiumRaw), bevy::asset::Error> {
+    load_stadium_raw(parse_stadium_raw(bytes)?)
+}
+
+// same as parse_stadium, with the format chosen by the file extension
+pub fn parse_stadium_file(
+    path: &Path,
+    bytes: &[u8],
+) -> Result<(Stadium, StadiumRaw), bevy::asset::Error> {
+    load_stadium_raw(parse_stadium_raw_file(path, bytes)?)
+}
+
+fn load_stadium_raw(stadium_raw: StadiumRaw) -> Result<(Stadium, StadiumRaw), bevy::asset::Error> {
+    // unknown properties are kept for re-export but ignored in game
+    for field in stadium_raw.unknown_fields() {
+        println!(
+            "Warning: unknown property \"{}\" in stadium \"{}\"",
+            field, stadium_raw.name
+        );
+    }
+    let stadium = resolve_stadium(&stadium_raw)?;
+    Ok((stadium, stadium_raw))
+}
+
+// the JSON5 file as written, without resolving traits and defaults
+pub fn parse_stadium_raw(bytes: &[u8]) -> Result<StadiumRaw, bevy::asset::Error> {
+    let data_str = std::str::from_utf8(bytes)?;
+    let stadium_value = parse_to_serde_value(data_str, &ParseOptions::default())?
+        .ok_or_else(|| bevy::asset::Error::msg("Empty stadium file"))?;
+    Ok(serde_json::from_value(stadium_value)?)
+}
+
+// .hbsb files are binary, anything else is JSON5
+pub fn parse_stadium_raw_file(path: &Path, bytes: &[u8]) -> Result<StadiumRaw, bevy::asset::Error> {
+    if binary::is_binary_path(path) {
+        binary::decode_raw(bytes)
+    } else {
+        parse_stadium_raw(bytes)
+    }
+}
+
+// the content of a stadium file in the format of its extension
+pub fn stadium_file_bytes(path: &Path, stadium_raw: &StadiumRaw) -> Vec<u8> {
+    if binary::is_binary_path(path) {
+        binary::encode_raw(stadium_raw)
+    } else {
+        stadium_raw.to_hbs().into_bytes()
+    }
+}
+
+pub fn resolve_stadium(stadium_raw: &StadiumRaw) -> Result<Stadium, bevy::asset::Error> {
+    stadium_raw.to_stadium().map_err(bevy::asset::Error::msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+    use crate::test_utils::stadium_files;
+
+    fn load(path: &Path) -> (Stadium, StadiumRaw) {
+        let bytes = std::fs::read(path).unwrap();
+        parse_stadium(&bytes).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+    }
+
+    // numbers are compared as the floats the parser reads, unset properties are left out
+    fn normalized(mut value: Value) -> Value {
+        fn floats(value: &mut Value) {
+            match value {
+                Value::Number(n) => *value = json!(n.as_f64().unwrap()),
+                Value::Object(map) => map.values_mut().for_each(floats),
+                Value::Array(arr) => arr.iter_mut().for_each(floats),
+                _ => {}
+            }
+        }
+        strip_nulls(&mut value);
+        floats(&mut value);
+        value
+    }
+
+    #[test]
+    fn raw_round_trip() {
+        for path in stadium_files() {
+            let text = std::fs::read_to_string(&path).unwrap();
+            let original = parse_to_serde_value(&text, &ParseOptions::default())
+                .unwrap()
+                .unwrap();
+            let (_, raw) = load(&path);
+            // the output is plain JSON, not JSON5
+            let written: Value = serde_json::from_str(&raw.to_hbs()).unwrap();
+            assert_eq!(
+                normalized(written),
+                normalized(original),
+                "{}",
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn flattened_export_keeps_collision_names() {
+        let path =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets/stadiums/base/classic.json5");
+        let (stadium, _) = load(&path);
+        let hbs = stadium.to_hbs();
+        assert!(hbs.contains("\"redKO\""));
+        assert!(hbs.contains("\"blueKO\""));
+        assert!(!hbs.contains("\"redko\""));
+    }
+
+    #[test]
+    fn invalid_values_are_errors() {
+        let error = |objects: &str| {
+            let hbs = format!(
+                r#"{{ "name": "Invalid", "bg": {{}}, "vertexes": [{{ "x": 0, "y": 0 }}], {} }}"#,
+                objects
+            );
+            parse_stadium(hbs.as_bytes()).unwrap_err().to_string()
+        };
+        assert!(error(r#""discs": [{ "pos": [0, 0], "color": "green" }]"#).starts_with("discs[0]"));
+        assert!(
+            error(r#""goals": [{ "p0": [0, 0], "p1": [0, 1], "team": "green" }]"#)
+                .starts_with("goals[0]")
+        );
+        assert!(
+            error(r#""planes": [{ "normal": [0, 1], "dist": 0, "cMask": ["red", "x"] }]"#)
+                .starts_with("planes[0]")
+        );
+        assert!(error(r#""segments": [{ "v0": 0, "v1": 1 }]"#).starts_with("segments[0]"));
+        assert!(error(r#""redSpawnPoints": [[0]]"#).starts_with("redSpawnPoints[0]"));
+        assert!(error(r#""ballPhysics": "disc0""#).starts_with("ballPhysics"));
+    }
+
+    #[test]
+    fn unknown_fields_are_kept() {
+        let hbs = r#"{
+            "name": "Unknown",
+            "bg": { "type": "grass", "stripes": true },
+            "traits": { "post": { "radius": 8, "goalPost": true } },
+            "vertexes": [{ "x": 0, "y": 0, "note": "center" }],
+            "segments": [{ "v0": 0, "v1": 0, "goalPost": 1 }],
+            "ballArea": [1, 2]
+        }"#;
+        let (_, raw) = parse_stadium(hbs.as_bytes()).unwrap();
+        let mut fields = raw.unknown_fields();
+        fields.sort();
+        assert_eq!(
+            fields,
+            vec![
+                "ballArea",
+                "bg.stripes",
+                "segments[0].goalPost",
+                "traits.post.goalPost",
+                "vertexes[0].note",
+            ]
+        );
+
+        let value: Value = serde_json::from_str(&raw.to_hbs()).unwrap();
+        assert_eq!(value["ballArea"], json!([1, 2]));
+        assert_eq!(value["bg"]["stripes"], json!(true));
+        assert_eq!(value["traits"]["post"]["goalPost"], json!(true));
+        assert_eq!(value["vertexes"][0]["note"], json!("center"));
+        assert_eq!(value["segments"][0]["goalPost"], json!(1));
+    }
+
+    #[test]
+    fn flattened_round_trip() {
+        for path in stadium_files() {
+            let (stadium, _) = load(&path);
+            let hbs = stadium.to_hbs();
+            let (reparsed, reparsed_raw) = parse_stadium(hbs.as_bytes()).unwrap();
+            assert!(reparsed_raw.traits.is_none(), "{}", path.display());
+            assert_eq!(
+                reparsed.to_raw().to_json(),
+                stadium.to_raw().to_json(),
+                "{}",
+                path.display()
+            );
+            assert_eq!(reparsed.discs.len(), stadium.discs.len());
+            assert_eq!(reparsed.segments.len(), stadium.segments.len());
+            assert_eq!(
+                reparsed.ball_physics.0.position,
+                stadium.ball_physics.0.position
+            );
+            assert_eq!(reparsed.content_hash(), stadium.content_hash());
+        }
+    }
+
+    #[test]
+    fn content_hash_ignores_formatting() {
+        let hash = |hbs: &str| parse_stadium(hbs.as_bytes()).unwrap().0.content_hash();
+        let written = hash(
+            r#"{
+            name: "A", bg: {},
+            traits: { wall: { bCoef: 0.5, curve: 0 } },
+            vertexes: [{ x: -10, y: 0 }, { x: 10, y: 0 }],
+            segments: [{ v0: 0, v1: 1, trait: "wall" }],
+        }"#,
+        );
+        let reordered = hash(
+            r#"{"segments":[{"bCoef":0.5,"v1":1,"v0":0}],"bg":{},
+            "vertexes":[{"y":0,"x":-10.0},{"x":10,"y":-0.0}],"name":"B"}"#,
+        );
+        let moved = hash(
+            r#"{"name":"A","bg":{},"vertexes":[{"x":-10,"y":0},{"x":11,"y":0}],
+            "segments":[{"v0":0,"v1":1,"bCoef":0.5}]}"#,
+        );
+        assert_eq!(written, reordered);
+        assert_ne!(written, moved);
+        // the hash is shared between players, it must not change between versions
+        assert_eq!(format_hash(written), "ad460316708d2dd0");
+    }
+}
@@ -0,0 +1,309 @@
+use bevy::math::DVec2;
+use serde_json::Value;
+
+use super::{disc::DiscRaw, stadium::StadiumRaw};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MirrorAxis {
+    // left and right are swapped, and so are the teams
+    Horizontal,
+    // top and bottom are swapped
+    Vertical,
+}
+
+fn map_pair(pair: &mut [f64; 2], f: &impl Fn(DVec2) -> DVec2) {
+    *pair = f(DVec2::from(*pair)).to_array();
+}
+
+fn map_disc(disc: &mut DiscRaw, point: &impl Fn(DVec2) -> DVec2, vector: &impl Fn(DVec2) -> DVec2) {
+    map_pair(&mut disc.pos, point);
+    if let Some(speed) = &mut disc.speed {
+        map_pair(speed, vector);
+    }
+    if let Some(gravity) = &mut disc.gravity {
+        map_pair(gravity, vector);
+    }
+}
+
+// the vectors of a disc stored as JSON, like the ball physics
+fn map_json_vectors(value: &mut Value, vector: &impl Fn(DVec2) -> DVec2) {
+    let Value::Object(object) = value else {
+        return;
+    };
+    for key in ["speed", "gravity"] {
+        let Some(pair) = object
+            .get(key)
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+        else {
+            continue;
+        };
+        object.insert(
+            key.to_string(),
+            Value::from(vector(pair).to_array().to_vec()),
+        );
+    }
+}
+
+fn swap_team_flag(flag: &str) -> String {
+    match flag.to_lowercase().as_str() {
+        "red" => "blue".to_string(),
+        "blue" => "red".to_string(),
+        "redko" => "blueKO".to_string(),
+        "blueko" => "redKO".to_string(),
+        _ => flag.to_string(),
+    }
+}
+
+fn swap_team_flags(flags: &mut Option<Vec<String>>) {
+    for flag in flags.iter_mut().flatten() {
+        *flag = swap_team_flag(flag);
+    }
+}
+
+fn swap_json_team_flags(value: &mut Value) {
+    let Value::Object(object) = value else {
+        return;
+    };
+    for key in ["cGroup", "cMask"] {
+        if let Some(Value::Array(flags)) = object.get_mut(key) {
+            for flag in flags.iter_mut() {
+                if let Value::String(s) = flag {
+                    *s = swap_team_flag(s);
+                }
+            }
+        }
+    }
+}
+
+fn scale_option(value: &mut Option<f64>, factor: f64) {
+    if let Some(value) = value {
+        *value *= factor;
+    }
+}
+
+// the geometry transformations used to build stadium variants
+// radiuses of discs and physics are kept, only positions and sizes change
+impl StadiumRaw {
+    // `point` maps positions, `vector` maps speeds and gravities, `normal` maps plane normals
+    fn map_geometry(
+        &mut self,
+        point: impl Fn(DVec2) -> DVec2,
+        vector: impl Fn(DVec2) -> DVec2,
+        normal: impl Fn(DVec2) -> DVec2,
+    ) {
+        for vertex in self.vertexes.iter_mut().flatten() {
+            let p = point(DVec2::new(vertex.x, vertex.y));
+            (vertex.x, vertex.y) = (p.x, p.y);
+        }
+        for disc in self.discs.iter_mut().flatten() {
+            map_disc(disc, &point, &vector);
+        }
+        for goal in self.goals.iter_mut().flatten() {
+            map_pair(&mut goal.p0, &point);
+            map_pair(&mut goal.p1, &point);
+        }
+        for plane in self.planes.iter_mut().flatten() {
+            // move a point of the plane and compute the distance along the new normal
+            let unit = DVec2::from(plane.normal).normalize_or_zero();
+            let new_normal = normal(DVec2::from(plane.normal));
+            plane.dist = new_normal.normalize_or_zero().dot(point(unit * plane.dist));
+            plane.normal = new_normal.to_array();
+        }
+        for spawn_points in [&mut self.red_spawn_points, &mut self.blue_spawn_points] {
+            for spawn_point in spawn_points.iter_mut().flatten() {
+                if let [x, y] = spawn_point.as_mut_slice() {
+                    let p = point(DVec2::new(*x, *y));
+                    (*x, *y) = (p.x, p.y);
+                }
+            }
+        }
+        if let Some(player_physics) = &mut self.player_physics {
+            if let Some(gravity) = &mut player_physics.gravity {
+                map_pair(gravity, &vector);
+            }
+        }
+        if let Some(ball) = &mut self.ball_physics {
+            map_json_vectors(ball, &vector);
+        }
+    }
+
+    pub fn translate(&mut self, offset: DVec2) {
+        // the background and the camera are always centered
+        self.map_geometry(|p| p + offset, |v| v, |n| n);
+    }
+
+    // the factors must be positive, use `mirror` to flip the stadium
+    // curves keep their angle, so arcs stay circular with a non-uniform scale
+    pub fn scale(&mut self, factor: DVec2) {
+        self.map_geometry(|p| p * factor, |v| v, |n| (n / factor).normalize_or_zero());
+
+        scale_option(&mut self.width, factor.x);
+        scale_option(&mut self.height, factor.y);
+        scale_option(&mut self.camera_width, factor.x);
+        scale_option(&mut self.camera_height, factor.y);
+        scale_option(&mut self.max_view_width, factor.x);
+        scale_option(&mut self.spawn_distance, factor.x);
+
+        // circles can only follow the average scale
+        let average = (factor.x * factor.y).sqrt();
+        scale_option(&mut self.bg.width, factor.x);
+        scale_option(&mut self.bg.height, factor.y);
+        scale_option(&mut self.bg.goal_line, factor.x);
+        scale_option(&mut self.bg.kick_off_radius, average);
+        scale_option(&mut self.bg.corner_radius, average);
+    }
+
+    pub fn mirror(&mut self, axis: MirrorAxis) {
+        let flip = match axis {
+            MirrorAxis::Horizontal => DVec2::new(-1.0, 1.0),
+            MirrorAxis::Vertical => DVec2::new(1.0, -1.0),
+        };
+        self.map_geometry(|p| p * flip, |v| v * flip, |n| n * flip);
+
+        // a reflection turns the segments inside out, going through them the other way
+        // around keeps the sign of curve, curveF and bias (even from traits) valid
+        for segment in self.segments.iter_mut().flatten() {
+            std::mem::swap(&mut segment.v0, &mut segment.v1);
+        }
+
+        if axis == MirrorAxis::Horizontal {
+            self.swap_teams();
+        }
+    }
+
+    // rotate by 90 degrees around the center, clockwise on screen
+    pub fn rotate_90(&mut self, clockwise: bool) {
+        // the y axis points down
+        let rotate = move |p: DVec2| {
+            if clockwise {
+                DVec2::new(-p.y, p.x)
+            } else {
+                DVec2::new(p.y, -p.x)
+            }
+        };
+        self.map_geometry(rotate, rotate, rotate);
+
+        std::mem::swap(&mut self.width, &mut self.height);
+        std::mem::swap(&mut self.camera_width, &mut self.camera_height);
+        std::mem::swap(&mut self.bg.width, &mut self.bg.height);
+    }
+
+    // goals, spawn points and team collision flags change side
+    fn swap_teams(&mut self) {
+        for goal in self.goals.iter_mut().flatten() {
+            goal.team = match goal.team.as_str() {
+                "red" => "blue".to_string(),
+                "blue" => "red".to_string(),
+                other => other.to_string(),
+            };
+        }
+        std::mem::swap(&mut self.red_spawn_points, &mut self.blue_spawn_points);
+
+        for vertex in self.vertexes.iter_mut().flatten() {
+            swap_team_flags(&mut vertex.c_group);
+            swap_team_flags(&mut vertex.c_mask);
+        }
+        for segment in self.segments.iter_mut().flatten() {
+            swap_team_flags(&mut segment.c_group);
+            swap_team_flags(&mut segment.c_mask);
+        }
+        for disc in self.discs.iter_mut().flatten() {
+            swap_team_flags(&mut disc.c_group);
+            swap_team_flags(&mut disc.c_mask);
+        }
+        for plane in self.planes.iter_mut().flatten() {
+            swap_team_flags(&mut plane.c_group);
+            swap_team_flags(&mut plane.c_mask);
+        }
+        if let Some(player_physics) = &mut self.player_physics {
+            swap_team_flags(&mut player_physics.c_group);
+        }
+        if let Some(Value::Object(traits)) = &mut self.traits {
+            traits.values_mut().for_each(swap_json_team_flags);
+        }
+        if let Some(ball) = &mut self.ball_physics {
+            swap_json_team_flags(ball);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::stadium::parse_stadium_raw;
+
+    const STADIUM: &str = r#"{
+        "name": "Transform",
+        "width": 300,
+        "height": 100,
+        "spawnDistance": 150,
+        "bg": { "type": "grass", "width": 250, "height": 80, "kickOffRadius": 50 },
+        "traits": { "line": { "curve": 45, "cMask": ["redKO"] } },
+        "vertexes": [{ "x": -250, "y": -30 }, { "x": -250, "y": 30, "cMask": ["red"] }],
+        "segments": [{ "v0": 0, "v1": 1, "curve": 90, "trait": "line" }],
+        "goals": [{ "p0": [-250, -30], "p1": [-250, 30], "team": "red" }],
+        "discs": [{ "pos": [-250, -30], "speed": [1, 2] }],
+        "planes": [{ "normal": [0, 1], "dist": -80 }],
+        "redSpawnPoints": [[-100, 0]],
+        "blueSpawnPoints": [[100, 10]]
+    }"#;
+
+    fn stadium() -> StadiumRaw {
+        parse_stadium_raw(STADIUM.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn mirror_twice_is_identity() {
+        for axis in [MirrorAxis::Horizontal, MirrorAxis::Vertical] {
+            let mut raw = stadium();
+            raw.mirror(axis);
+            raw.mirror(axis);
+            assert_eq!(raw.to_json(), stadium().to_json());
+        }
+    }
+
+    #[test]
+    fn mirror_swaps_teams() {
+        let mut raw = stadium();
+        raw.mirror(MirrorAxis::Horizontal);
+        let json = raw.to_json();
+        assert_eq!(json["goals"][0]["team"], "blue");
+        assert_eq!(json["goals"][0]["p0"][0], 250.0);
+        assert_eq!(json["blueSpawnPoints"][0][0], 100.0);
+        assert_eq!(json["redSpawnPoints"][0], serde_json::json!([-100.0, 10.0]));
+        assert_eq!(json["vertexes"][1]["cMask"][0], "blue");
+        assert_eq!(json["traits"]["line"]["cMask"][0], "blueKO");
+        assert_eq!(json["segments"][0]["v0"], 1);
+        assert_eq!(json["discs"][0]["speed"][0], -1.0);
+    }
+
+    #[test]
+    fn rotate_four_times_is_identity() {
+        let mut raw = stadium();
+        for _ in 0..4 {
+            raw.rotate_90(true);
+        }
+        assert_eq!(raw.to_json(), stadium().to_json());
+
+        raw.rotate_90(true);
+        raw.rotate_90(false);
+        assert_eq!(raw.to_json(), stadium().to_json());
+    }
+
+    #[test]
+    fn scale_and_translate_move_planes() {
+        let mut raw = stadium();
+        raw.scale(DVec2::new(2.0, 0.5));
+        assert_eq!(raw.width, Some(600.0));
+        assert_eq!(raw.height, Some(50.0));
+        assert_eq!(raw.bg.kick_off_radius, Some(50.0));
+        let plane = &raw.planes.as_ref().unwrap()[0];
+        assert_eq!((plane.normal, plane.dist), ([0.0, 1.0], -40.0));
+
+        raw.translate(DVec2::new(10.0, 5.0));
+        let plane = &raw.planes.as_ref().unwrap()[0];
+        assert_eq!(plane.dist, -35.0);
+        let vertex = &raw.vertexes.as_ref().unwrap()[0];
+        assert_eq!((vertex.x, vertex.y), (-490.0, -10.0));
+    }
+}
@@ -1,6 +1,7 @@
 use bevy::{math::DVec2, prelude::*};
 use bevy_prototype_lyon::prelude::*;
 use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 bitflags! {
@@ -37,38 +38,123 @@ impl std::str::FromStr for CollisionFlag {
     }
 }
 
-pub fn parse_color(color_val: &Value, transparent_supported: bool) -> Color {
+pub fn parse_color(color_val: &Value, transparent_supported: bool) -> Result<Color, String> {
     // the value is either "transparent", a hex string, or an array of 3 ints
     // from the documentation, there are cases where transparent is not supported
+    let invalid = || format!("invalid color {}", color_val);
     match color_val {
-        Value::String(s) => {
-            if s == "transparent" && !transparent_supported {
-                panic!("Transparent color not supported")
-            } else if s == "transparent" {
-                Color::rgba_u8(0, 0, 0, 0)
-            } else {
-                let hex = u32::from_str_radix(s, 16).unwrap();
-                let r: u8 = ((hex >> 16) & 0xFF) as u8;
-                let g: u8 = ((hex >> 8) & 0xFF) as u8;
-                let b: u8 = (hex & 0xFF) as u8;
-                Color::rgb_u8(r, g, b)
+        Value::String(s) if s == "transparent" => match transparent_supported {
+            true => Ok(Color::rgba_u8(0, 0, 0, 0)),
+            false => Err("transparent color not supported here".to_string()),
+        },
+        Value::String(s) => parse_hex_color(s).ok_or_else(invalid),
+        Value::Array(arr) if arr.len() == 3 => {
+            let channel = |c: &Value| c.as_u64().and_then(|c| u8::try_from(c).ok());
+            match (channel(&arr[0]), channel(&arr[1]), channel(&arr[2])) {
+                (Some(r), Some(g), Some(b)) => Ok(Color::rgb_u8(r, g, b)),
+                _ => Err(invalid()),
             }
         }
-        Value::Array(arr) => Color::rgb_u8(
-            arr[0].as_u64().unwrap() as u8,
-            arr[1].as_u64().unwrap() as u8,
-            arr[2].as_u64().unwrap() as u8,
-        ),
-        _ => panic!("Invalid color value"),
+        _ => Err(invalid()),
+    }
+}
+
+// inverse of parse_color, fully transparent colors become "transparent"
+pub fn color_to_value(color: Color) -> Value {
+    let [r, g, b, a] = color.as_rgba_u8();
+    if a == 0 {
+        return Value::String("transparent".to_string());
+    }
+    Value::String(format!("{:02X}{:02X}{:02X}", r, g, b))
+}
+
+// "RRGGBB", shared by the parser, the linter and the /colors command
+pub fn parse_hex_color(s: &str) -> Option<Color> {
+    if s.len() != 6 || !s.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hex = u32::from_str_radix(s, 16).ok()?;
+    let r: u8 = ((hex >> 16) & 0xFF) as u8;
+    let g: u8 = ((hex >> 8) & 0xFF) as u8;
+    let b: u8 = (hex & 0xFF) as u8;
+    Some(Color::rgb_u8(r, g, b))
+}
+
+// remove the null properties left by unset options
+pub fn strip_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(strip_nulls);
+        }
+        Value::Array(arr) => arr.iter_mut().for_each(strip_nulls),
+        _ => {}
     }
 }
 
-pub fn parse_collision(vec: &Vec<String>) -> CollisionFlag {
+// 64 bits FNV-1a, stable across platforms and versions unlike the std hashers
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+pub fn parse_collision(vec: &[String]) -> Result<CollisionFlag, String> {
     let mut flag = CollisionFlag::empty();
     for s in vec {
-        flag |= s.parse().unwrap();
+        flag |= s
+            .parse()
+            .map_err(|_| format!("unknown collision flag \"{}\"", s))?;
+    }
+    Ok(flag)
+}
+
+// the names used by HaxBall, which are case sensitive
+const COLLISION_NAMES: [(CollisionFlag, &str); 12] = [
+    (CollisionFlag::BALL, "ball"),
+    (CollisionFlag::RED, "red"),
+    (CollisionFlag::BLUE, "blue"),
+    (CollisionFlag::REDKO, "redKO"),
+    (CollisionFlag::BLUEKO, "blueKO"),
+    (CollisionFlag::WALL, "wall"),
+    (CollisionFlag::KICK, "kick"),
+    (CollisionFlag::SCORE, "score"),
+    (CollisionFlag::C0, "c0"),
+    (CollisionFlag::C1, "c1"),
+    (CollisionFlag::C2, "c2"),
+    (CollisionFlag::C3, "c3"),
+];
+
+// inverse of parse_collision
+pub fn collision_to_strings(flag: CollisionFlag) -> Vec<String> {
+    let mut names = vec![];
+    let mut rest = flag;
+    if rest.contains(CollisionFlag::ALL) {
+        names.push("all".to_string());
+        rest.remove(CollisionFlag::ALL);
+    }
+    for (flag, name) in COLLISION_NAMES {
+        if rest.contains(flag) {
+            names.push(name.to_string());
+        }
     }
-    flag
+    names
+}
+
+pub fn distance_to_polyline(pos: DVec2, points: &[DVec2]) -> f64 {
+    points
+        .windows(2)
+        .map(|w| {
+            let (a, b) = (w[0], w[1]);
+            let ab = b - a;
+            let t = if ab.length_squared() > 0.0 {
+                ((pos - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            pos.distance(a + ab * t)
+        })
+        .fold(f64::INFINITY, f64::min)
 }
 
 pub fn arc(center: Vec2, radius: f32, start_angle: f32, end_angle: f32, tolerance: f32) -> Path {
@@ -88,7 +174,8 @@ pub fn arc(center: Vec2, radius: f32, start_angle: f32, end_angle: f32, toleranc
     path_builder.build()
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 #[allow(dead_code)]
 pub enum Team {
     Spectator = 1,
@@ -96,6 +183,32 @@ pub enum Team {
     Blue = 3,
 }
 
+impl Team {
+    pub fn opponent(&self) -> Team {
+        match self {
+            Team::Red => Team::Blue,
+            Team::Blue => Team::Red,
+            Team::Spectator => Team::Spectator,
+        }
+    }
+
+    pub fn collision_flag(&self) -> CollisionFlag {
+        match self {
+            Team::Red => CollisionFlag::RED,
+            Team::Blue => CollisionFlag::BLUE,
+            Team::Spectator => CollisionFlag::empty(),
+        }
+    }
+
+    pub fn kickoff_flag(&self) -> CollisionFlag {
+        match self {
+            Team::Red => CollisionFlag::REDKO,
+            Team::Blue => CollisionFlag::BLUEKO,
+            Team::Spectator => CollisionFlag::empty(),
+        }
+    }
+}
+
 #[derive(Component, Debug, Clone, Copy)]
 pub struct Position(pub DVec2);
 
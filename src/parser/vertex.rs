@@ -1,22 +1,27 @@
 use bevy::math::DVec2;
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::collections::HashMap;
 
 use super::{
     hx_trait::{Trait, Traitable},
-    utils::{parse_collision, BouncingCoef, Collision, CollisionFlag, Position},
+    utils::{
+        collision_to_strings, parse_collision, BouncingCoef, Collision, CollisionFlag, Position,
+    },
 };
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct VertexRaw {
-    x: f64,
-    y: f64,
-    b_coef: Option<f64>,
-    c_group: Option<Vec<String>>,
-    c_mask: Option<Vec<String>>,
+    pub x: f64,
+    pub y: f64,
+    pub b_coef: Option<f64>,
+    pub c_group: Option<Vec<String>>,
+    pub c_mask: Option<Vec<String>>,
     #[serde(rename = "trait")]
-    hx_trait: Option<String>,
+    pub hx_trait: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl Default for VertexRaw {
@@ -28,30 +33,12 @@ impl Default for VertexRaw {
             c_group: Some(vec!["wall".to_string()]),
             c_mask: Some(vec!["all".to_string()]),
             hx_trait: None,
+            extra: Map::new(),
         }
     }
 }
 
-impl Traitable for VertexRaw {
-    fn apply_trait(&self, traits: &HashMap<String, Trait>) -> VertexRaw {
-        let tr_def = Trait::default();
-        let tr_d = match &self.hx_trait {
-            Some(tr_name) => traits.get(tr_name).unwrap_or(&tr_def),
-            None => &tr_def,
-        };
-        let b_coef = self.b_coef.or(tr_d.b_coef);
-        let c_group = self.c_group.as_ref().or(tr_d.c_group.as_ref()).cloned();
-        let c_mask = self.c_mask.as_ref().or(tr_d.c_mask.as_ref()).cloned();
-        let hx_trait = self.hx_trait.clone();
-        VertexRaw {
-            b_coef,
-            c_group,
-            c_mask,
-            hx_trait,
-            ..*self
-        }
-    }
-}
+impl Traitable for VertexRaw {}
 
 impl VertexRaw {
     pub fn apply_default(&self) -> VertexRaw {
@@ -63,21 +50,22 @@ impl VertexRaw {
             c_group: self.c_group.as_ref().or(default.c_group.as_ref()).cloned(),
             c_mask: self.c_mask.as_ref().or(default.c_mask.as_ref()).cloned(),
             hx_trait: self.hx_trait.clone(),
+            extra: self.extra.clone(),
         }
     }
 
-    pub fn to_vertex(&self, traits: &HashMap<String, Trait>) -> Vertex {
-        let vertex_raw = self.apply_trait(traits).apply_default();
+    pub fn to_vertex(&self, traits: &HashMap<String, Trait>) -> Result<Vertex, String> {
+        let vertex_raw = self.apply_trait(traits)?.apply_default();
         let position = DVec2::new(vertex_raw.x, vertex_raw.y);
         let b_coef = vertex_raw.b_coef.unwrap();
-        let c_group = parse_collision(&vertex_raw.c_group.unwrap());
-        let c_mask = parse_collision(&vertex_raw.c_mask.unwrap());
-        Vertex {
+        let c_group = parse_collision(&vertex_raw.c_group.unwrap())?;
+        let c_mask = parse_collision(&vertex_raw.c_mask.unwrap())?;
+        Ok(Vertex {
             position,
             b_coef,
             c_group,
             c_mask,
-        }
+        })
     }
 }
 
@@ -93,6 +81,18 @@ pub struct Vertex {
 pub struct VertexComp;
 
 impl Vertex {
+    pub fn to_raw(&self) -> VertexRaw {
+        VertexRaw {
+            x: self.position.x,
+            y: self.position.y,
+            b_coef: Some(self.b_coef),
+            c_group: Some(collision_to_strings(self.c_group)),
+            c_mask: Some(collision_to_strings(self.c_mask)),
+            hx_trait: None,
+            extra: Map::new(),
+        }
+    }
+
     pub fn spawn(&self, stadium_parent: &mut ChildBuilder) {
         stadium_parent.spawn((
             VertexComp,
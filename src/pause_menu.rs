@@ -0,0 +1,127 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    editor::EditorSession,
+    game::RestartMatch,
+    input::{Action, ActionInput},
+    menu::{stadium_list, AssetsLoading, DataAssets},
+    settings::{SettingsOpener, SettingsWindow},
+    AppState, PauseState,
+};
+
+pub struct PauseMenuPlugin;
+
+impl Plugin for PauseMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PauseMenuData>()
+            .add_systems(
+                Update,
+                (
+                    toggle_pause,
+                    pause_menu.run_if(in_state(PauseState::Paused)),
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), resume);
+    }
+}
+
+#[derive(Resource, Default)]
+struct PauseMenuData {
+    stadium_index: usize,
+}
+
+fn toggle_pause(
+    actions: ActionInput,
+    settings_window: Res<SettingsWindow>,
+    pause_state: Res<State<PauseState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    // the pause binding may be what the settings are waiting for
+    if settings_window.is_open() || !actions.just_pressed(Action::Pause) {
+        return;
+    }
+
+    match pause_state.get() {
+        PauseState::Running => next_pause_state.set(PauseState::Paused),
+        PauseState::Paused => next_pause_state.set(PauseState::Running),
+    }
+}
+
+// where the pause menu can go
+#[derive(SystemParam)]
+struct PauseMenuExits<'w> {
+    next_pause_state: ResMut<'w, NextState<PauseState>>,
+    next_app_state: ResMut<'w, NextState<AppState>>,
+    editor_session: Option<Res<'w, EditorSession>>,
+}
+
+fn pause_menu(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut menu_data: ResMut<PauseMenuData>,
+    asset_server: Res<AssetServer>,
+    mut exits: PauseMenuExits,
+    mut restarts: EventWriter<RestartMatch>,
+    mut settings: SettingsOpener,
+) {
+    let stadiums = stadium_list();
+
+    egui::Window::new("Paused")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(contexts.ctx_mut(), |ui| {
+            if ui.button("Resume").clicked() {
+                exits.next_pause_state.set(PauseState::Running);
+            }
+
+            if ui.button("Restart match").clicked() {
+                restarts.send(RestartMatch);
+                exits.next_pause_state.set(PauseState::Running);
+            }
+
+            ui.separator();
+
+            if ui.button("Settings").clicked() {
+                settings.open();
+            }
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source("pause_stadium")
+                    .selected_text(stadiums[menu_data.stadium_index].0)
+                    .show_ui(ui, |ui| {
+                        for (index, (name, _)) in stadiums.iter().enumerate() {
+                            ui.selectable_value(&mut menu_data.stadium_index, index, *name);
+                        }
+                    });
+                if ui.button("Change stadium").clicked() {
+                    // the menu switches back to the game as soon as the stadium is loaded
+                    let stadium = asset_server.load(stadiums[menu_data.stadium_index].1);
+                    commands.insert_resource(AssetsLoading(vec![stadium.clone_untyped()]));
+                    commands.insert_resource(DataAssets { stadium });
+                    // the editor session was for the previous stadium
+                    commands.remove_resource::<EditorSession>();
+                    exits.next_app_state.set(AppState::Menu);
+                }
+            });
+
+            ui.separator();
+
+            if exits.editor_session.is_some() && ui.button("Back to editor").clicked() {
+                exits.next_app_state.set(AppState::Editor);
+            }
+
+            if ui.button("Back to menu").clicked() {
+                exits.next_app_state.set(AppState::Menu);
+            }
+        });
+}
+
+fn resume(mut next_pause_state: ResMut<NextState<PauseState>>) {
+    next_pause_state.set(PauseState::Running);
+}
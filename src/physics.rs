@@ -1,44 +1,101 @@
+use bevy::ecs::query::Has;
 use bevy::math::DVec2;
 use bevy::prelude::*;
 
+use crate::parser::ball_physics::BallComp;
 use crate::parser::disc::{Damping, DiscComp, Gravity, InverseMass, Radius, Velocity};
 use crate::parser::plane::PlaneComp;
 use crate::parser::segment::{Bias, Curve, CurvedUtils, SegmentComp};
-use crate::parser::utils::{BouncingCoef, Collision, CollisionFlag, Position};
+use crate::parser::stadium::StadiumComp;
+use crate::parser::utils::{BouncingCoef, Collision, CollisionFlag, Position, Team};
 use crate::parser::vertex::VertexComp;
-use crate::AppState;
+use crate::player::{KickState, Player, PlayerInput};
+use crate::{AppState, PauseState};
+
+// a disc can be kicked that far from the edge of the player
+pub const KICK_MARGIN: f64 = 4.0;
 
 pub struct PhysicsPlugin;
 
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PhysicsSet;
+
+// a player touched or kicked the ball, sent in the order of the tick
+#[derive(Event, Debug, Clone, Copy)]
+pub struct BallTouchEvent {
+    pub player: usize,
+    pub team: Team,
+    pub kick: bool,
+    // the ball just after the touch
+    pub position: DVec2,
+    pub velocity: DVec2,
+}
+
+// the ball bounced on a plane, segment or vertex
+#[derive(Event, Debug, Clone, Copy)]
+pub struct BallBounceEvent {
+    pub position: DVec2,
+    // change of the normal velocity, in units per tick
+    pub impulse: f64,
+}
+
+// the discs pushed out of walls, planes and vertexes
+type WallDisc = (
+    &'static mut Position,
+    &'static mut Velocity,
+    &'static Radius,
+    &'static InverseMass,
+    &'static BouncingCoef,
+    &'static Collision,
+    Has<BallComp>,
+);
+
+// every disc, players included, for the collisions between discs
+type AnyDisc = (
+    &'static DiscComp,
+    &'static mut Position,
+    &'static mut Velocity,
+    &'static Radius,
+    &'static InverseMass,
+    &'static BouncingCoef,
+    &'static Collision,
+    Option<&'static Player>,
+    Has<BallComp>,
+);
+
+type KickedDisc = (
+    &'static Position,
+    &'static Radius,
+    &'static mut Velocity,
+    &'static Collision,
+    Has<BallComp>,
+);
+
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            FixedUpdate,
-            (
-                update_discs,
-                disc_disc_collision,
-                disc_plane_collision,
-                disc_straight_segment_collision,
-                disc_curved_segment_collision,
-                disc_vertex_collision,
-            )
-                .chain()
-                .run_if(in_state(AppState::InGame)),
-        );
+        app.add_event::<BallTouchEvent>()
+            .add_event::<BallBounceEvent>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    update_players,
+                    player_kick,
+                    update_discs,
+                    disc_disc_collision,
+                    disc_plane_collision,
+                    disc_straight_segment_collision,
+                    disc_curved_segment_collision,
+                    disc_vertex_collision,
+                )
+                    .chain()
+                    .in_set(PhysicsSet)
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(in_state(PauseState::Running)),
+            );
     }
 }
 
-fn disc_disc_collision(
-    mut discs: Query<(
-        &DiscComp,
-        &mut Position,
-        &mut Velocity,
-        &Radius,
-        &InverseMass,
-        &BouncingCoef,
-        &Collision,
-    )>,
-) {
+fn disc_disc_collision(mut discs: Query<AnyDisc>, mut touches: EventWriter<BallTouchEvent>) {
     let mut combinations = discs.iter_combinations_mut();
     while let Some([disc_a, disc_b]) = combinations.fetch_next() {
         let (
@@ -49,6 +106,8 @@ fn disc_disc_collision(
             inv_mass_a,
             b_coef_a,
             collision_a,
+            player_a,
+            is_ball_a,
         ) = disc_a;
         let (
             disc_comp_b,
@@ -58,6 +117,8 @@ fn disc_disc_collision(
             inv_mass_b,
             b_coef_b,
             collision_b,
+            player_b,
+            is_ball_b,
         ) = disc_b;
 
         if disc_comp_a.index == disc_comp_b.index {
@@ -90,21 +151,30 @@ fn disc_disc_collision(
             velocity_a.0 += normal * impulse * mass_factor;
             velocity_b.0 -= normal * impulse * (1.0 - mass_factor);
         }
+
+        let touch = match (player_a, player_b) {
+            (Some(player), None) if is_ball_b => Some((player, &position_b, &velocity_b)),
+            (None, Some(player)) if is_ball_a => Some((player, &position_a, &velocity_a)),
+            _ => None,
+        };
+        if let Some((player, ball_position, ball_velocity)) = touch {
+            touches.send(BallTouchEvent {
+                player: player.id,
+                team: player.team,
+                kick: false,
+                position: ball_position.0,
+                velocity: ball_velocity.0,
+            });
+        }
     }
 }
 
 fn disc_plane_collision(
-    mut discs: Query<(
-        &mut Position,
-        &mut Velocity,
-        &Radius,
-        &InverseMass,
-        &BouncingCoef,
-        &Collision,
-    )>,
+    mut discs: Query<WallDisc>,
     planes: Query<(&PlaneComp, &BouncingCoef, &Collision)>,
+    mut bounces: EventWriter<BallBounceEvent>,
 ) {
-    for (mut position, mut velocity, radius, inv_mass, b_coef_disc, collision_disc) in
+    for (mut position, mut velocity, radius, inv_mass, b_coef_disc, collision_disc, is_ball) in
         discs.iter_mut()
     {
         for (plane_comp, b_coef_plane, collision_plane) in planes.iter() {
@@ -130,6 +200,12 @@ fn disc_plane_collision(
             if normal_velocity < 0.0 {
                 let impulse = -(1.0 + b_coef_disc.0 * b_coef_plane.0) * normal_velocity;
                 velocity.0 += plane_comp.normal * impulse;
+                if is_ball {
+                    bounces.send(BallBounceEvent {
+                        position: position.0,
+                        impulse,
+                    });
+                }
             }
         }
     }
@@ -151,23 +227,14 @@ fn segment_apply_bias(bias: &Bias, dist: f64, norm_segment: DVec2) -> (f64, DVec
 }
 
 fn disc_straight_segment_collision(
-    mut discs: Query<
-        (
-            &mut Position,
-            &mut Velocity,
-            &Radius,
-            &InverseMass,
-            &BouncingCoef,
-            &Collision,
-        ),
-        Without<VertexComp>,
-    >,
+    mut discs: Query<WallDisc, Without<VertexComp>>,
     segments: Query<(&SegmentComp, &BouncingCoef, &Bias, &Collision), Without<Curve>>,
     vertexes: Query<(&VertexComp, &Position, &Collision)>,
+    mut bounces: EventWriter<BallBounceEvent>,
 ) {
     let vertexes_vec = vertexes.iter().collect::<Vec<_>>();
 
-    for (mut position, mut velocity, radius, inv_mass, b_coef_disc, collision_disc) in
+    for (mut position, mut velocity, radius, inv_mass, b_coef_disc, collision_disc, is_ball) in
         discs.iter_mut()
     {
         for (segment_comp, b_coef_segment, bias, collision_segment) in segments.iter() {
@@ -207,29 +274,26 @@ fn disc_straight_segment_collision(
             if normal_velocity < 0.0 {
                 let impulse = -(1.0 + b_coef_disc.0 * b_coef_segment.0) * normal_velocity;
                 velocity.0 += norm_segment_f * impulse;
+                if is_ball {
+                    bounces.send(BallBounceEvent {
+                        position: position.0,
+                        impulse,
+                    });
+                }
             }
         }
     }
 }
 
 fn disc_curved_segment_collision(
-    mut discs: Query<
-        (
-            &mut Position,
-            &mut Velocity,
-            &Radius,
-            &InverseMass,
-            &BouncingCoef,
-            &Collision,
-        ),
-        Without<VertexComp>,
-    >,
+    mut discs: Query<WallDisc, Without<VertexComp>>,
     segments: Query<(&SegmentComp, &BouncingCoef, &Bias, &Collision, &Curve)>,
     vertexes: Query<(&VertexComp, &Position, &Collision)>,
+    mut bounces: EventWriter<BallBounceEvent>,
 ) {
     let vertexes_vec = vertexes.iter().collect::<Vec<_>>();
 
-    for (mut position, mut velocity, radius, inv_mass, b_coef_disc, collision_disc) in
+    for (mut position, mut velocity, radius, inv_mass, b_coef_disc, collision_disc, is_ball) in
         discs.iter_mut()
     {
         for (segment_comp, b_coef_segment, bias, collision_segment, curve) in segments.iter() {
@@ -275,26 +339,23 @@ fn disc_curved_segment_collision(
             if normal_velocity < 0.0 {
                 let impulse = -(1.0 + b_coef_disc.0 * b_coef_segment.0) * normal_velocity;
                 velocity.0 += norm_segment_f * impulse;
+                if is_ball {
+                    bounces.send(BallBounceEvent {
+                        position: position.0,
+                        impulse,
+                    });
+                }
             }
         }
     }
 }
 
 fn disc_vertex_collision(
-    mut discs: Query<
-        (
-            &mut Position,
-            &mut Velocity,
-            &Radius,
-            &InverseMass,
-            &BouncingCoef,
-            &Collision,
-        ),
-        Without<VertexComp>,
-    >,
+    mut discs: Query<WallDisc, Without<VertexComp>>,
     vertexes: Query<(&Position, &Collision), With<VertexComp>>,
+    mut bounces: EventWriter<BallBounceEvent>,
 ) {
-    for (mut position, mut velocity, radius, inv_mass, b_coef_disc, collision_disc) in
+    for (mut position, mut velocity, radius, inv_mass, b_coef_disc, collision_disc, is_ball) in
         discs.iter_mut()
     {
         for (vertex_pos, collision_vertex) in vertexes.iter() {
@@ -319,6 +380,12 @@ fn disc_vertex_collision(
             if normal_velocity < 0.0 {
                 let impulse = -(1.0 + b_coef_disc.0) * normal_velocity;
                 velocity.0 += norm_vertex * impulse;
+                if is_ball {
+                    bounces.send(BallBounceEvent {
+                        position: position.0,
+                        impulse,
+                    });
+                }
             }
         }
     }
@@ -330,3 +397,78 @@ fn update_discs(mut discs: Query<(&mut Position, &mut Velocity, &Gravity, &Dampi
         velocity.0 = (velocity.0 + gravity.0) * damping.0;
     }
 }
+
+fn update_players(
+    mut players: Query<(&PlayerInput, &mut KickState, &mut Velocity, &mut Damping), With<Player>>,
+    stadium: Query<&StadiumComp>,
+) {
+    let Ok(stadium) = stadium.get_single() else {
+        return;
+    };
+    let physics = &stadium.player_physics;
+
+    for (input, mut kick_state, mut velocity, mut damping) in players.iter_mut() {
+        if !input.kick {
+            kick_state.kicked = false;
+        }
+        kick_state.kicking = input.kick && !kick_state.kicked;
+
+        let (acceleration, damping_value) = if kick_state.kicking {
+            (physics.kicking_acceleration, physics.kicking_damping)
+        } else {
+            (physics.acceleration, physics.damping)
+        };
+
+        if input.direction != DVec2::ZERO {
+            velocity.0 += input.direction.normalize() * acceleration;
+        }
+        damping.0 = damping_value;
+    }
+}
+
+fn player_kick(
+    mut players: Query<(&Player, &Position, &Radius, &mut Velocity, &mut KickState)>,
+    mut discs: Query<KickedDisc, Without<Player>>,
+    stadium: Query<&StadiumComp>,
+    mut touches: EventWriter<BallTouchEvent>,
+) {
+    let Ok(stadium) = stadium.get_single() else {
+        return;
+    };
+    let physics = &stadium.player_physics;
+
+    for (player, player_pos, player_radius, mut player_velocity, mut kick_state) in
+        players.iter_mut()
+    {
+        if !kick_state.kicking {
+            continue;
+        }
+
+        for (disc_pos, disc_radius, mut disc_velocity, collision, is_ball) in discs.iter_mut() {
+            if collision.group & CollisionFlag::KICK == CollisionFlag::empty() {
+                continue;
+            }
+
+            let dist = player_pos.0.distance(disc_pos.0);
+            if dist > player_radius.0 + disc_radius.0 + KICK_MARGIN {
+                continue;
+            }
+
+            // a disc exactly on the player has no direction to be kicked in
+            let normal = (disc_pos.0 - player_pos.0).normalize_or_zero();
+            disc_velocity.0 += normal * physics.kick_strength;
+            player_velocity.0 -= normal * physics.kickback;
+            kick_state.kicking = false;
+            kick_state.kicked = true;
+            if is_ball {
+                touches.send(BallTouchEvent {
+                    player: player.id,
+                    team: player.team,
+                    kick: true,
+                    position: disc_pos.0,
+                    velocity: disc_velocity.0,
+                });
+            }
+        }
+    }
+}
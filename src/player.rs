@@ -0,0 +1,262 @@
+use bevy::{math::DVec2, prelude::*, sprite::Anchor};
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{
+    bot::{BotController, BotRegistry},
+    game::{spawn_position, MatchRng, MatchSettings},
+    input::{Action, ActionInput, InputMap},
+    lobby::{Controller, Lobby},
+    parser::{
+        disc::{Damping, DiscComp, Gravity, InverseMass, Radius, Velocity},
+        player_physics::PlayerPhysics,
+        stadium::StadiumComp,
+        utils::{BouncingCoef, Collision, CollisionFlag, Position, Team},
+    },
+    AppState,
+};
+
+// the team kits are applied by the renderer
+const DEFAULT_COLOR: Color = Color::GRAY;
+const NAME_COLOR: Color = Color::WHITE;
+const KICK_RING_COLOR: Color = Color::WHITE;
+
+// players collide with everything but the kickoff barriers, which are handled by the match
+pub const PLAYER_MASK: CollisionFlag = CollisionFlag::BALL
+    .union(CollisionFlag::RED)
+    .union(CollisionFlag::BLUE)
+    .union(CollisionFlag::WALL);
+
+// index offset so that players never share a DiscComp index with stadium discs
+const PLAYER_INDEX_OFFSET: usize = 1000;
+
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Lobby>()
+            .init_resource::<InputMap>()
+            .add_systems(OnExit(AppState::InGame), despawn_players)
+            .add_systems(
+                Update,
+                (
+                    spawn_players,
+                    // there is no keyboard nor gamepad when running headless
+                    local_input.run_if(resource_exists::<Input<KeyCode>>()),
+                )
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct Player {
+    pub id: usize,
+    pub name: String,
+    pub avatar: String,
+    pub team: Team,
+}
+
+// the player controlled by the keyboard and gamepads of this client
+#[derive(Component, Debug, Clone, Copy)]
+pub struct LocalPlayer;
+
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct PlayerInput {
+    pub direction: DVec2,
+    pub kick: bool,
+}
+
+// a kick is only allowed once per key press, like in HaxBall
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct KickState {
+    pub kicking: bool,
+    pub kicked: bool,
+}
+
+#[derive(Component, Debug, Clone, Copy)]
+pub struct KickRing;
+
+#[derive(Component, Debug, Clone, Copy)]
+pub struct AvatarText;
+
+// one of the colored stripes drawn over the player disc
+#[derive(Component, Debug, Clone, Copy)]
+pub struct KitStripe;
+
+impl Player {
+    pub fn spawn(
+        &self,
+        commands: &mut Commands,
+        physics: &PlayerPhysics,
+        position: DVec2,
+    ) -> Entity {
+        let radius = physics.radius as f32;
+        let z = 0.5 + self.id as f32 * 0.001;
+        // the camera is flipped vertically, text must be flipped back
+        let text_scale = Vec3::new(1.0, -1.0, 1.0);
+
+        commands
+            .spawn((
+                self.clone(),
+                DiscComp {
+                    index: PLAYER_INDEX_OFFSET + self.id,
+                },
+                (
+                    ShapeBundle {
+                        path: GeometryBuilder::build_as(&shapes::Circle {
+                            radius,
+                            center: Vec2::ZERO,
+                        }),
+                        transform: Transform::from_xyz(position.x as f32, position.y as f32, z),
+                        ..default()
+                    },
+                    Fill::color(DEFAULT_COLOR),
+                    Stroke::new(Color::BLACK, 2.0),
+                ),
+                Position(position),
+                Velocity(DVec2::ZERO),
+                Gravity(physics.gravity),
+                Radius(physics.radius),
+                InverseMass(physics.inv_mass),
+                Damping(physics.damping),
+                BouncingCoef(physics.b_coef),
+                Collision {
+                    group: physics.c_group | self.team.collision_flag(),
+                    mask: PLAYER_MASK,
+                },
+                PlayerInput::default(),
+                KickState::default(),
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    AvatarText,
+                    Text2dBundle {
+                        text: Text::from_section(
+                            self.avatar.clone(),
+                            TextStyle {
+                                font_size: radius * 1.2,
+                                color: Color::WHITE,
+                                ..default()
+                            },
+                        )
+                        .with_alignment(TextAlignment::Center),
+                        transform: Transform::from_xyz(0.0, 0.0, 0.01).with_scale(text_scale),
+                        ..default()
+                    },
+                ));
+
+                parent.spawn(Text2dBundle {
+                    text: Text::from_section(
+                        self.name.clone(),
+                        TextStyle {
+                            font_size: 14.0,
+                            color: NAME_COLOR,
+                            ..default()
+                        },
+                    )
+                    .with_alignment(TextAlignment::Center),
+                    text_anchor: Anchor::TopCenter,
+                    transform: Transform::from_xyz(0.0, radius + 8.0, 0.01).with_scale(text_scale),
+                    ..default()
+                });
+
+                parent.spawn((
+                    KickRing,
+                    ShapeBundle {
+                        path: GeometryBuilder::build_as(&shapes::Circle {
+                            radius,
+                            center: Vec2::ZERO,
+                        }),
+                        transform: Transform::from_xyz(0.0, 0.0, 0.005),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                    Stroke::new(KICK_RING_COLOR, 2.0),
+                ));
+            })
+            .id()
+    }
+}
+
+// the players of the lobby are spawned once the stadium exists, and again after a restart
+fn spawn_players(
+    mut commands: Commands,
+    lobby: Res<Lobby>,
+    settings: Res<MatchSettings>,
+    mut rng: ResMut<MatchRng>,
+    bots: Option<Res<BotRegistry>>,
+    stadiums: Query<&StadiumComp>,
+    players: Query<(), With<Player>>,
+) {
+    // the stadium is also spawned again by hot reloading, players are kept then
+    if !players.is_empty() {
+        return;
+    }
+    let Ok(stadium) = stadiums.get_single() else {
+        return;
+    };
+
+    let mut teams = lobby.slots.iter().map(|slot| slot.team).collect::<Vec<_>>();
+    if settings.shuffle_teams {
+        // spectators keep watching
+        let mut playing = teams
+            .iter()
+            .copied()
+            .filter(|team| *team != Team::Spectator)
+            .collect::<Vec<_>>();
+        rng.0.shuffle(&mut playing);
+        let mut playing = playing.into_iter();
+        for team in teams.iter_mut().filter(|team| **team != Team::Spectator) {
+            *team = playing.next().unwrap();
+        }
+    }
+
+    let (mut red_slot, mut blue_slot) = (0, 0);
+    for (id, (slot, team)) in lobby.slots.iter().zip(teams).enumerate() {
+        let (spawn_points, team_slot) = match team {
+            Team::Red => (&stadium.red_spawn_points, &mut red_slot),
+            Team::Blue => (&stadium.blue_spawn_points, &mut blue_slot),
+            Team::Spectator => continue,
+        };
+        let position = spawn_position(spawn_points, stadium.spawn_distance, team, *team_slot);
+        *team_slot += 1;
+
+        let player = Player {
+            id,
+            name: slot.name.clone(),
+            avatar: slot.avatar.clone(),
+            team,
+        };
+        let entity = player.spawn(&mut commands, &stadium.player_physics, position);
+        match &slot.controller {
+            Controller::Local => {
+                commands.entity(entity).insert(LocalPlayer);
+            }
+            Controller::Bot(name) => match bots.as_ref().and_then(|bots| bots.create(name)) {
+                Some(mut bot) => {
+                    // each bot draws from its own generator, in lobby order
+                    bot.seed(rng.0.next_u64());
+                    commands.entity(entity).insert(BotController(bot));
+                }
+                None => println!("Unknown bot \"{}\", the player stays idle", name),
+            },
+            Controller::External => {}
+        }
+    }
+}
+
+fn despawn_players(mut commands: Commands, players: Query<Entity, With<Player>>) {
+    for entity in players.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn local_input(actions: ActionInput, mut query: Query<&mut PlayerInput, With<LocalPlayer>>) {
+    let direction = actions.direction();
+    let kick = actions.pressed(Action::Kick);
+
+    for mut input in query.iter_mut() {
+        input.direction = direction;
+        input.kick = kick;
+    }
+}
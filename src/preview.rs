@@ -0,0 +1,503 @@
+use std::fmt::Write;
+
+use bevy::{math::DVec2, prelude::Color};
+use tiny_skia::{FillRule, Paint, PathBuilder, Pixmap, Stroke, Transform};
+
+use crate::{
+    parser::{
+        background::{
+            BackgroundType, GRASS_BORDER_COLOR, GRASS_FILL_COLOR, HOCKEY_BORDER_COLOR,
+            HOCKEY_FILL_COLOR,
+        },
+        disc::Disc,
+        segment::Segment,
+        stadium::Stadium,
+        utils::Team,
+    },
+    recording::Heatmap,
+};
+
+const MARGIN: f64 = 20.0;
+const ARC_STEPS: usize = 48;
+const LINE_WIDTH: f64 = 3.0;
+const DISC_OUTLINE_WIDTH: f64 = 1.5;
+const SPAWN_POINT_RADIUS: f64 = 6.0;
+// heatmap cells of the most visited places are that opaque
+const HEATMAP_MAX_ALPHA: f32 = 0.8;
+const TRAJECTORY_WIDTH: f64 = 2.0;
+const RED_COLOR: Color = Color::rgb(0.9, 0.43, 0.34);
+const BLUE_COLOR: Color = Color::rgb(0.34, 0.54, 0.9);
+
+#[derive(Debug, Clone, Copy)]
+pub struct Style {
+    pub fill: Option<Color>,
+    pub stroke: Option<Color>,
+    pub stroke_width: f64,
+}
+
+impl Style {
+    pub fn fill(color: Color) -> Style {
+        Style {
+            fill: Some(color),
+            stroke: None,
+            stroke_width: 0.0,
+        }
+    }
+
+    pub fn stroke(color: Color, width: f64) -> Style {
+        Style {
+            fill: None,
+            stroke: Some(color),
+            stroke_width: width,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Shape {
+    // centered on `center`, like the stadium background
+    Rect {
+        center: DVec2,
+        half_size: DVec2,
+        corner_radius: f64,
+        style: Style,
+    },
+    Circle {
+        center: DVec2,
+        radius: f64,
+        style: Style,
+    },
+    Polyline {
+        points: Vec<DVec2>,
+        style: Style,
+    },
+}
+
+// a stadium drawn as simple shapes, in stadium coordinates (y pointing down)
+#[derive(Debug, Clone)]
+pub struct Scene {
+    pub min: DVec2,
+    pub max: DVec2,
+    pub background: Color,
+    pub shapes: Vec<Shape>,
+}
+
+fn disc_shape(disc: &Disc) -> Shape {
+    let fill = (disc.color.a() > 0.0).then_some(disc.color);
+    Shape::Circle {
+        center: disc.position,
+        radius: disc.radius,
+        style: Style {
+            fill,
+            stroke: Some(Color::BLACK),
+            stroke_width: DISC_OUTLINE_WIDTH,
+        },
+    }
+}
+
+pub fn team_color(team: Team) -> Color {
+    match team {
+        Team::Blue => BLUE_COLOR,
+        _ => RED_COLOR,
+    }
+}
+
+impl Scene {
+    pub fn from_stadium(stadium: &Stadium) -> Scene {
+        let bg = &stadium.bg;
+        let mut shapes = vec![];
+
+        let (background, border) = match bg.bg_type {
+            BackgroundType::Grass => (GRASS_FILL_COLOR, Some(GRASS_BORDER_COLOR)),
+            BackgroundType::Hockey => (HOCKEY_FILL_COLOR, Some(HOCKEY_BORDER_COLOR)),
+            BackgroundType::None => (bg.color, None),
+        };
+        // same lines as the in-game background
+        if let Some(border) = border {
+            let style = Style::stroke(border, LINE_WIDTH);
+            shapes.push(Shape::Rect {
+                center: DVec2::ZERO,
+                half_size: DVec2::new(bg.width, bg.height),
+                corner_radius: bg.corner_radius,
+                style,
+            });
+            shapes.push(Shape::Circle {
+                center: DVec2::ZERO,
+                radius: bg.kick_off_radius,
+                style,
+            });
+            if bg.height != 0.0 {
+                shapes.push(Shape::Polyline {
+                    points: vec![DVec2::new(0.0, -bg.height), DVec2::new(0.0, bg.height)],
+                    style,
+                });
+            }
+        }
+
+        let vertexes = stadium
+            .vertexes
+            .iter()
+            .map(|v| v.position)
+            .collect::<Vec<_>>();
+        for segment in &stadium.segments {
+            let (vis, color) = match segment {
+                Segment::Straight(s) => (s.vis, s.color),
+                Segment::Curved(s) => (s.vis, s.color),
+            };
+            if vis {
+                shapes.push(Shape::Polyline {
+                    points: segment.points(&vertexes, ARC_STEPS),
+                    style: Style::stroke(color, LINE_WIDTH),
+                });
+            }
+        }
+
+        for goal in &stadium.goals {
+            shapes.push(Shape::Polyline {
+                points: vec![goal.p0, goal.p1],
+                style: Style::stroke(team_color(goal.team).with_a(0.6), LINE_WIDTH),
+            });
+        }
+
+        for (team, points) in [
+            (Team::Red, &stadium.red_spawn_points),
+            (Team::Blue, &stadium.blue_spawn_points),
+        ] {
+            for point in points {
+                shapes.push(Shape::Circle {
+                    center: *point,
+                    radius: SPAWN_POINT_RADIUS,
+                    style: Style {
+                        fill: Some(team_color(team)),
+                        stroke: Some(Color::WHITE),
+                        stroke_width: DISC_OUTLINE_WIDTH,
+                    },
+                });
+            }
+        }
+
+        // the ball comes first and is drawn below the other discs, like in game
+        let discs = std::iter::once(&stadium.ball_physics.0).chain(&stadium.discs);
+        shapes.extend(discs.filter(|disc| disc.vis).map(disc_shape));
+
+        let (min, max) = Scene::bounds(stadium, &vertexes);
+        Scene {
+            min: min - DVec2::splat(MARGIN),
+            max: max + DVec2::splat(MARGIN),
+            background,
+            shapes,
+        }
+    }
+
+    // draw how often the positions were in each cell of a grid, over what is already drawn
+    pub fn add_heatmap(&mut self, positions: &[DVec2], cell_size: f64, color: Color) {
+        let mut heatmap = Heatmap::new(self.min, self.max, cell_size);
+        for position in positions {
+            heatmap.add(*position);
+        }
+        let max_count = heatmap.max_count();
+        let half_size = DVec2::splat(cell_size / 2.0);
+        for (center, count) in heatmap.cells() {
+            // the square root keeps the places visited a few times visible
+            let alpha = HEATMAP_MAX_ALPHA * (count as f32 / max_count as f32).sqrt();
+            self.shapes.push(Shape::Rect {
+                center,
+                half_size,
+                corner_radius: 0.0,
+                style: Style::fill(color.with_a(alpha)),
+            });
+        }
+    }
+
+    // draw a path with a dot where it starts
+    pub fn add_trajectory(&mut self, path: &[DVec2], color: Color) {
+        let Some(start) = path.first() else {
+            return;
+        };
+        self.shapes.push(Shape::Polyline {
+            points: path.to_vec(),
+            style: Style::stroke(color, TRAJECTORY_WIDTH),
+        });
+        self.shapes.push(Shape::Circle {
+            center: *start,
+            radius: TRAJECTORY_WIDTH * 2.0,
+            style: Style::fill(color),
+        });
+    }
+
+    // the stadium size when it has one, the extent of its objects otherwise
+    fn bounds(stadium: &Stadium, vertexes: &[DVec2]) -> (DVec2, DVec2) {
+        if stadium.width > 0.0 && stadium.height > 0.0 {
+            let half_size = DVec2::new(stadium.width, stadium.height);
+            return (-half_size, half_size);
+        }
+
+        let discs = std::iter::once(&stadium.ball_physics.0).chain(&stadium.discs);
+        let mut min = DVec2::splat(f64::INFINITY);
+        let mut max = DVec2::splat(f64::NEG_INFINITY);
+        for point in vertexes.iter().copied() {
+            min = min.min(point);
+            max = max.max(point);
+        }
+        for disc in discs {
+            min = min.min(disc.position - disc.radius);
+            max = max.max(disc.position + disc.radius);
+        }
+        let half_size = DVec2::new(stadium.bg.width, stadium.bg.height);
+        (min.min(-half_size), max.max(half_size))
+    }
+
+    pub fn size(&self) -> DVec2 {
+        self.max - self.min
+    }
+
+    pub fn to_svg(&self) -> String {
+        let size = self.size();
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+            size.x.ceil(),
+            size.y.ceil(),
+            self.min.x,
+            self.min.y,
+            size.x,
+            size.y
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" {}/>"#,
+            self.min.x,
+            self.min.y,
+            size.x,
+            size.y,
+            svg_style(&Style::fill(self.background))
+        )
+        .unwrap();
+
+        for shape in &self.shapes {
+            match shape {
+                Shape::Rect {
+                    center,
+                    half_size,
+                    corner_radius,
+                    style,
+                } => writeln!(
+                    svg,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" rx="{}" {}/>"#,
+                    center.x - half_size.x,
+                    center.y - half_size.y,
+                    2.0 * half_size.x,
+                    2.0 * half_size.y,
+                    corner_radius,
+                    svg_style(style)
+                ),
+                Shape::Circle {
+                    center,
+                    radius,
+                    style,
+                } => writeln!(
+                    svg,
+                    r#"<circle cx="{}" cy="{}" r="{}" {}/>"#,
+                    center.x,
+                    center.y,
+                    radius,
+                    svg_style(style)
+                ),
+                Shape::Polyline { points, style } => {
+                    let points = points
+                        .iter()
+                        .map(|p| format!("{},{}", p.x, p.y))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    writeln!(
+                        svg,
+                        r#"<polyline points="{}" stroke-linecap="round" {}/>"#,
+                        points,
+                        svg_style(style)
+                    )
+                }
+            }
+            .unwrap();
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    // one stadium unit is `scale` pixels
+    pub fn to_pixmap(&self, scale: f64) -> Option<Pixmap> {
+        let size = self.size() * scale;
+        let mut pixmap = Pixmap::new(size.x.ceil() as u32, size.y.ceil() as u32)?;
+        pixmap.fill(skia_color(self.background));
+        let transform = Transform::from_translate(-self.min.x as f32, -self.min.y as f32)
+            .post_scale(scale as f32, scale as f32);
+
+        for shape in &self.shapes {
+            let (path, style) = match shape {
+                Shape::Rect {
+                    center,
+                    half_size,
+                    corner_radius,
+                    style,
+                } => (rounded_rect(*center, *half_size, *corner_radius), style),
+                Shape::Circle {
+                    center,
+                    radius,
+                    style,
+                } => (
+                    PathBuilder::from_circle(center.x as f32, center.y as f32, *radius as f32),
+                    style,
+                ),
+                Shape::Polyline { points, style } => {
+                    let mut builder = PathBuilder::new();
+                    for (i, point) in points.iter().enumerate() {
+                        if i == 0 {
+                            builder.move_to(point.x as f32, point.y as f32);
+                        } else {
+                            builder.line_to(point.x as f32, point.y as f32);
+                        }
+                    }
+                    (builder.finish(), style)
+                }
+            };
+            // degenerate shapes (empty polylines, zero radius...) have no path
+            let Some(path) = path else {
+                continue;
+            };
+
+            if let Some(fill) = style.fill {
+                let paint = skia_paint(fill);
+                pixmap.fill_path(&path, &paint, FillRule::Winding, transform, None);
+            }
+            if let Some(stroke) = style.stroke {
+                let paint = skia_paint(stroke);
+                let stroke = Stroke {
+                    width: style.stroke_width as f32,
+                    line_cap: tiny_skia::LineCap::Round,
+                    ..Default::default()
+                };
+                pixmap.stroke_path(&path, &paint, &stroke, transform, None);
+            }
+        }
+
+        Some(pixmap)
+    }
+
+    pub fn to_png(&self, scale: f64) -> Result<Vec<u8>, String> {
+        let pixmap = self
+            .to_pixmap(scale)
+            .ok_or_else(|| "the image would be empty".to_string())?;
+        pixmap.encode_png().map_err(|e| e.to_string())
+    }
+}
+
+fn svg_color(color: Color) -> (String, f32) {
+    let [r, g, b, _] = color.as_rgba_u8();
+    (format!("#{:02X}{:02X}{:02X}", r, g, b), color.a())
+}
+
+fn svg_style(style: &Style) -> String {
+    let mut attributes = String::new();
+    match style.fill {
+        Some(fill) => {
+            let (color, opacity) = svg_color(fill);
+            write!(attributes, r#"fill="{}" fill-opacity="{}""#, color, opacity).unwrap();
+        }
+        None => attributes.push_str(r#"fill="none""#),
+    }
+    if let Some(stroke) = style.stroke {
+        let (color, opacity) = svg_color(stroke);
+        write!(
+            attributes,
+            r#" stroke="{}" stroke-opacity="{}" stroke-width="{}""#,
+            color, opacity, style.stroke_width
+        )
+        .unwrap();
+    }
+    attributes
+}
+
+fn skia_color(color: Color) -> tiny_skia::Color {
+    let [r, g, b, a] = color.as_rgba_u8();
+    tiny_skia::Color::from_rgba8(r, g, b, a)
+}
+
+fn skia_paint(color: Color) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color(skia_color(color));
+    paint.anti_alias = true;
+    paint
+}
+
+fn rounded_rect(center: DVec2, half_size: DVec2, corner_radius: f64) -> Option<tiny_skia::Path> {
+    let (x0, y0) = (
+        (center.x - half_size.x) as f32,
+        (center.y - half_size.y) as f32,
+    );
+    let (x1, y1) = (
+        (center.x + half_size.x) as f32,
+        (center.y + half_size.y) as f32,
+    );
+    let r = corner_radius.clamp(0.0, half_size.x.min(half_size.y)) as f32;
+    // control point distance for a quarter circle made of a cubic curve
+    let k = r * 0.552_284_8;
+
+    let mut builder = PathBuilder::new();
+    builder.move_to(x0 + r, y0);
+    builder.line_to(x1 - r, y0);
+    builder.cubic_to(x1 - r + k, y0, x1, y0 + r - k, x1, y0 + r);
+    builder.line_to(x1, y1 - r);
+    builder.cubic_to(x1, y1 - r + k, x1 - r + k, y1, x1 - r, y1);
+    builder.line_to(x0 + r, y1);
+    builder.cubic_to(x0 + r - k, y1, x0, y1 - r + k, x0, y1 - r);
+    builder.line_to(x0, y0 + r);
+    builder.cubic_to(x0, y0 + r - k, x0 + r - k, y0, x0 + r, y0);
+    builder.close();
+    builder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::classic;
+
+    #[test]
+    fn bounds_cover_the_stadium() {
+        let stadium = classic();
+        let scene = Scene::from_stadium(&stadium);
+        let half_size = DVec2::new(stadium.width, stadium.height);
+        assert_eq!(scene.min, -half_size - MARGIN);
+        assert_eq!(scene.max, half_size + MARGIN);
+
+        // without a size, the objects give the bounds
+        let mut unsized_stadium = stadium.clone();
+        unsized_stadium.width = 0.0;
+        unsized_stadium.height = 0.0;
+        let scene = Scene::from_stadium(&unsized_stadium);
+        for vertex in &stadium.vertexes {
+            assert!(vertex.position.cmpge(scene.min).all());
+            assert!(vertex.position.cmple(scene.max).all());
+        }
+    }
+
+    #[test]
+    fn classic_svg() {
+        let scene = Scene::from_stadium(&classic());
+        let svg = scene.to_svg();
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        let (red, _) = svg_color(team_color(Team::Red));
+        let (blue, _) = svg_color(team_color(Team::Blue));
+        // the spawn points and the goals of both teams
+        assert!(svg.contains(&red));
+        assert!(svg.contains(&blue));
+        let polylines = scene
+            .shapes
+            .iter()
+            .filter(|shape| matches!(shape, Shape::Polyline { .. }))
+            .count();
+        assert_eq!(svg.matches("<polyline").count(), polylines);
+    }
+}
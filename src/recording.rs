@@ -0,0 +1,286 @@
+use bevy::{math::DVec2, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{MatchPhase, MatchSeed, MatchSet, MatchState, RestartMatch},
+    parser::{
+        ball_physics::BallComp,
+        stadium::StadiumComp,
+        utils::{Position, Team},
+    },
+    physics::PhysicsSet,
+    player::Player,
+    AppState, PauseState,
+};
+
+// 10 samples per second
+pub const SAMPLE_INTERVAL: u32 = 6;
+
+pub struct RecordingPlugin;
+
+impl Plugin for RecordingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::InGame), setup_recording)
+            .add_systems(OnExit(AppState::InGame), cleanup_recording)
+            .add_systems(Update, restart_recording.run_if(in_state(AppState::InGame)))
+            .add_systems(
+                FixedUpdate,
+                record_positions
+                    .after(PhysicsSet)
+                    // before the positions are reset after a goal
+                    .before(MatchSet)
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(in_state(PauseState::Running)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedPlayer {
+    pub id: usize,
+    pub name: String,
+    pub team: Team,
+    pub positions: Vec<DVec2>,
+}
+
+// the positions of the ball and the players, sampled every `interval` ticks
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct MatchRecording {
+    // the match seed and the content hash of the stadium, enough to tell which match it is
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default)]
+    pub hash: u64,
+    pub interval: u32,
+    // one path per kickoff, so that the ball does not jump back to the center
+    pub ball: Vec<Vec<DVec2>>,
+    // sorted by id
+    pub players: Vec<RecordedPlayer>,
+    #[serde(skip)]
+    ticks: u32,
+    #[serde(skip)]
+    kickoff: bool,
+}
+
+impl Default for MatchRecording {
+    fn default() -> Self {
+        MatchRecording {
+            seed: 0,
+            hash: 0,
+            interval: SAMPLE_INTERVAL,
+            ball: vec![],
+            players: vec![],
+            ticks: 0,
+            kickoff: false,
+        }
+    }
+}
+
+impl MatchRecording {
+    pub fn player(&self, id: usize) -> Option<&RecordedPlayer> {
+        self.players.iter().find(|player| player.id == id)
+    }
+
+    // called every tick, only keeps one sample every `interval` ticks
+    pub fn add_tick(
+        &mut self,
+        phase: MatchPhase,
+        ball: Option<DVec2>,
+        players: impl IntoIterator<Item = (Player, DVec2)>,
+    ) {
+        let kickoff = matches!(phase, MatchPhase::Kickoff(_));
+        let new_kickoff = kickoff && !self.kickoff;
+        self.kickoff = kickoff;
+        let sampled = self.ticks.is_multiple_of(self.interval.max(1));
+        self.ticks += 1;
+        if phase == MatchPhase::Ended || !(sampled || new_kickoff) {
+            return;
+        }
+
+        if let Some(ball) = ball {
+            match self.ball.last_mut() {
+                Some(path) if !new_kickoff => path.push(ball),
+                _ => self.ball.push(vec![ball]),
+            }
+        }
+        for (player, position) in players {
+            match self.players.iter_mut().find(|p| p.id == player.id) {
+                Some(recorded) => recorded.positions.push(position),
+                None => {
+                    self.players.push(RecordedPlayer {
+                        id: player.id,
+                        name: player.name,
+                        team: player.team,
+                        positions: vec![position],
+                    });
+                    self.players.sort_by_key(|player| player.id);
+                }
+            }
+        }
+    }
+}
+
+// how often each cell of a grid over the stadium was visited
+#[derive(Debug, Clone)]
+pub struct Heatmap {
+    // corner of the first cell
+    pub min: DVec2,
+    pub cell_size: f64,
+    pub columns: usize,
+    pub rows: usize,
+    // row by row
+    pub counts: Vec<u32>,
+}
+
+impl Heatmap {
+    // a grid covering `min` to `max`, positions outside are ignored
+    pub fn new(min: DVec2, max: DVec2, cell_size: f64) -> Heatmap {
+        let cells = ((max - min) / cell_size).ceil().max(DVec2::ONE);
+        let (columns, rows) = (cells.x as usize, cells.y as usize);
+        Heatmap {
+            min,
+            cell_size,
+            columns,
+            rows,
+            counts: vec![0; columns * rows],
+        }
+    }
+
+    pub fn add(&mut self, position: DVec2) {
+        let cell = ((position - self.min) / self.cell_size).floor();
+        if cell.x < 0.0 || cell.y < 0.0 {
+            return;
+        }
+        let (column, row) = (cell.x as usize, cell.y as usize);
+        if column < self.columns && row < self.rows {
+            self.counts[row * self.columns + column] += 1;
+        }
+    }
+
+    pub fn max_count(&self) -> u32 {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+
+    // the visited cells with their center and count
+    pub fn cells(&self) -> impl Iterator<Item = (DVec2, u32)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(index, &count)| {
+                let cell = DVec2::new((index % self.columns) as f64, (index / self.columns) as f64);
+                (self.min + (cell + 0.5) * self.cell_size, count)
+            })
+    }
+}
+
+fn setup_recording(mut commands: Commands) {
+    commands.insert_resource(MatchRecording::default());
+}
+
+fn restart_recording(mut commands: Commands, mut restarts: EventReader<RestartMatch>) {
+    if !restarts.is_empty() {
+        restarts.clear();
+        commands.insert_resource(MatchRecording::default());
+    }
+}
+
+fn cleanup_recording(mut commands: Commands) {
+    commands.remove_resource::<MatchRecording>();
+}
+
+fn record_positions(
+    mut recording: ResMut<MatchRecording>,
+    match_state: Res<MatchState>,
+    seed: Res<MatchSeed>,
+    stadiums: Query<&StadiumComp>,
+    balls: Query<&Position, With<BallComp>>,
+    players: Query<(&Player, &Position)>,
+) {
+    // a restart draws a new seed, the stadium is spawned after the recording starts
+    // and hot reloading can replace it
+    recording.seed = seed.0;
+    if let Ok(stadium) = stadiums.get_single() {
+        recording.hash = stadium.hash;
+    }
+    let ball = balls.get_single().ok().map(|position| position.0);
+    let players = players
+        .iter()
+        .map(|(player, position)| (player.clone(), position.0));
+    recording.add_tick(match_state.phase, ball, players);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(id: usize, team: Team) -> Player {
+        Player {
+            id,
+            name: format!("P{}", id),
+            avatar: String::new(),
+            team,
+        }
+    }
+
+    #[test]
+    fn samples_and_splits_the_ball_path() {
+        let mut recording = MatchRecording::default();
+        let phases = [
+            (MatchPhase::Kickoff(Team::Red), 3),
+            (MatchPhase::Playing, 12),
+            (MatchPhase::Kickoff(Team::Blue), 1),
+            (MatchPhase::Playing, 5),
+        ];
+        let mut x = 0.0;
+        for (phase, ticks) in phases {
+            for _ in 0..ticks {
+                let players = [
+                    (player(1, Team::Blue), DVec2::Y),
+                    (player(0, Team::Red), DVec2::X),
+                ];
+                recording.add_tick(phase, Some(DVec2::new(x, 0.0)), players);
+                x += 1.0;
+            }
+        }
+        // ticks 0, 6, 12 then the kickoff at 15 and 18
+        let paths = recording
+            .ball
+            .iter()
+            .map(|path| path.iter().map(|p| p.x).collect());
+        assert_eq!(
+            paths.collect::<Vec<Vec<_>>>(),
+            [vec![0.0, 6.0, 12.0], vec![15.0, 18.0]]
+        );
+        assert_eq!(recording.players[0].team, Team::Red);
+        assert_eq!(recording.player(1).unwrap().positions.len(), 5);
+
+        let json = serde_json::to_string(&recording).unwrap();
+        let loaded: MatchRecording = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.ball, recording.ball);
+        assert_eq!(loaded.interval, SAMPLE_INTERVAL);
+
+        // recordings saved before the seed and the hash were added still load
+        let old: MatchRecording =
+            serde_json::from_str(r#"{ "interval": 6, "ball": [], "players": [] }"#).unwrap();
+        assert_eq!((old.seed, old.hash), (0, 0));
+    }
+
+    #[test]
+    fn heatmap_counts_cells() {
+        let mut heatmap = Heatmap::new(DVec2::new(-100.0, -50.0), DVec2::new(100.0, 50.0), 20.0);
+        assert_eq!((heatmap.columns, heatmap.rows), (10, 5));
+        for position in [DVec2::ZERO, DVec2::new(5.0, 5.0), DVec2::new(-99.0, 49.0)] {
+            heatmap.add(position);
+        }
+        // outside of the grid
+        heatmap.add(DVec2::new(100.0, 0.0));
+
+        assert_eq!(heatmap.max_count(), 2);
+        let cells = heatmap.cells().collect::<Vec<_>>();
+        assert_eq!(
+            cells,
+            [(DVec2::new(10.0, 0.0), 2), (DVec2::new(-90.0, 40.0), 1)]
+        );
+    }
+}
@@ -1,8 +1,11 @@
 use crate::{
     menu::{DataAssets, StadiumAsset},
-    parser::utils::Position,
+    parser::{disc::Radius, stadium::StadiumComp, utils::Position},
+    player::{AvatarText, KickRing, KitStripe, Player, PlayerInput},
+    team_colors::TeamColors,
 };
 use bevy::{prelude::*, render::camera::ScalingMode};
+use bevy_prototype_lyon::prelude::*;
 
 use crate::AppState;
 
@@ -11,10 +14,17 @@ pub struct RendererPlugin;
 impl Plugin for RendererPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(AppState::InGame), (spawn_stadium,))
-            .add_systems(Update, draw_discs.run_if(in_state(AppState::InGame)));
+            .add_systems(OnExit(AppState::InGame), despawn_stadium)
+            .add_systems(
+                Update,
+                (draw_discs, draw_kick_rings, draw_team_colors).run_if(in_state(AppState::InGame)),
+            );
     }
 }
 
+#[derive(Component, Debug, Clone, Copy)]
+pub struct GameCamera;
+
 fn spawn_stadium(
     mut commands: Commands,
     stadium_assets: Res<Assets<StadiumAsset>>,
@@ -23,25 +33,100 @@ fn spawn_stadium(
     let stadium = stadium_assets.get(&data_assets.stadium).unwrap();
     let st = &stadium.0;
 
-    commands.spawn(Camera2dBundle {
-        projection: OrthographicProjection {
-            scale: st.width as f32,
-            scaling_mode: ScalingMode::FixedHorizontal(2.0),
-            ..Default::default()
-        },
-        transform: Transform {
-            scale: Vec3::new(1.0, -1.0, -1.0),
+    commands.spawn((
+        GameCamera,
+        Camera2dBundle {
+            projection: OrthographicProjection {
+                scale: st.width as f32,
+                scaling_mode: ScalingMode::FixedHorizontal(2.0),
+                ..Default::default()
+            },
+            transform: Transform {
+                scale: Vec3::new(1.0, -1.0, -1.0),
+                ..Default::default()
+            },
             ..Default::default()
         },
-        ..Default::default()
-    });
+    ));
 
     st.spawn(&mut commands);
 }
 
+fn despawn_stadium(
+    mut commands: Commands,
+    stadiums: Query<Entity, With<StadiumComp>>,
+    cameras: Query<Entity, With<GameCamera>>,
+) {
+    for entity in stadiums.iter().chain(cameras.iter()) {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.insert_resource(ClearColor::default());
+}
+
 fn draw_discs(mut query: Query<(&mut Transform, &Position)>) {
     for (mut transform, position) in query.iter_mut() {
         transform.translation.x = position.0.x as f32;
         transform.translation.y = position.0.y as f32;
     }
 }
+
+fn draw_kick_rings(
+    players: Query<(&PlayerInput, &Children)>,
+    mut rings: Query<&mut Visibility, With<KickRing>>,
+) {
+    for (input, children) in players.iter() {
+        for &child in children.iter() {
+            if let Ok(mut visibility) = rings.get_mut(child) {
+                *visibility = if input.kick {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                };
+            }
+        }
+    }
+}
+
+fn draw_team_colors(
+    mut commands: Commands,
+    team_colors: Res<TeamColors>,
+    mut players: Query<(Entity, Ref<Player>, &Radius, &Children, &mut Fill)>,
+    stripes: Query<Entity, With<KitStripe>>,
+    mut avatars: Query<&mut Text, With<AvatarText>>,
+) {
+    for (entity, player, radius, children, mut fill) in players.iter_mut() {
+        if !team_colors.is_changed() && !player.is_added() {
+            continue;
+        }
+        let Some(kit) = team_colors.get(player.team) else {
+            continue;
+        };
+
+        *fill = Fill::color(kit.fill_color());
+        for &child in children.iter() {
+            // also removes the stripe from the children of the player
+            if stripes.contains(child) {
+                commands.entity(child).despawn_recursive();
+            }
+            if let Ok(mut text) = avatars.get_mut(child) {
+                for section in text.sections.iter_mut() {
+                    section.style.color = kit.text_color;
+                }
+            }
+        }
+
+        commands.entity(entity).with_children(|parent| {
+            for (color, path) in kit.stripe_paths(radius.0 as f32) {
+                parent.spawn((
+                    KitStripe,
+                    ShapeBundle {
+                        path,
+                        transform: Transform::from_xyz(0.0, 0.0, 0.001),
+                        ..default()
+                    },
+                    Fill::color(color),
+                ));
+            }
+        });
+    }
+}
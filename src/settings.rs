@@ -0,0 +1,428 @@
+use std::path::Path;
+
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    ecs::system::SystemParam,
+    prelude::*,
+    window::{PresentMode, PrimaryWindow, WindowMode},
+};
+use bevy_egui::{egui, EguiContexts, EguiSettings};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    input::{Action, Binding, InputMap, Stick},
+    lobby::{Controller, Lobby},
+};
+
+const RESOLUTIONS: [(f32, f32); 4] = [
+    (1280.0, 720.0),
+    (1600.0, 900.0),
+    (1920.0, 1080.0),
+    (2560.0, 1440.0),
+];
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin);
+        }
+        app.init_resource::<Settings>()
+            .init_resource::<SettingsWindow>()
+            .add_systems(
+                Update,
+                (
+                    apply_settings.run_if(resource_changed::<Settings>()),
+                    settings_window.run_if(|window: Res<SettingsWindow>| window.draft.is_some()),
+                    show_fps.run_if(|settings: Res<Settings>| settings.show_fps),
+                ),
+            );
+    }
+}
+
+// the preferences of the player, saved in the config directory on native
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub ui_scale: f64,
+    pub vsync: bool,
+    pub fullscreen: bool,
+    // logical size of the window when not fullscreen
+    pub resolution: (f32, f32),
+    pub volume: f32,
+    pub show_fps: bool,
+    pub nickname: String,
+    pub avatar: String,
+    pub controls: InputMap,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            ui_scale: 2.0,
+            vsync: true,
+            fullscreen: false,
+            resolution: RESOLUTIONS[0],
+            volume: 1.0,
+            show_fps: false,
+            nickname: "Player".to_string(),
+            avatar: "1".to_string(),
+            controls: InputMap::default(),
+        }
+    }
+}
+
+impl Settings {
+    // the saved settings, or the defaults when there are none yet
+    pub fn load() -> Settings {
+        match config_path() {
+            Some(path) => Settings::load_from(&path),
+            None => Settings::default(),
+        }
+    }
+
+    // the properties missing from the file keep their default value
+    fn load_from(path: &Path) -> Settings {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Settings::default(),
+            Err(e) => {
+                println!("Failed to read {}: {}", path.display(), e);
+                return Settings::default();
+            }
+        };
+        serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            println!("Invalid settings in {}: {}", path.display(), e);
+            Settings::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        // on the web, the settings only last for the session
+        let Some(path) = config_path() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_vec_pretty(self).expect("settings can always be serialized");
+        std::fs::write(&path, json).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        }
+    }
+}
+
+// `settings.json` in the config directory of the platform
+#[cfg(not(target_arch = "wasm32"))]
+fn config_path() -> Option<std::path::PathBuf> {
+    use std::{env::var_os, path::PathBuf};
+
+    let dir = if cfg!(windows) {
+        PathBuf::from(var_os("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(var_os("HOME")?).join("Library/Application Support")
+    } else {
+        var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| Some(PathBuf::from(var_os("HOME")?).join(".config")))?
+    };
+    Some(dir.join("haxbevy").join("settings.json"))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn config_path() -> Option<std::path::PathBuf> {
+    None
+}
+
+// the settings screen edits a copy, applied when saved
+#[derive(Resource, Default)]
+pub struct SettingsWindow {
+    draft: Option<Settings>,
+    // the action waiting for a key or a gamepad button
+    rebinding: Option<Action>,
+    status: Option<String>,
+}
+
+impl SettingsWindow {
+    pub fn open(&mut self, settings: &Settings) {
+        self.draft = Some(settings.clone());
+        self.rebinding = None;
+        self.status = None;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.draft.is_some()
+    }
+}
+
+// what the menus need to open the settings screen
+#[derive(SystemParam)]
+pub struct SettingsOpener<'w> {
+    settings: Res<'w, Settings>,
+    window: ResMut<'w, SettingsWindow>,
+}
+
+impl SettingsOpener<'_> {
+    pub fn open(&mut self) {
+        self.window.open(&self.settings);
+    }
+}
+
+fn apply_settings(
+    settings: Res<Settings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut egui_settings: ResMut<EguiSettings>,
+    mut global_volume: ResMut<GlobalVolume>,
+    mut input_map: ResMut<InputMap>,
+    mut lobby: ResMut<Lobby>,
+) {
+    if let Ok(mut window) = windows.get_single_mut() {
+        window.present_mode = settings.present_mode();
+        window.mode = settings.window_mode();
+        let (width, height) = settings.resolution;
+        if window.resolution.width() != width || window.resolution.height() != height {
+            window.resolution.set(width, height);
+        }
+    }
+    egui_settings.scale_factor = settings.ui_scale;
+    *global_volume = GlobalVolume::new(settings.volume);
+    input_map.set_if_neq(settings.controls.clone());
+
+    // the first human of the lobby is this player
+    let local = lobby
+        .slots
+        .iter_mut()
+        .find(|slot| slot.controller == Controller::Local);
+    if let Some(slot) = local {
+        slot.name = settings.nickname.clone();
+        slot.avatar = settings.avatar.clone();
+    }
+}
+
+fn binding_names(bindings: &[Binding]) -> String {
+    if bindings.is_empty() {
+        return "None".to_string();
+    }
+    let names = bindings.iter().map(Binding::name);
+    names.collect::<Vec<_>>().join(", ")
+}
+
+fn stick_name(stick: Option<Stick>) -> &'static str {
+    match stick {
+        None => "None",
+        Some(Stick::Left) => "Left stick",
+        Some(Stick::Right) => "Right stick",
+    }
+}
+
+fn settings_window(
+    mut contexts: EguiContexts,
+    mut settings: ResMut<Settings>,
+    mut window: ResMut<SettingsWindow>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+) {
+    let SettingsWindow {
+        draft: Some(draft),
+        rebinding,
+        status,
+    } = &mut *window
+    else {
+        return;
+    };
+
+    // the next key or button pressed is added to the action
+    if let Some(action) = *rebinding {
+        let key = keys.get_just_pressed().next().map(|key| Binding::Key(*key));
+        let button = buttons
+            .get_just_pressed()
+            .next()
+            .map(|button| Binding::Button(button.button_type));
+        if let Some(binding) = key.or(button) {
+            draft.controls.bind(action, binding);
+            *rebinding = None;
+        }
+    }
+
+    let mut close = false;
+    egui::Window::new("Settings")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(contexts.ctx_mut(), |ui| {
+            egui::Grid::new("settings").num_columns(2).show(ui, |ui| {
+                ui.label("Nickname");
+                ui.add(egui::TextEdit::singleline(&mut draft.nickname).desired_width(120.0));
+                ui.end_row();
+
+                ui.label("Avatar");
+                let avatar = egui::TextEdit::singleline(&mut draft.avatar)
+                    .char_limit(2)
+                    .desired_width(30.0);
+                ui.add(avatar);
+                ui.end_row();
+
+                ui.label("UI scale");
+                ui.add(egui::Slider::new(&mut draft.ui_scale, 0.5..=3.0).step_by(0.25));
+                ui.end_row();
+
+                ui.label("Volume");
+                ui.add(egui::Slider::new(&mut draft.volume, 0.0..=1.0));
+                ui.end_row();
+
+                ui.label("Resolution");
+                ui.add_enabled_ui(!draft.fullscreen, |ui| {
+                    let (width, height) = draft.resolution;
+                    egui::ComboBox::from_id_source("resolution")
+                        .selected_text(format!("{}x{}", width, height))
+                        .show_ui(ui, |ui| {
+                            for (width, height) in RESOLUTIONS {
+                                let text = format!("{}x{}", width, height);
+                                ui.selectable_value(&mut draft.resolution, (width, height), text);
+                            }
+                        });
+                });
+                ui.end_row();
+
+                ui.label("Display");
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut draft.fullscreen, "Fullscreen");
+                    ui.checkbox(&mut draft.vsync, "VSync");
+                    ui.checkbox(&mut draft.show_fps, "Show FPS");
+                });
+                ui.end_row();
+            });
+
+            ui.separator();
+            ui.label("Controls");
+            egui::Grid::new("controls").num_columns(4).show(ui, |ui| {
+                for action in Action::ALL {
+                    ui.label(format!("{:?}", action));
+                    ui.label(binding_names(draft.controls.bindings(action)));
+                    let text = if *rebinding == Some(action) {
+                        "Press a key or button..."
+                    } else {
+                        "Add"
+                    };
+                    if ui.button(text).clicked() {
+                        *rebinding = Some(action);
+                    }
+                    if ui.button("Clear").clicked() {
+                        draft.controls.clear(action);
+                    }
+                    ui.end_row();
+                }
+
+                // the stick moves in the 8 directions of the keyboard
+                ui.label("Move");
+                egui::ComboBox::from_id_source("stick")
+                    .selected_text(stick_name(draft.controls.stick))
+                    .show_ui(ui, |ui| {
+                        for stick in [None, Some(Stick::Left), Some(Stick::Right)] {
+                            let text = stick_name(stick);
+                            ui.selectable_value(&mut draft.controls.stick, stick, text);
+                        }
+                    });
+                ui.end_row();
+            });
+            if ui.button("Reset controls").clicked() {
+                draft.controls = InputMap::default();
+                *rebinding = None;
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    settings.set_if_neq(draft.clone());
+                    match settings.save() {
+                        Ok(()) => close = true,
+                        // still applied for this session
+                        Err(e) => *status = Some(format!("Failed to save: {}", e)),
+                    }
+                }
+                if ui.button("Cancel").clicked() {
+                    close = true;
+                }
+                if let Some(status) = status {
+                    ui.label(status.as_str());
+                }
+            });
+        });
+
+    if close {
+        window.draft = None;
+    }
+}
+
+fn show_fps(diagnostics: Res<DiagnosticsStore>, mut contexts: EguiContexts) {
+    let mut fps_value = 0.0;
+    if let Some(fps) = diagnostics.get(FrameTimeDiagnosticsPlugin::FPS) {
+        if let Some(value) = fps.smoothed() {
+            fps_value = value;
+        }
+    }
+
+    egui::Window::new("FPS")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::RIGHT_TOP, egui::Vec2::new(-10.0, 10.0))
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(format!("{:.0}", fps_value));
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn settings_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("haxbevy-{}-settings.json", name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn invalid_files_fall_back_to_defaults() {
+        let path = settings_file("invalid", r#"{ "ui_scale": "#);
+        assert_eq!(Settings::load_from(&path), Settings::default());
+
+        let path = settings_file("wrong-type", r#"{ "vsync": "yes" }"#);
+        assert_eq!(Settings::load_from(&path), Settings::default());
+
+        let missing = std::env::temp_dir().join("haxbevy-missing-settings.json");
+        assert_eq!(Settings::load_from(&missing), Settings::default());
+    }
+
+    #[test]
+    fn partial_files_keep_defaults() {
+        let path = settings_file("partial", r#"{ "nickname": "Bob", "volume": 0.5 }"#);
+        let settings = Settings::load_from(&path);
+        assert_eq!(settings.nickname, "Bob");
+        assert_eq!(settings.volume, 0.5);
+        assert_eq!(
+            Settings {
+                nickname: "Player".to_string(),
+                volume: 1.0,
+                ..settings
+            },
+            Settings::default()
+        );
+    }
+}
@@ -0,0 +1,301 @@
+use std::{f32::consts::TAU, sync::Arc, time::Duration};
+
+use bevy::{
+    audio::{AddAudioSource, AudioSink, AudioSinkPlayback, Decodable, Source, Volume},
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+};
+
+use crate::{
+    game::{GoalEvent, MatchPhase, MatchState},
+    physics::{BallBounceEvent, BallTouchEvent},
+    rng::Rng,
+    AppState, PauseState,
+};
+
+const SAMPLE_RATE: u32 = 44_100;
+// bounces softer than that are the ball rolling along a wall
+const MIN_BOUNCE_IMPULSE: f64 = 0.5;
+// bounces that hard or harder are played at full volume
+const LOUD_BOUNCE_IMPULSE: f64 = 6.0;
+const CROWD_VOLUME: f32 = 0.25;
+
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<Synth>()
+            .add_systems(Startup, setup_sounds)
+            .add_systems(OnEnter(AppState::InGame), start_crowd)
+            .add_systems(OnExit(AppState::InGame), stop_crowd)
+            .add_systems(OnEnter(PauseState::Paused), pause_crowd)
+            .add_systems(OnEnter(PauseState::Running), resume_crowd)
+            .add_systems(
+                Update,
+                (
+                    play_match_sounds.run_if(
+                        in_state(AppState::InGame)
+                            .and_then(resource_exists::<MatchState>())
+                            .and_then(resource_exists::<LastPhase>()),
+                    ),
+                    update_crowd_volume.run_if(resource_changed::<GlobalVolume>()),
+                ),
+            );
+    }
+}
+
+// a sound generated by the game, there are no sound files to load
+#[derive(Debug, Clone, TypeUuid, TypePath)]
+#[uuid = "5b1d7e0a-8f3c-4d8e-9a61-2c7f4e9b3d10"]
+pub struct Synth {
+    samples: Arc<[f32]>,
+}
+
+pub struct SynthDecoder {
+    samples: Arc<[f32]>,
+    index: usize,
+}
+
+impl Iterator for SynthDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.samples.get(self.index).copied();
+        self.index += 1;
+        sample
+    }
+}
+
+impl Source for SynthDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some(self.samples.len().saturating_sub(self.index))
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(
+            self.samples.len() as f32 / SAMPLE_RATE as f32,
+        ))
+    }
+}
+
+impl Decodable for Synth {
+    type DecoderItem = f32;
+    type Decoder = SynthDecoder;
+
+    fn decoder(&self) -> SynthDecoder {
+        SynthDecoder {
+            samples: self.samples.clone(),
+            index: 0,
+        }
+    }
+}
+
+impl Synth {
+    // `sample` gives the signal at a time in seconds
+    fn new(duration: f32, mut sample: impl FnMut(f32) -> f32) -> Synth {
+        let count = (duration * SAMPLE_RATE as f32) as usize;
+        let samples = (0..count)
+            .map(|i| sample(i as f32 / SAMPLE_RATE as f32).clamp(-1.0, 1.0))
+            .collect();
+        Synth { samples }
+    }
+
+    // a thump dropping from 180 to 60 Hz with a click
+    fn kick() -> Synth {
+        let mut noise = Noise::new(1);
+        let mut phase = 0.0;
+        Synth::new(0.1, |t| {
+            phase += (60.0 + 120.0 * (-t * 30.0).exp()) / SAMPLE_RATE as f32;
+            (TAU * phase).sin() * (-t * 35.0).exp() + 0.4 * noise.next() * (-t * 200.0).exp()
+        })
+    }
+
+    fn bounce() -> Synth {
+        let mut noise = Noise::new(2);
+        Synth::new(0.08, |t| {
+            0.7 * (TAU * 420.0 * t).sin() * (-t * 60.0).exp()
+                + 0.3 * noise.next() * (-t * 300.0).exp()
+        })
+    }
+
+    // a distorted Bb major chord
+    fn horn() -> Synth {
+        let duration = 1.6;
+        Synth::new(duration, |t| {
+            let chord = [233.1, 293.7, 349.2]
+                .iter()
+                .map(|frequency| (3.0 * (TAU * frequency * t).sin()).tanh())
+                .sum::<f32>();
+            0.25 * chord * envelope(t, duration, 0.05, 0.3)
+        })
+    }
+
+    // a 2.8 kHz trill for each (start, duration) blow
+    fn whistle(blows: &[(f32, f32)]) -> Synth {
+        let mut noise = Noise::new(3);
+        let duration = blows
+            .iter()
+            .map(|(start, length)| start + length)
+            .fold(0.0, f32::max);
+        let mut phase = 0.0;
+        Synth::new(duration, |t| {
+            phase += (2800.0 + 120.0 * (TAU * 35.0 * t).sin()) / SAMPLE_RATE as f32;
+            let breath = noise.next();
+            let level = blows
+                .iter()
+                .map(|&(start, length)| envelope(t - start, length, 0.02, 0.05))
+                .sum::<f32>();
+            level * (0.4 * (TAU * phase).sin() + 0.05 * breath)
+        })
+    }
+
+    // low-passed noise swelling twice per loop, so that it loops without a seam
+    fn crowd() -> Synth {
+        let duration = 4.0;
+        let mut noise = Noise::new(4);
+        let mut low_pass = 0.0;
+        Synth::new(duration, |t| {
+            low_pass += 0.04 * (noise.next() - low_pass);
+            let swell = 0.75 + 0.25 * (TAU * 2.0 * t / duration).sin();
+            3.0 * low_pass * swell
+        })
+    }
+}
+
+// rises in `attack` seconds and fades in `release` seconds, 0 outside of `0..duration`
+fn envelope(t: f32, duration: f32, attack: f32, release: f32) -> f32 {
+    if !(0.0..duration).contains(&t) {
+        return 0.0;
+    }
+    (t / attack).min(1.0) * ((duration - t) / release).min(1.0)
+}
+
+// white noise between -1 and 1, the same every time
+struct Noise(Rng);
+
+impl Noise {
+    fn new(seed: u64) -> Noise {
+        Noise(Rng::new(seed))
+    }
+
+    fn next(&mut self) -> f32 {
+        self.0.next_f64() as f32 * 2.0 - 1.0
+    }
+}
+
+#[derive(Resource)]
+struct Sounds {
+    kick: Handle<Synth>,
+    bounce: Handle<Synth>,
+    horn: Handle<Synth>,
+    whistle: Handle<Synth>,
+    final_whistle: Handle<Synth>,
+    crowd: Handle<Synth>,
+}
+
+#[derive(Component)]
+struct Crowd;
+
+// the phase heard last, a new match starts without one so that its kickoff is whistled
+#[derive(Resource, Default)]
+struct LastPhase(Option<MatchPhase>);
+
+fn setup_sounds(mut commands: Commands, mut synths: ResMut<Assets<Synth>>) {
+    commands.insert_resource(Sounds {
+        kick: synths.add(Synth::kick()),
+        bounce: synths.add(Synth::bounce()),
+        horn: synths.add(Synth::horn()),
+        whistle: synths.add(Synth::whistle(&[(0.0, 0.35)])),
+        final_whistle: synths.add(Synth::whistle(&[(0.0, 0.25), (0.35, 0.25), (0.7, 0.9)])),
+        crowd: synths.add(Synth::crowd()),
+    });
+}
+
+fn play(commands: &mut Commands, sound: &Handle<Synth>, volume: f32) {
+    commands.spawn(AudioSourceBundle {
+        source: sound.clone(),
+        settings: PlaybackSettings::DESPAWN.with_volume(Volume::new_relative(volume)),
+    });
+}
+
+fn play_match_sounds(
+    mut commands: Commands,
+    sounds: Res<Sounds>,
+    match_state: Res<MatchState>,
+    mut touches: EventReader<BallTouchEvent>,
+    mut bounces: EventReader<BallBounceEvent>,
+    mut goals: EventReader<GoalEvent>,
+    mut last_phase: ResMut<LastPhase>,
+) {
+    // several ticks can run in a frame, one sound of each kind is enough
+    if touches.iter().any(|touch| touch.kick) {
+        play(&mut commands, &sounds.kick, 1.0);
+    }
+    let impulse = bounces
+        .iter()
+        .map(|bounce| bounce.impulse)
+        .fold(0.0, f64::max);
+    if impulse >= MIN_BOUNCE_IMPULSE {
+        let volume = (impulse / LOUD_BOUNCE_IMPULSE).min(1.0) as f32;
+        play(&mut commands, &sounds.bounce, volume);
+    }
+    if goals.iter().count() > 0 {
+        play(&mut commands, &sounds.horn, 1.0);
+    }
+
+    let phase = match_state.phase;
+    let was_kickoff = matches!(last_phase.0, Some(MatchPhase::Kickoff(_)));
+    match phase {
+        MatchPhase::Kickoff(_) if !was_kickoff => play(&mut commands, &sounds.whistle, 1.0),
+        MatchPhase::Ended if last_phase.0 != Some(MatchPhase::Ended) => {
+            play(&mut commands, &sounds.final_whistle, 1.0)
+        }
+        _ => {}
+    }
+    last_phase.0 = Some(phase);
+}
+
+fn start_crowd(mut commands: Commands, sounds: Res<Sounds>) {
+    commands.insert_resource(LastPhase::default());
+    commands.spawn((
+        AudioSourceBundle {
+            source: sounds.crowd.clone(),
+            settings: PlaybackSettings::LOOP.with_volume(Volume::new_relative(CROWD_VOLUME)),
+        },
+        Crowd,
+    ));
+}
+
+fn stop_crowd(mut commands: Commands, crowd: Query<Entity, With<Crowd>>) {
+    commands.remove_resource::<LastPhase>();
+    for entity in crowd.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn pause_crowd(crowd: Query<&AudioSink, With<Crowd>>) {
+    for sink in crowd.iter() {
+        sink.pause();
+    }
+}
+
+fn resume_crowd(crowd: Query<&AudioSink, With<Crowd>>) {
+    for sink in crowd.iter() {
+        sink.play();
+    }
+}
+
+// the global volume is only applied when a sound starts, the crowd is already playing
+fn update_crowd_volume(global_volume: Res<GlobalVolume>, crowd: Query<&AudioSink, With<Crowd>>) {
+    for sink in crowd.iter() {
+        sink.set_volume(CROWD_VOLUME * global_volume.volume.get());
+    }
+}
@@ -0,0 +1,364 @@
+use bevy::{math::DVec2, prelude::*};
+use serde::Serialize;
+
+use crate::{
+    game::{GoalEvent, MatchPhase, MatchSet, MatchState, RestartMatch},
+    parser::{goal::GoalComp, utils::Team},
+    physics::BallTouchEvent,
+    player::Player,
+    AppState, PauseState,
+};
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::InGame), setup_stats)
+            .add_systems(OnExit(AppState::InGame), cleanup_stats)
+            .add_systems(Update, restart_stats.run_if(in_state(AppState::InGame)))
+            .add_systems(
+                FixedUpdate,
+                update_stats
+                    .after(MatchSet)
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(in_state(PauseState::Running)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerStats {
+    pub id: usize,
+    pub name: String,
+    pub team: Team,
+    pub goals: u32,
+    pub assists: u32,
+    pub own_goals: u32,
+    pub kicks: u32,
+    pub shots_on_target: u32,
+    pub passes: u32,
+    pub passes_completed: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TeamStats {
+    pub goals: u32,
+    pub shots_on_target: u32,
+    pub passes: u32,
+    pub passes_completed: u32,
+    // ticks of play with the team the last to touch the ball
+    pub possession_ticks: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GoalRecord {
+    // match ticks, like the clock
+    pub tick: u32,
+    // the team given the goal
+    pub team: Team,
+    // the last player to touch the ball, from the other team for an own goal
+    pub scorer: Option<usize>,
+    // the teammate who touched the ball before the scorer
+    pub assist: Option<usize>,
+    pub own_goal: bool,
+}
+
+// the statistics of the current match, built from the touch and goal events
+#[derive(Resource, Debug, Clone, Default, Serialize)]
+pub struct MatchStats {
+    // sorted by id
+    pub players: Vec<PlayerStats>,
+    pub red: TeamStats,
+    pub blue: TeamStats,
+    pub goals: Vec<GoalRecord>,
+    // the last two different players to touch the ball, the most recent last
+    #[serde(skip)]
+    last_touches: Vec<usize>,
+    #[serde(skip)]
+    possession: Option<Team>,
+    // the player who kicked the ball, until someone else touches it
+    #[serde(skip)]
+    pending_pass: Option<usize>,
+}
+
+impl MatchStats {
+    pub fn player(&self, id: usize) -> Option<&PlayerStats> {
+        self.players.iter().find(|player| player.id == id)
+    }
+
+    fn player_mut(&mut self, id: usize) -> Option<&mut PlayerStats> {
+        self.players.iter_mut().find(|player| player.id == id)
+    }
+
+    pub fn team(&self, team: Team) -> Option<&TeamStats> {
+        match team {
+            Team::Red => Some(&self.red),
+            Team::Blue => Some(&self.blue),
+            Team::Spectator => None,
+        }
+    }
+
+    fn team_mut(&mut self, team: Team) -> Option<&mut TeamStats> {
+        match team {
+            Team::Red => Some(&mut self.red),
+            Team::Blue => Some(&mut self.blue),
+            Team::Spectator => None,
+        }
+    }
+
+    // share of the possession in percent, 50 before anyone touched the ball
+    pub fn possession(&self, team: Team) -> f64 {
+        let total = self.red.possession_ticks + self.blue.possession_ticks;
+        match self.team(team) {
+            Some(_) if total == 0 => 50.0,
+            Some(stats) => stats.possession_ticks as f64 * 100.0 / total as f64,
+            None => 0.0,
+        }
+    }
+
+    pub fn add_player(&mut self, player: &Player) {
+        if self.player(player.id).is_some() {
+            return;
+        }
+        self.players.push(PlayerStats {
+            id: player.id,
+            name: player.name.clone(),
+            team: player.team,
+            goals: 0,
+            assists: 0,
+            own_goals: 0,
+            kicks: 0,
+            shots_on_target: 0,
+            passes: 0,
+            passes_completed: 0,
+        });
+        self.players.sort_by_key(|player| player.id);
+    }
+
+    // `goals` are the goals of the stadium, to find the shots on target
+    pub fn add_touch(&mut self, touch: &BallTouchEvent, goals: &[GoalComp]) {
+        // a pass ends when another player touches the ball
+        if let Some(passer) = self.pending_pass.filter(|&passer| passer != touch.player) {
+            self.pending_pass = None;
+            let passer_team = self.player(passer).map(|player| player.team);
+            let completed = passer_team == Some(touch.team);
+            if let Some(stats) = self.player_mut(passer) {
+                stats.passes += 1;
+                stats.passes_completed += u32::from(completed);
+            }
+            if let Some(stats) = passer_team.and_then(|team| self.team_mut(team)) {
+                stats.passes += 1;
+                stats.passes_completed += u32::from(completed);
+            }
+        }
+
+        if touch.kick {
+            self.pending_pass = Some(touch.player);
+            let on_target = goals
+                .iter()
+                .filter(|goal| goal.team == touch.team.opponent())
+                .any(|goal| ray_hits_segment(touch.position, touch.velocity, goal.p0, goal.p1));
+            if let Some(stats) = self.player_mut(touch.player) {
+                stats.kicks += 1;
+                stats.shots_on_target += u32::from(on_target);
+            }
+            if let Some(stats) = self.team_mut(touch.team) {
+                stats.shots_on_target += u32::from(on_target);
+            }
+        }
+
+        if self.last_touches.last() != Some(&touch.player) {
+            self.last_touches.push(touch.player);
+            if self.last_touches.len() > 2 {
+                self.last_touches.remove(0);
+            }
+        }
+        self.possession = Some(touch.team);
+    }
+
+    // `team` is the team given the goal
+    pub fn add_goal(&mut self, team: Team, tick: u32) {
+        let scorer = self.last_touches.last().copied();
+        let scorer_team = scorer
+            .and_then(|id| self.player(id))
+            .map(|player| player.team);
+        let own_goal = scorer_team.is_some_and(|scorer_team| scorer_team != team);
+        let assist = match self.last_touches.as_slice() {
+            [assist, _] if !own_goal && self.player(*assist).map(|p| p.team) == Some(team) => {
+                Some(*assist)
+            }
+            _ => None,
+        };
+
+        if let Some(stats) = scorer.and_then(|id| self.player_mut(id)) {
+            if own_goal {
+                stats.own_goals += 1;
+            } else {
+                stats.goals += 1;
+            }
+        }
+        if let Some(stats) = assist.and_then(|id| self.player_mut(id)) {
+            stats.assists += 1;
+        }
+        if let Some(stats) = self.team_mut(team) {
+            stats.goals += 1;
+        }
+        self.goals.push(GoalRecord {
+            tick,
+            team,
+            scorer,
+            assist,
+            own_goal,
+        });
+
+        // the ball goes back to the center
+        self.last_touches.clear();
+        self.pending_pass = None;
+        self.possession = None;
+    }
+
+    // one tick of play
+    pub fn add_tick(&mut self) {
+        if let Some(stats) = self.possession.and_then(|team| self.team_mut(team)) {
+            stats.possession_ticks += 1;
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut json = serde_json::to_value(self).expect("stats can always be serialized");
+        for (key, team) in [("red", Team::Red), ("blue", Team::Blue)] {
+            json[key]["possession"] = self.possession(team).into();
+        }
+        json
+    }
+}
+
+// the ball moving from `origin` along `direction` crosses the segment
+fn ray_hits_segment(origin: DVec2, direction: DVec2, p0: DVec2, p1: DVec2) -> bool {
+    let segment = p1 - p0;
+    let denominator = direction.perp_dot(segment);
+    if denominator == 0.0 {
+        return false;
+    }
+    let to_segment = p0 - origin;
+    let t = to_segment.perp_dot(segment) / denominator;
+    let s = to_segment.perp_dot(direction) / denominator;
+    t >= 0.0 && (0.0..=1.0).contains(&s)
+}
+
+fn setup_stats(mut commands: Commands) {
+    commands.insert_resource(MatchStats::default());
+}
+
+// the respawned players are added again by update_stats
+fn restart_stats(mut commands: Commands, mut restarts: EventReader<RestartMatch>) {
+    if !restarts.is_empty() {
+        restarts.clear();
+        commands.insert_resource(MatchStats::default());
+    }
+}
+
+fn cleanup_stats(mut commands: Commands) {
+    commands.remove_resource::<MatchStats>();
+}
+
+fn update_stats(
+    mut stats: ResMut<MatchStats>,
+    match_state: Res<MatchState>,
+    mut touches: EventReader<BallTouchEvent>,
+    mut goal_events: EventReader<GoalEvent>,
+    players: Query<&Player, Added<Player>>,
+    goals: Query<&GoalComp>,
+) {
+    for player in players.iter() {
+        stats.add_player(player);
+    }
+    let goals = goals.iter().copied().collect::<Vec<_>>();
+    for touch in touches.iter() {
+        stats.add_touch(touch, &goals);
+    }
+    for goal in goal_events.iter() {
+        stats.add_goal(goal.team, match_state.ticks);
+    }
+    if match_state.phase == MatchPhase::Playing {
+        stats.add_tick();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(id: usize, team: Team) -> Player {
+        Player {
+            id,
+            name: format!("P{}", id),
+            avatar: String::new(),
+            team,
+        }
+    }
+
+    fn touch(player: usize, team: Team, kick: bool, velocity: DVec2) -> BallTouchEvent {
+        BallTouchEvent {
+            player,
+            team,
+            kick,
+            position: DVec2::ZERO,
+            velocity,
+        }
+    }
+
+    fn stats() -> MatchStats {
+        let mut stats = MatchStats::default();
+        stats.add_player(&player(0, Team::Red));
+        stats.add_player(&player(1, Team::Red));
+        stats.add_player(&player(2, Team::Blue));
+        stats
+    }
+
+    #[test]
+    fn goals_assists_and_passes() {
+        let goals = [GoalComp {
+            p0: DVec2::new(100.0, -10.0),
+            p1: DVec2::new(100.0, 10.0),
+            team: Team::Blue,
+        }];
+        let mut stats = stats();
+        // 0 passes to 1, who shoots on target and scores
+        stats.add_touch(&touch(0, Team::Red, true, DVec2::Y), &goals);
+        stats.add_tick();
+        stats.add_touch(&touch(1, Team::Red, false, DVec2::Y), &goals);
+        stats.add_touch(&touch(1, Team::Red, true, DVec2::X), &goals);
+        stats.add_goal(Team::Red, 60);
+
+        let (passer, scorer) = (stats.player(0).unwrap(), stats.player(1).unwrap());
+        assert_eq!(
+            (passer.passes, passer.passes_completed, passer.assists),
+            (1, 1, 1)
+        );
+        assert_eq!(
+            (scorer.goals, scorer.shots_on_target, scorer.kicks),
+            (1, 1, 1)
+        );
+        assert_eq!(stats.red.goals, 1);
+        assert_eq!(stats.possession(Team::Red), 100.0);
+
+        // 2 intercepts a pass of 0 then puts the ball in its own goal
+        stats.add_touch(&touch(0, Team::Red, true, DVec2::Y), &goals);
+        stats.add_touch(&touch(2, Team::Blue, false, DVec2::Y), &goals);
+        stats.add_tick();
+        stats.add_goal(Team::Red, 120);
+
+        let passer = stats.player(0).unwrap();
+        assert_eq!((passer.passes, passer.passes_completed), (2, 1));
+        assert_eq!(stats.player(2).unwrap().own_goals, 1);
+        let goal = stats.goals.last().unwrap();
+        assert!(goal.own_goal && goal.scorer == Some(2) && goal.assist.is_none());
+        assert_eq!(stats.possession(Team::Blue), 50.0);
+
+        let json = stats.to_json();
+        assert_eq!(json["red"]["goals"], 2);
+        assert_eq!(json["goals"][1]["team"], "red");
+        assert_eq!(json["players"][2]["own_goals"], 1);
+    }
+}
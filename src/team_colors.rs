@@ -0,0 +1,323 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{
+    parser::utils::{parse_hex_color, Team},
+    AppState,
+};
+
+const MAX_STRIPES: usize = 3;
+const STRIPE_ARC_STEPS: usize = 16;
+
+pub struct TeamColorsPlugin;
+
+impl Plugin for TeamColorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TeamColors>()
+            .init_resource::<TeamColorsUi>()
+            .add_systems(Update, team_colors_ui.run_if(in_state(AppState::InGame)));
+    }
+}
+
+// the kit of a team, as set by the HaxBall command
+// `/colors <team> <angle> <text color> <stripe 1> [stripe 2] [stripe 3]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TeamKit {
+    // angle of the stripes in degrees, counter-clockwise
+    pub angle: f32,
+    pub text_color: Color,
+    pub stripes: Vec<Color>,
+}
+
+impl TeamKit {
+    pub fn new(stripe: Color) -> TeamKit {
+        TeamKit {
+            angle: 0.0,
+            text_color: Color::WHITE,
+            stripes: vec![stripe],
+        }
+    }
+
+    pub fn fill_color(&self) -> Color {
+        self.stripes.first().copied().unwrap_or(Color::WHITE)
+    }
+
+    // split a disc of the given radius into equal-width stripes perpendicular to the kit angle
+    pub fn stripe_paths(&self, radius: f32) -> Vec<(Color, Path)> {
+        let count = self.stripes.len();
+        if count < 2 {
+            return vec![];
+        }
+
+        // the camera is flipped vertically, so the angle is negated to stay counter-clockwise
+        let angle = -self.angle * PI / 180.0;
+        let dir = Vec2::new(angle.cos(), angle.sin());
+        let perp = Vec2::new(-dir.y, dir.x);
+        let to_world = |phi: f32| (dir * phi.cos() + perp * phi.sin()) * radius;
+
+        self.stripes
+            .iter()
+            .enumerate()
+            .map(|(i, color)| {
+                let start = -1.0 + 2.0 * i as f32 / count as f32;
+                let end = -1.0 + 2.0 * (i + 1) as f32 / count as f32;
+                let (phi_start, phi_end) = (start.acos(), end.acos());
+
+                let mut points = Vec::with_capacity(2 * (STRIPE_ARC_STEPS + 1));
+                for step in 0..=STRIPE_ARC_STEPS {
+                    let t = step as f32 / STRIPE_ARC_STEPS as f32;
+                    points.push(to_world(phi_end + (phi_start - phi_end) * t));
+                }
+                for step in 0..=STRIPE_ARC_STEPS {
+                    let t = step as f32 / STRIPE_ARC_STEPS as f32;
+                    points.push(to_world(-(phi_start + (phi_end - phi_start) * t)));
+                }
+
+                let path = GeometryBuilder::build_as(&shapes::Polygon {
+                    points,
+                    closed: true,
+                });
+                (*color, path)
+            })
+            .collect()
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct TeamColors {
+    pub red: TeamKit,
+    pub blue: TeamKit,
+}
+
+impl Default for TeamColors {
+    fn default() -> Self {
+        TeamColors {
+            red: TeamKit::new(Color::rgb_u8(0xE5, 0x6E, 0x56)),
+            blue: TeamKit::new(Color::rgb_u8(0x56, 0x89, 0xE5)),
+        }
+    }
+}
+
+impl TeamColors {
+    pub fn get(&self, team: Team) -> Option<&TeamKit> {
+        match team {
+            Team::Red => Some(&self.red),
+            Team::Blue => Some(&self.blue),
+            Team::Spectator => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, team: Team) -> Option<&mut TeamKit> {
+        match team {
+            Team::Red => Some(&mut self.red),
+            Team::Blue => Some(&mut self.blue),
+            Team::Spectator => None,
+        }
+    }
+
+    // apply a `/colors` command, the leading "/colors" being optional
+    pub fn apply_command(&mut self, command: &str) -> Result<(), String> {
+        let mut args = command.split_whitespace().peekable();
+        if args.peek() == Some(&"/colors") {
+            args.next();
+        }
+
+        let team = match args.next() {
+            Some("red") => Team::Red,
+            Some("blue") => Team::Blue,
+            Some(t) => return Err(format!("Unknown team \"{}\"", t)),
+            None => return Err("Usage: /colors <red|blue> <angle> <text> <c1> [c2] [c3]".into()),
+        };
+
+        let kit = match args.next() {
+            Some("clear") => match team {
+                Team::Red => TeamColors::default().red,
+                _ => TeamColors::default().blue,
+            },
+            Some(angle) => {
+                let angle = angle
+                    .parse::<f32>()
+                    .map_err(|_| format!("Invalid angle \"{}\"", angle))?;
+                let text_color = args
+                    .next()
+                    .ok_or("Missing text color")
+                    .and_then(|c| parse_hex_color(c).ok_or("Invalid text color"))?;
+                let stripes = args
+                    .map(|c| parse_hex_color(c).ok_or(format!("Invalid stripe color \"{}\"", c)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if stripes.is_empty() || stripes.len() > MAX_STRIPES {
+                    return Err(format!("Expected 1 to {} stripe colors", MAX_STRIPES));
+                }
+                TeamKit {
+                    angle,
+                    text_color,
+                    stripes,
+                }
+            }
+            None => return Err("Missing angle".into()),
+        };
+
+        *self.get_mut(team).unwrap() = kit;
+        Ok(())
+    }
+}
+
+#[derive(Resource, Default)]
+struct TeamColorsUi {
+    command: String,
+    error: Option<String>,
+}
+
+fn color_edit(ui: &mut egui::Ui, color: &mut Color) -> bool {
+    let [r, g, b, _] = color.as_rgba_u8();
+    let mut srgb = [r, g, b];
+    let changed = ui.color_edit_button_srgb(&mut srgb).changed();
+    if changed {
+        *color = Color::rgb_u8(srgb[0], srgb[1], srgb[2]);
+    }
+    changed
+}
+
+fn kit_ui(ui: &mut egui::Ui, kit: &mut TeamKit) -> bool {
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        ui.label("Angle");
+        changed |= ui
+            .add(egui::Slider::new(&mut kit.angle, 0.0..=180.0))
+            .changed();
+    });
+    ui.horizontal(|ui| {
+        ui.label("Text");
+        changed |= color_edit(ui, &mut kit.text_color);
+    });
+    ui.horizontal(|ui| {
+        ui.label("Stripes");
+        for stripe in kit.stripes.iter_mut() {
+            changed |= color_edit(ui, stripe);
+        }
+        if kit.stripes.len() < MAX_STRIPES && ui.small_button("+").clicked() {
+            kit.stripes.push(kit.fill_color());
+            changed = true;
+        }
+        if kit.stripes.len() > 1 && ui.small_button("-").clicked() {
+            kit.stripes.pop();
+            changed = true;
+        }
+    });
+
+    changed
+}
+
+fn team_colors_ui(
+    mut contexts: EguiContexts,
+    mut team_colors: ResMut<TeamColors>,
+    mut ui_state: ResMut<TeamColorsUi>,
+) {
+    // edit a copy so that the resource is only marked as changed on actual edits
+    let mut colors = team_colors.clone();
+    let mut changed = false;
+
+    egui::Window::new("Team colors")
+        .default_open(false)
+        .resizable(false)
+        .anchor(egui::Align2::LEFT_BOTTOM, egui::Vec2::new(10.0, -10.0))
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Red");
+            changed |= kit_ui(ui, &mut colors.red);
+            ui.separator();
+            ui.label("Blue");
+            changed |= kit_ui(ui, &mut colors.blue);
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut ui_state.command);
+                if ui.button("Apply").clicked() {
+                    let command = ui_state.command.clone();
+                    match colors.apply_command(&command) {
+                        Ok(()) => {
+                            ui_state.error = None;
+                            changed = true;
+                        }
+                        Err(e) => ui_state.error = Some(e),
+                    }
+                }
+            });
+            if let Some(error) = &ui_state.error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+        });
+
+    if changed {
+        *team_colors = colors;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(command: &str) -> Result<TeamColors, String> {
+        let mut colors = TeamColors::default();
+        colors.apply_command(command).map(|()| colors)
+    }
+
+    #[test]
+    fn commands_set_the_kit() {
+        let colors = apply("/colors blue 60 FFFFFF 0000FF 00FF00 FF0000").unwrap();
+        assert_eq!(
+            colors.blue,
+            TeamKit {
+                angle: 60.0,
+                text_color: Color::WHITE,
+                stripes: vec![
+                    Color::rgb_u8(0, 0, 255),
+                    Color::rgb_u8(0, 255, 0),
+                    Color::rgb_u8(255, 0, 0),
+                ],
+            }
+        );
+        assert_eq!(colors.red, TeamColors::default().red);
+
+        // without the command name, as typed in the chat
+        let mut colors = apply("red 0 000000 FFFFFF").unwrap();
+        assert_eq!(colors.red.text_color, Color::BLACK);
+        colors.apply_command("/colors red clear").unwrap();
+        assert_eq!(colors.red, TeamColors::default().red);
+    }
+
+    #[test]
+    fn invalid_commands_are_errors() {
+        assert_eq!(
+            apply("/colors red up FFFFFF FF0000").unwrap_err(),
+            "Invalid angle \"up\""
+        );
+        assert_eq!(apply("/colors red 30").unwrap_err(), "Missing text color");
+        assert_eq!(
+            apply("/colors red 30 FFFFFF").unwrap_err(),
+            "Expected 1 to 3 stripe colors"
+        );
+        assert_eq!(
+            apply("/colors red 30 FFFFFF 111111 222222 333333 444444").unwrap_err(),
+            "Expected 1 to 3 stripe colors"
+        );
+        assert!(apply("/colors green 30 FFFFFF FF0000").is_err());
+        assert!(apply("/colors red 30 FFFFFF FF00").is_err());
+    }
+
+    #[test]
+    fn one_path_per_stripe() {
+        let mut kit = TeamKit::new(Color::RED);
+        // a single stripe is the fill of the disc
+        assert!(kit.stripe_paths(15.0).is_empty());
+        kit.stripes.push(Color::BLUE);
+        assert_eq!(kit.stripe_paths(15.0).len(), 2);
+        kit.stripes.push(Color::GREEN);
+        let paths = kit.stripe_paths(15.0);
+        let colors = paths.iter().map(|(color, _)| *color).collect::<Vec<_>>();
+        assert_eq!(colors, kit.stripes);
+    }
+}
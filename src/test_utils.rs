@@ -0,0 +1,28 @@
+// fixtures shared by the tests of several modules
+
+use std::path::PathBuf;
+
+use crate::parser::stadium::{parse_stadium, Stadium};
+
+// every stadium file shipped with the game
+pub fn stadium_files() -> Vec<PathBuf> {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets/stadiums");
+    let mut files = vec![];
+    for dir in ["base", "custom"] {
+        for entry in std::fs::read_dir(root.join(dir)).unwrap() {
+            files.push(entry.unwrap().path());
+        }
+    }
+    files.sort();
+    assert!(!files.is_empty());
+    files
+}
+
+// the default stadium of the game
+pub fn classic() -> Stadium {
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/assets/stadiums/base/classic.json5"
+    );
+    parse_stadium(&std::fs::read(path).unwrap()).unwrap().0
+}
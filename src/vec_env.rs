@@ -0,0 +1,339 @@
+use std::{
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+use crate::{
+    env::{EnvConfig, Environment, StepInfo},
+    parser::stadium::Stadium,
+    player::PlayerInput,
+};
+
+enum Command {
+    // the environments to reset, by index in the worker
+    Reset(Vec<(usize, Arc<Stadium>, u64)>),
+    // the actions of all the agents of the worker
+    Step(Vec<PlayerInput>),
+}
+
+// the results of the environments of a worker, in the layout of VecEnv
+#[derive(Clone)]
+struct Reply {
+    observations: Vec<f32>,
+    rewards: Vec<f32>,
+    dones: Vec<bool>,
+    infos: Vec<StepInfo>,
+}
+
+struct Worker {
+    // index of the first environment of the worker
+    start: usize,
+    count: usize,
+    commands: Sender<Command>,
+    replies: Receiver<Reply>,
+    handle: Option<JoinHandle<()>>,
+}
+
+// a bevy App is not Send, the environments are created and stay in their thread
+fn run_worker(
+    config: EnvConfig,
+    count: usize,
+    commands: Receiver<Command>,
+    replies: Sender<Reply>,
+) {
+    let mut envs = (0..count)
+        .map(|_| Environment::new(config.clone()))
+        .collect::<Vec<_>>();
+    let agents = envs.first().map_or(0, Environment::agent_count);
+    let observation_size = config.observation.size();
+    let mut reply = Reply {
+        observations: vec![0.0; count * agents * observation_size],
+        rewards: vec![0.0; count * agents],
+        // nothing can be stepped before a reset
+        dones: vec![true; count],
+        infos: vec![StepInfo::default(); count],
+    };
+
+    for command in commands {
+        reply.rewards.fill(0.0);
+        match command {
+            Command::Reset(resets) => {
+                for (index, stadium, seed) in resets {
+                    envs[index].reset(Stadium::clone(&stadium), seed);
+                    reply.dones[index] = false;
+                    reply.infos[index] = StepInfo::default();
+                }
+            }
+            Command::Step(actions) => {
+                for (index, env) in envs.iter_mut().enumerate() {
+                    // finished environments wait for a reset
+                    if reply.dones[index] {
+                        continue;
+                    }
+                    let step = env.step(&actions[index * agents..(index + 1) * agents]);
+                    reply.rewards[index * agents..(index + 1) * agents]
+                        .copy_from_slice(&step.rewards);
+                    reply.dones[index] = step.done;
+                    reply.infos[index] = step.info;
+                }
+            }
+        }
+        let size = agents * observation_size;
+        for (env, out) in envs.iter().zip(reply.observations.chunks_mut(size.max(1))) {
+            env.observe_into(out);
+        }
+
+        if replies.send(reply.clone()).is_err() {
+            return;
+        }
+    }
+}
+
+// many independent environments stepped together on worker threads
+//
+// the buffers are indexed by environment then agent: the observation of agent `a` of
+// environment `e` starts at `(e * agents + a) * observation_size`
+// each environment only depends on its stadium, seed and actions, so the results are the
+// same as running it alone, whatever the number of threads
+pub struct VecEnv {
+    workers: Vec<Worker>,
+    env_count: usize,
+    agents: usize,
+    observation_size: usize,
+    observations: Vec<f32>,
+    rewards: Vec<f32>,
+    dones: Vec<bool>,
+    infos: Vec<StepInfo>,
+}
+
+impl VecEnv {
+    // `threads` is capped by the number of environments, 0 uses every core
+    pub fn new(config: EnvConfig, env_count: usize, threads: usize) -> VecEnv {
+        let agents = Environment::new(config.clone()).agent_count();
+        let observation_size = config.observation.size();
+        let threads = match threads {
+            0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            threads => threads,
+        }
+        .clamp(1, env_count.max(1));
+
+        let mut workers = vec![];
+        let mut start = 0;
+        for thread in 0..threads {
+            // the first threads take the remainder
+            let count = env_count / threads + usize::from(thread < env_count % threads);
+            let (commands, worker_commands) = channel();
+            let (worker_replies, replies) = channel();
+            let config = config.clone();
+            let handle = std::thread::Builder::new()
+                .name(format!("haxbevy-env-{}", thread))
+                .spawn(move || run_worker(config, count, worker_commands, worker_replies))
+                .expect("failed to spawn an environment thread");
+            workers.push(Worker {
+                start,
+                count,
+                commands,
+                replies,
+                handle: Some(handle),
+            });
+            start += count;
+        }
+
+        VecEnv {
+            workers,
+            env_count,
+            agents,
+            observation_size,
+            observations: vec![0.0; env_count * agents * observation_size],
+            rewards: vec![0.0; env_count * agents],
+            dones: vec![true; env_count],
+            infos: vec![StepInfo::default(); env_count],
+        }
+    }
+
+    pub fn env_count(&self) -> usize {
+        self.env_count
+    }
+
+    pub fn agent_count(&self) -> usize {
+        self.agents
+    }
+
+    pub fn observation_size(&self) -> usize {
+        self.observation_size
+    }
+
+    pub fn observations(&self) -> &[f32] {
+        &self.observations
+    }
+
+    pub fn rewards(&self) -> &[f32] {
+        &self.rewards
+    }
+
+    pub fn dones(&self) -> &[bool] {
+        &self.dones
+    }
+
+    pub fn infos(&self) -> &[StepInfo] {
+        &self.infos
+    }
+
+    // reset every environment, `seeds` has one seed per environment
+    pub fn reset(&mut self, stadium: &Stadium, seeds: &[u64]) -> &[f32] {
+        let seeds = seeds.iter().map(|&seed| Some(seed)).collect::<Vec<_>>();
+        self.reset_some(stadium, &seeds)
+    }
+
+    // reset the environments with a seed, usually the ones that are done
+    pub fn reset_some(&mut self, stadium: &Stadium, seeds: &[Option<u64>]) -> &[f32] {
+        assert_eq!(seeds.len(), self.env_count, "one seed per environment");
+        let stadium = Arc::new(stadium.clone());
+        for worker in &self.workers {
+            let resets = seeds[worker.start..worker.start + worker.count]
+                .iter()
+                .enumerate()
+                .filter_map(|(index, seed)| seed.map(|seed| (index, stadium.clone(), seed)))
+                .collect();
+            worker.send(Command::Reset(resets));
+        }
+        self.gather();
+        &self.observations
+    }
+
+    // `actions` has one input per agent of every environment, environments that are done
+    // are not stepped until they are reset
+    pub fn step(&mut self, actions: &[PlayerInput]) {
+        assert_eq!(
+            actions.len(),
+            self.env_count * self.agents,
+            "one action per agent of every environment"
+        );
+        for worker in &self.workers {
+            let range = worker.start * self.agents..(worker.start + worker.count) * self.agents;
+            worker.send(Command::Step(actions[range].to_vec()));
+        }
+        self.gather();
+    }
+
+    // the workers run in parallel, their results are copied in order
+    fn gather(&mut self) {
+        let (agents, size) = (self.agents, self.agents * self.observation_size);
+        for worker in &self.workers {
+            let reply = worker
+                .replies
+                .recv()
+                .expect("an environment thread panicked");
+            let envs = worker.start..worker.start + worker.count;
+            self.observations[envs.start * size..envs.end * size]
+                .copy_from_slice(&reply.observations);
+            self.rewards[envs.start * agents..envs.end * agents].copy_from_slice(&reply.rewards);
+            self.dones[envs.clone()].copy_from_slice(&reply.dones);
+            self.infos[envs].copy_from_slice(&reply.infos);
+        }
+    }
+}
+
+impl Worker {
+    fn send(&self, command: Command) {
+        self.commands
+            .send(command)
+            .expect("an environment thread panicked");
+    }
+}
+
+impl Drop for VecEnv {
+    fn drop(&mut self) {
+        for worker in &mut self.workers {
+            // closing the channel stops the worker
+            let (commands, _) = channel();
+            drop(std::mem::replace(&mut worker.commands, commands));
+            if let Some(handle) = worker.handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::DVec2;
+
+    use super::*;
+    use crate::{
+        lobby::{Controller, Lobby, LobbySlot},
+        parser::utils::Team,
+        test_utils::classic,
+    };
+
+    fn config() -> EnvConfig {
+        EnvConfig {
+            lobby: Lobby {
+                slots: vec![
+                    LobbySlot {
+                        controller: Controller::External,
+                        ..LobbySlot::local("Agent", Team::Red)
+                    },
+                    LobbySlot::bot("Chaser (easy)", Team::Blue),
+                ],
+            },
+            frame_skip: 2,
+            max_ticks: 600,
+            ..EnvConfig::default()
+        }
+    }
+
+    // a different but deterministic action for each environment and step
+    fn action(env: usize, step: usize) -> PlayerInput {
+        let angle = (env * 7 + step / 10) as f64;
+        PlayerInput {
+            direction: DVec2::from_angle(angle),
+            kick: step.is_multiple_of(3),
+        }
+    }
+
+    #[test]
+    fn vectorized_matches_single_runs() {
+        let seeds = [1, 2, 3, 4, 5];
+        let mut vec_env = VecEnv::new(config(), seeds.len(), 2);
+        vec_env.reset(&classic(), &seeds);
+        let mut histories = vec![vec![]; seeds.len()];
+        let mut finished = vec![false; seeds.len()];
+        for step in 0..300 {
+            let actions = (0..seeds.len())
+                .map(|env| action(env, step))
+                .collect::<Vec<_>>();
+            vec_env.step(&actions);
+            for env in 0..seeds.len() {
+                if finished[env] {
+                    continue;
+                }
+                let size = vec_env.observation_size();
+                let observation = vec_env.observations()[env * size..(env + 1) * size].to_vec();
+                histories[env].push((observation, vec_env.rewards()[env], vec_env.infos()[env]));
+                finished[env] = vec_env.dones()[env];
+            }
+        }
+        assert!(finished.iter().all(|&finished| finished));
+
+        for (env, &seed) in seeds.iter().enumerate() {
+            let mut alone = Environment::new(config());
+            alone.reset(classic(), seed);
+            for (step, expected) in histories[env].iter().enumerate() {
+                let result = alone.step(&[action(env, step)]);
+                let actual = (
+                    result.observations[0].clone(),
+                    result.rewards[0],
+                    result.info,
+                );
+                assert_eq!(&actual, expected, "environment {} step {}", env, step);
+                if result.done {
+                    break;
+                }
+            }
+        }
+    }
+}